@@ -0,0 +1,558 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env, fs,
+    ops::Not,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use ratatui::style::{palette::tailwind::SLATE, Color};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    discovery::DiscoveredAPIResource,
+    health::{self, HealthRule},
+    keys::{Command, KeyChord, Keymap, Mode},
+    state::{Tab, TabView},
+    table::SortBy,
+    DynResult,
+};
+
+pub const MIN_REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The validated contents of the configuration file.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Config {
+    /// How long to wait for input before refreshing the displayed resources.
+    pub(crate) refresh_interval: Duration,
+    /// Commands to try, in order, when exec'ing into a container.
+    pub(crate) exec_commands: Vec<String>,
+    /// The tabs to open on launch when there is no saved session.
+    pub(crate) tabs: Vec<TabConfig>,
+    pub(crate) keymap: Keymap,
+    pub(crate) theme: Theme,
+    /// Column preferences, keyed by any of the names of the resource they apply to.
+    pub(crate) columns: BTreeMap<String, ColumnPreference>,
+    /// Rules for coloring rows by health, keyed by any of the names of the resource they apply
+    /// to. Configured rules come before the built-in ones, so they take precedence.
+    pub(crate) row_health: Vec<(String, Vec<HealthRule>)>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            refresh_interval: Duration::from_secs(2),
+            exec_commands: vec!["/bin/bash".into(), "/bin/sh".into()],
+            tabs: vec![],
+            keymap: Keymap::default(),
+            theme: Theme::default(),
+            columns: BTreeMap::new(),
+            row_health: health::default_rules()
+                .into_iter()
+                .map(|(name, rules)| (name.to_string(), rules))
+                .collect(),
+        }
+    }
+}
+
+/// A tab, as written in the configuration file and in session files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct TabConfig {
+    pub(crate) resource: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) title: Option<String>,
+    /// The kubeconfig context to use, the current context if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) context: Option<String>,
+    /// The namespace to show, `default` if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) namespace: Option<String>,
+    /// Show the resource across all namespaces, ignoring `namespace`.
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub(crate) all_namespaces: bool,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub(crate) filter: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) label_selector: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) field_selector: Option<String>,
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub(crate) tree: bool,
+    #[serde(default, skip_serializing_if = "Not::not")]
+    pub(crate) wide: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) sort: Option<SortBy>,
+    /// Overrides the column preference for the resource.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) columns: Option<ColumnPreference>,
+}
+
+impl TabConfig {
+    pub(crate) fn tab(&self) -> Tab {
+        let default = Tab::default();
+        Tab {
+            title: self.title.clone(),
+            context: self.context.clone(),
+            namespace: if self.all_namespaces {
+                None
+            } else {
+                self.namespace.clone().or(default.namespace.clone())
+            },
+            resource: self.resource.clone(),
+            filter: self.filter.clone(),
+            label_selector: self.label_selector.clone(),
+            field_selector: self.field_selector.clone(),
+            view: if self.tree {
+                TabView::Tree
+            } else {
+                TabView::Table
+            },
+            wide: self.wide,
+            sort: self.sort.clone(),
+            columns: self.columns.clone(),
+            ..default
+        }
+    }
+}
+
+impl From<&Tab> for TabConfig {
+    fn from(tab: &Tab) -> Self {
+        Self {
+            resource: tab.resource.clone(),
+            title: tab.title.clone(),
+            context: tab.context.clone(),
+            namespace: tab.namespace.clone(),
+            all_namespaces: tab.namespace.is_none(),
+            filter: tab.filter.clone(),
+            label_selector: tab.label_selector.clone(),
+            field_selector: tab.field_selector.clone(),
+            tree: tab.view == TabView::Tree,
+            wide: tab.wide,
+            sort: tab.sort.clone(),
+            columns: tab.columns.clone(),
+        }
+    }
+}
+
+/// Check the tabs of the configuration file or a session file.
+pub(crate) fn validate_tabs(tabs: &[TabConfig]) -> Vec<String> {
+    tabs.iter()
+        .enumerate()
+        .filter(|(_, tab)| tab.resource.is_empty())
+        .map(|(idx, _)| format!("tabs[{idx}]: resource must not be empty"))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Theme {
+    /// Borders of focused widgets and fields being edited.
+    pub(crate) focused: Color,
+    pub(crate) unfocused: Color,
+    pub(crate) error: Color,
+    pub(crate) warning: Color,
+    pub(crate) tab_highlight: Color,
+    pub(crate) header_background: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            focused: Color::LightCyan,
+            unfocused: Color::White,
+            error: Color::Red,
+            warning: Color::Yellow,
+            tab_highlight: Color::Cyan,
+            header_background: SLATE.c800,
+        }
+    }
+}
+
+/// Which of a resource's table columns to show, by (case-insensitive) column name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ColumnPreference {
+    /// If set, show only these columns, in this order.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) show: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) hide: Vec<String>,
+}
+
+impl ColumnPreference {
+    /// The indices of the columns to display, in display order.
+    pub(crate) fn visible_columns(&self, names: &[String]) -> Vec<usize> {
+        let index_of = |name: &String| names.iter().position(|n| n.eq_ignore_ascii_case(name));
+        let shown: Vec<usize> = if self.show.is_empty() {
+            (0..names.len()).collect()
+        } else {
+            self.show.iter().filter_map(index_of).collect()
+        };
+        let hidden: Vec<usize> = self.hide.iter().filter_map(index_of).collect();
+        shown.into_iter().filter(|i| !hidden.contains(i)).collect()
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+struct RawConfig {
+    refresh_interval: Option<String>,
+    exec_commands: Option<Vec<String>>,
+    tabs: Vec<TabConfig>,
+    /// Chords for commands, by mode and then by command.
+    keybindings: BTreeMap<String, BTreeMap<String, Chords>>,
+    theme: BTreeMap<String, String>,
+    columns: BTreeMap<String, ColumnPreference>,
+    row_health: BTreeMap<String, Vec<HealthRule>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Chords {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Config {
+    /// Load the configuration from `path`, or from the default path if it exists.
+    pub(crate) fn load(path: Option<&Path>) -> DynResult<Self> {
+        let (path, contents) = match path {
+            Some(p) => (p.to_path_buf(), fs::read_to_string(p)?),
+            None => match default_path() {
+                Some(p) if p.exists() => {
+                    let contents = fs::read_to_string(&p)?;
+                    (p, contents)
+                }
+                _ => return Ok(Self::default()),
+            },
+        };
+
+        contents.parse().map_err(|errors: Vec<String>| {
+            format!(
+                "invalid configuration file {}:\n  {}",
+                path.display(),
+                errors.join("\n  ")
+            )
+            .into()
+        })
+    }
+
+    /// The column preference for the resource, if there is one.
+    pub(crate) fn columns_for(
+        &self,
+        resource: &DiscoveredAPIResource,
+    ) -> Option<&ColumnPreference> {
+        self.columns
+            .iter()
+            .find_map(|(name, preference)| names(name, resource).then_some(preference))
+    }
+
+    /// The rules for coloring the resource's rows, in order of precedence.
+    pub(crate) fn health_rules_for<'a>(
+        &'a self,
+        resource: &'a DiscoveredAPIResource,
+    ) -> impl Iterator<Item = &'a HealthRule> {
+        self.row_health
+            .iter()
+            .filter(|(name, _)| names(name, resource))
+            .flat_map(|(_, rules)| rules)
+    }
+}
+
+/// Whether `name` is one of the names of the resource, as in the keys of `columns` and
+/// `row-health`.
+fn names(name: &str, resource: &DiscoveredAPIResource) -> bool {
+    name == resource.plural
+        || name == resource.singular
+        || name.eq_ignore_ascii_case(&resource.kind)
+        || resource.short_names.iter().flatten().any(|s| s == name)
+}
+
+impl FromStr for Config {
+    type Err = Vec<String>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let raw: RawConfig = toml::from_str(s).map_err(|e| vec![e.to_string()])?;
+        let mut config = Config::default();
+        let mut errors = vec![];
+
+        if let Some(interval) = raw.refresh_interval {
+            match parse_duration(&interval) {
+                Ok(d) if d >= MIN_REFRESH_INTERVAL => config.refresh_interval = d,
+                Ok(_) => errors.push(format!(
+                    "refresh-interval must be at least {}ms, not {interval:?}",
+                    MIN_REFRESH_INTERVAL.as_millis()
+                )),
+                Err(e) => errors.push(format!("refresh-interval: {e}")),
+            }
+        }
+
+        if let Some(commands) = raw.exec_commands {
+            if commands.is_empty() {
+                errors.push("exec-commands must not be empty".into());
+            } else {
+                config.exec_commands = commands;
+            }
+        }
+
+        errors.extend(validate_tabs(&raw.tabs));
+        config.tabs = raw.tabs;
+
+        let mut bound: HashMap<(Mode, KeyChord), Command> = HashMap::new();
+        for (mode_name, bindings) in raw.keybindings {
+            let mode = match mode_name.parse::<Mode>() {
+                Ok(m) => m,
+                Err(e) => {
+                    errors.push(format!("keybindings: {e}"));
+                    continue;
+                }
+            };
+            for (name, chords) in bindings {
+                let command = match name.parse::<Command>() {
+                    Ok(c) => c,
+                    Err(e) => {
+                        errors.push(format!("keybindings.{mode_name}: {e}"));
+                        continue;
+                    }
+                };
+                let chords = match chords {
+                    Chords::One(c) => vec![c],
+                    Chords::Many(cs) => cs,
+                };
+                let mut parsed = vec![];
+                for chord in chords {
+                    match chord.parse::<KeyChord>() {
+                        Ok(c) => match bound.insert((mode, c), command) {
+                            Some(other) if other != command => errors.push(format!(
+                                "keybindings.{mode_name}: {chord:?} is bound to both {} and {}",
+                                other.name(),
+                                command.name()
+                            )),
+                            _ => parsed.push(c),
+                        },
+                        Err(e) => errors.push(format!("keybindings.{mode_name}.{name}: {e}")),
+                    }
+                }
+                config.keymap.rebind(mode, command, &parsed);
+            }
+        }
+
+        for (name, value) in raw.theme {
+            let color = match Color::from_str(&value) {
+                Ok(c) => c,
+                Err(_) => {
+                    errors.push(format!("theme.{name}: invalid color {value:?}"));
+                    continue;
+                }
+            };
+            let slot = match name.as_str() {
+                "focused" => &mut config.theme.focused,
+                "unfocused" => &mut config.theme.unfocused,
+                "error" => &mut config.theme.error,
+                "warning" => &mut config.theme.warning,
+                "tab-highlight" => &mut config.theme.tab_highlight,
+                "header-background" => &mut config.theme.header_background,
+                _ => {
+                    errors.push(format!("theme: unknown color {name:?}"));
+                    continue;
+                }
+            };
+            *slot = color;
+        }
+
+        config.columns = raw.columns;
+
+        for (name, rules) in &raw.row_health {
+            for (idx, rule) in rules.iter().enumerate() {
+                if let Err(e) = rule.validate() {
+                    errors.push(format!("row-health.{name}[{idx}]: {e}"));
+                }
+            }
+        }
+        config.row_health.splice(0..0, raw.row_health);
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/flotilla/config.toml`, falling back to `~/.config` if `XDG_CONFIG_HOME` is unset.
+pub(crate) fn default_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|h| h.join(".config")))?;
+    Some(config_home.join("flotilla").join("config.toml"))
+}
+
+/// Parse a duration like `500ms`, `2s`, or `1.5m`.
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .ok_or_else(|| format!("missing unit in duration {s:?} (use ms, s, or m)"))?;
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid number in duration {s:?}"))?;
+    let seconds = match unit.trim() {
+        "ms" => number / 1000.0,
+        "s" => number,
+        "m" => number * 60.0,
+        _ => return Err(format!("invalid unit in duration {s:?} (use ms, s, or m)")),
+    };
+    Duration::try_from_secs_f64(seconds).map_err(|_| format!("duration {s:?} is too long"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{KeyCode, KeyModifiers};
+    use rstest::rstest;
+
+    use super::*;
+    use crate::health::Health;
+
+    #[rstest]
+    #[case("500ms", Duration::from_millis(500))]
+    #[case("2s", Duration::from_secs(2))]
+    #[case("1.5s", Duration::from_millis(1500))]
+    #[case("1m", Duration::from_secs(60))]
+    fn test_parse_duration(#[case] s: &str, #[case] expected: Duration) {
+        assert_eq!(parse_duration(s), Ok(expected));
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("2")]
+    #[case("s")]
+    #[case("2h")]
+    #[case("1e30s")]
+    #[case("1000000000000000000000000000000s")]
+    fn test_parse_invalid_duration(#[case] s: &str) {
+        assert!(parse_duration(s).is_err());
+    }
+
+    #[rstest]
+    fn test_parse_config() {
+        let config: Config = r##"
+            refresh-interval = "5s"
+
+            [[tabs]]
+            resource = "deployments"
+            namespace = "kube-system"
+
+            [[tabs]]
+            resource = "nodes"
+            context = "prod"
+            all-namespaces = true
+            wide = true
+
+            [keybindings.global]
+            quit = ["q", "ctrl-c"]
+            exec = "S"
+
+            [keybindings.tree]
+            expand = "space"
+
+            [theme]
+            focused = "magenta"
+            header-background = "#202020"
+
+            [columns.pods]
+            hide = ["Nominated Node", "Readiness Gates"]
+
+            [[row-health.certificates]]
+            health = "warning"
+            condition = "Ready"
+            condition-status = "False"
+        "##
+        .parse()
+        .unwrap();
+
+        assert_eq!(config.refresh_interval, Duration::from_secs(5));
+        assert_eq!(config.tabs.len(), 2);
+        assert_eq!(config.tabs[1].tab().namespace, None);
+        assert_eq!(config.tabs[1].tab().context.as_deref(), Some("prod"));
+        assert!(config.tabs[1].tab().wide);
+        assert_eq!(
+            config
+                .keymap
+                .get(Mode::Table, &KeyChord::plain(KeyCode::Char('q'))),
+            Some(Command::Quit)
+        );
+        assert_eq!(
+            config.keymap.get(
+                Mode::Global,
+                &KeyChord::new(KeyCode::Char('c'), KeyModifiers::CONTROL)
+            ),
+            Some(Command::Quit)
+        );
+        assert_eq!(
+            config
+                .keymap
+                .get(Mode::Global, &KeyChord::plain(KeyCode::Char('S'))),
+            Some(Command::Exec)
+        );
+        assert_eq!(
+            config
+                .keymap
+                .get(Mode::Tree, &KeyChord::plain(KeyCode::Char(' '))),
+            Some(Command::Expand)
+        );
+        assert_eq!(
+            config
+                .keymap
+                .get(Mode::Tree, &KeyChord::plain(KeyCode::Char('l'))),
+            None
+        );
+        assert_eq!(config.theme.focused, Color::Magenta);
+        assert_eq!(config.theme.header_background, Color::Rgb(0x20, 0x20, 0x20));
+        assert_eq!(config.columns["pods"].hide.len(), 2);
+        assert_eq!(config.row_health[0].0, "certificates");
+        assert_eq!(config.row_health[0].1[0].health, Health::Warning);
+    }
+
+    #[rstest]
+    #[case("refresh-interval = \"10ms\"", "refresh-interval must be at least")]
+    #[case("refresh-interval = \"soon\"", "refresh-interval")]
+    #[case(
+        "[keybindings.global]\nfrobnicate = \"z\"",
+        "unknown command \"frobnicate\""
+    )]
+    #[case("[keybindings.global]\nquit = \"hyper-q\"", "unknown modifier")]
+    #[case("[keybindings.global]\nquit = \"z\"\nexec = \"z\"", "is bound to both")]
+    #[case("[keybindings.sideways]\nquit = \"z\"", "unknown mode \"sideways\"")]
+    #[case("[theme]\nfocused = \"blurple\"", "invalid color \"blurple\"")]
+    #[case("[theme]\nbackground = \"red\"", "unknown color \"background\"")]
+    #[case("[[tabs]]\nresource = \"\"", "resource must not be empty")]
+    #[case("colour = \"red\"", "unknown field `colour`")]
+    #[case(
+        "[[row-health.pods]]\nhealth = \"error\"",
+        "row-health.pods[0]: needs a column or a condition"
+    )]
+    #[case(
+        "[[row-health.pods]]\nhealth = \"error\"\ncolumn = \"Status\"",
+        "a column needs values or partial"
+    )]
+    #[case("[[row-health.pods]]\nhealth = \"purple\"", "unknown variant `purple`")]
+    fn test_parse_invalid_config(#[case] s: &str, #[case] expected: &str) {
+        let errors = s.parse::<Config>().unwrap_err();
+        assert!(
+            errors.iter().any(|e| e.contains(expected)),
+            "{errors:?} does not contain {expected:?}"
+        );
+    }
+
+    #[rstest]
+    #[case(ColumnPreference::default(), vec![0, 1, 2])]
+    #[case(ColumnPreference { show: vec![], hide: vec!["age".into()] }, vec![0, 1])]
+    #[case(ColumnPreference { show: vec!["Age".into(), "Name".into()], hide: vec![] }, vec![2, 0])]
+    #[case(ColumnPreference { show: vec!["Name".into(), "Nope".into()], hide: vec!["Name".into()] }, vec![])]
+    fn test_visible_columns(#[case] preference: ColumnPreference, #[case] expected: Vec<usize>) {
+        let names = vec!["Name".to_string(), "Ready".to_string(), "Age".to_string()];
+        assert_eq!(preference.visible_columns(&names), expected);
+    }
+}
@@ -0,0 +1,380 @@
+use std::path::Path;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use k8s_openapi::chrono::{DateTime, Utc};
+use kube::{
+    api::{DynamicObject, Patch},
+    Client,
+};
+use serde_json::{json, Value};
+use x509_parser::pem::Pem;
+
+use crate::{
+    discovery::DiscoveredAPIResource, state::ObjectRef, ui::format_age, workloads::patch_params,
+    DynResult,
+};
+
+/// The resources whose objects hold data by key, which can be viewed and edited key by key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DataKind {
+    ConfigMap,
+    /// Secrets hold their values base64-encoded, and are masked until revealed.
+    Secret,
+}
+
+impl DataKind {
+    pub(crate) fn of(resource: &DiscoveredAPIResource) -> Option<Self> {
+        match (resource.group.as_str(), resource.plural.as_str()) {
+            ("", "configmaps") => Some(Self::ConfigMap),
+            ("", "secrets") => Some(Self::Secret),
+            _ => None,
+        }
+    }
+}
+
+/// What is shown in place of a value that hasn't been revealed.
+pub(crate) const MASK: &str = "••••••••";
+
+/// A (decoded) value from a config map's or secret's `data`.
+#[derive(Debug)]
+pub(crate) struct DataEntry {
+    pub(crate) key: String,
+    pub(crate) value: Vec<u8>,
+    pub(crate) content: Content,
+    pub(crate) revealed: bool,
+}
+
+/// What a value looks like, which determines how it is displayed.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Content {
+    Text(String),
+    /// JSON, pretty-printed, like a `.dockerconfigjson`.
+    Json(String),
+    /// PEM blocks, like a TLS secret's `tls.crt` and `tls.key`, with any certificates among them.
+    Pem {
+        text: String,
+        certificates: Vec<Certificate>,
+    },
+    Binary,
+}
+
+#[derive(Debug, PartialEq)]
+pub(crate) struct Certificate {
+    pub(crate) subject: String,
+    pub(crate) not_after: DateTime<Utc>,
+}
+
+impl DataEntry {
+    pub(crate) fn new(key: String, value: Vec<u8>) -> Self {
+        let content = Content::of(&value);
+        Self {
+            key,
+            value,
+            content,
+            revealed: false,
+        }
+    }
+
+    /// The values of the object's `data`, by key, decoded if it is a secret.
+    /// Only secrets' values are masked.
+    pub(crate) fn all(kind: DataKind, object: &DynamicObject) -> Vec<Self> {
+        object.data["data"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(key, value)| {
+                let value = value.as_str()?;
+                let mut entry = match kind {
+                    DataKind::ConfigMap => Self::new(key.clone(), value.into()),
+                    DataKind::Secret => Self::new(key.clone(), STANDARD.decode(value).ok()?),
+                };
+                entry.revealed = kind == DataKind::ConfigMap;
+                Some(entry)
+            })
+            .collect()
+    }
+
+    /// The value as text to edit, unless it is binary.
+    pub(crate) fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.value).ok()
+    }
+
+    /// A description of the value that doesn't reveal it, like `1234 bytes, JSON`.
+    pub(crate) fn summary(&self, now: DateTime<Utc>) -> String {
+        let size = format!("{} bytes", self.value.len());
+        match &self.content {
+            Content::Text(_) => size,
+            Content::Json(_) => format!("{size}, JSON"),
+            Content::Binary => format!("{size}, binary"),
+            Content::Pem { certificates, .. } => match certificates.as_slice() {
+                [] => format!("{size}, PEM"),
+                [certificate] => format!("{size}, {}", certificate.describe(now)),
+                certificates => format!(
+                    "{size}, {} certificates, first {}",
+                    certificates.len(),
+                    certificates[0].describe(now)
+                ),
+            },
+        }
+    }
+
+    /// When the earliest certificate in the value expires, if it has any.
+    pub(crate) fn expires(&self) -> Option<DateTime<Utc>> {
+        match &self.content {
+            Content::Pem { certificates, .. } => certificates.iter().map(|c| c.not_after).min(),
+            _ => None,
+        }
+    }
+
+    /// The lines to display for the value: the value itself if it is revealed, otherwise a mask.
+    pub(crate) fn lines(&self) -> Vec<&str> {
+        match &self.content {
+            _ if !self.revealed => vec![MASK],
+            Content::Text(text) | Content::Json(text) | Content::Pem { text, .. } => {
+                text.lines().collect()
+            }
+            Content::Binary => vec!["(binary data, copy it to see it)"],
+        }
+    }
+}
+
+impl Content {
+    fn of(value: &[u8]) -> Self {
+        let Ok(text) = std::str::from_utf8(value) else {
+            return Self::Binary;
+        };
+        if text.trim_start().starts_with("-----BEGIN ") {
+            let certificates = Pem::iter_from_buffer(value)
+                .map_while(Result::ok)
+                .filter(|pem| pem.label == "CERTIFICATE")
+                .filter_map(|pem| {
+                    let certificate = pem.parse_x509().ok()?;
+                    Some(Certificate {
+                        subject: certificate.subject().to_string(),
+                        not_after: DateTime::from_timestamp(
+                            certificate.validity().not_after.timestamp(),
+                            0,
+                        )?,
+                    })
+                })
+                .collect();
+            return Self::Pem {
+                text: text.to_string(),
+                certificates,
+            };
+        }
+        match serde_json::from_str::<serde_json::Value>(text) {
+            Ok(json @ (serde_json::Value::Object(_) | serde_json::Value::Array(_))) => {
+                Self::Json(serde_json::to_string_pretty(&json).unwrap_or_else(|_| text.into()))
+            }
+            _ => Self::Text(text.to_string()),
+        }
+    }
+}
+
+impl Certificate {
+    fn describe(&self, now: DateTime<Utc>) -> String {
+        let date = self.not_after.format("%Y-%m-%d");
+        if self.not_after < now {
+            format!(
+                "{}, expired {date} ({} ago)",
+                self.subject,
+                format_age(now - self.not_after)
+            )
+        } else {
+            format!(
+                "{}, expires {date} (in {})",
+                self.subject,
+                format_age(self.not_after - now)
+            )
+        }
+    }
+}
+
+/// The object's data, by key.
+pub(crate) async fn fetch(
+    client: Client,
+    kind: DataKind,
+    target: &ObjectRef,
+) -> DynResult<Vec<DataEntry>> {
+    let object = target.api(client).get(&target.name).await?;
+    Ok(DataEntry::all(kind, &object))
+}
+
+/// Set (or, given `None`, delete) a single key of the object's data, leaving the other keys alone.
+pub(crate) async fn set(
+    client: Client,
+    kind: DataKind,
+    target: &ObjectRef,
+    key: &str,
+    value: Option<&str>,
+) -> DynResult<()> {
+    target
+        .api(client)
+        .patch(
+            &target.name,
+            &patch_params(),
+            &Patch::Merge(data_patch(kind, key, value)),
+        )
+        .await?;
+    Ok(())
+}
+
+/// A JSON merge patch of a single key, in which `null` deletes the key.
+fn data_patch(kind: DataKind, key: &str, value: Option<&str>) -> Value {
+    let value = match (kind, value) {
+        (_, None) => Value::Null,
+        (DataKind::ConfigMap, Some(value)) => value.into(),
+        (DataKind::Secret, Some(value)) => STANDARD.encode(value).into(),
+    };
+    json!({"data": {key: value}})
+}
+
+/// Whether a character can be part of a key, like `app.properties` or `tls.crt`.
+pub(crate) fn is_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')
+}
+
+/// The suffix for the temporary file a value is edited in, so that the editor can
+/// highlight it according to the key's extension.
+pub(crate) fn editor_suffix(key: &str) -> String {
+    Path::new(key)
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default()
+}
+
+/// The value as edited, without the final newline that editors add if the value didn't have one.
+pub(crate) fn edited_value(original: &str, mut edited: String) -> String {
+    if !original.ends_with('\n') && edited.ends_with('\n') {
+        edited.pop();
+    }
+    edited
+}
+
+/// The escape sequence that asks the terminal to put `value` on the clipboard (OSC 52),
+/// which works over SSH, unlike talking to a local clipboard.
+pub(crate) fn osc52(value: &[u8]) -> String {
+    format!("\x1b]52;c;{}\x07", STANDARD.encode(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    const CERTIFICATE: &str = "\
+-----BEGIN CERTIFICATE-----
+MIIBgzCCASmgAwIBAgIUXHbDGclSg/mJCwMwj5GlmPzTSbwwCgYIKoZIzj0EAwIw
+FzEVMBMGA1UEAwwMZXhhbXBsZS50ZXN0MB4XDTI1MDEwMTAwMDAwMFoXDTMwMDEw
+MTAwMDAwMFowFzEVMBMGA1UEAwwMZXhhbXBsZS50ZXN0MFkwEwYHKoZIzj0CAQYI
+KoZIzj0DAQcDQgAEkwAyf4s3FF1tmCkoCQA628+msCahcoBXaI/07k9IGNZZdXaK
+Rtp/Am5kc9oXKx8ybR8B0CYmezc04+JhAQ5QW6NTMFEwHQYDVR0OBBYEFHks7DRS
+fdcWj/fliJ+hsr6PeZuAMB8GA1UdIwQYMBaAFHks7DRSfdcWj/fliJ+hsr6PeZuA
+MA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIgLk1K0/yZh7BwTEv0
+gs043EUKujH41CK5HZginevTaUwCIQDoyqWmxzdjV3GbxQ8GBe6lvL1S6TCH16Mk
+TnfrAz32fg==
+-----END CERTIFICATE-----
+";
+
+    fn date(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[rstest]
+    #[case(b"hunter2", Content::Text("hunter2".into()))]
+    #[case(b"42", Content::Text("42".into()))]
+    #[case(br#"{"auths":{}}"#, Content::Json("{\n  \"auths\": {}\n}".into()))]
+    #[case(b"\xff\xfe", Content::Binary)]
+    fn test_content(#[case] value: &[u8], #[case] expected: Content) {
+        assert_eq!(Content::of(value), expected);
+    }
+
+    #[rstest]
+    fn test_certificate() {
+        let entry = DataEntry::new("tls.crt".into(), CERTIFICATE.into());
+        let Content::Pem { certificates, .. } = &entry.content else {
+            panic!("not PEM: {:?}", entry.content);
+        };
+        assert_eq!(
+            certificates,
+            &[Certificate {
+                subject: "CN=example.test".into(),
+                not_after: date("2030-01-01T00:00:00Z"),
+            }]
+        );
+        assert_eq!(entry.expires(), Some(date("2030-01-01T00:00:00Z")));
+    }
+
+    #[rstest]
+    #[case("2029-12-01T00:00:00Z", "CN=example.test, expires 2030-01-01 (in 31d)")]
+    #[case("2030-01-03T00:00:00Z", "CN=example.test, expired 2030-01-01 (2d ago)")]
+    fn test_summary(#[case] now: &str, #[case] expected: &str) {
+        let entry = DataEntry::new("tls.crt".into(), CERTIFICATE.into());
+        assert_eq!(
+            entry.summary(date(now)),
+            format!("{} bytes, {expected}", CERTIFICATE.len())
+        );
+    }
+
+    #[rstest]
+    fn test_lines_are_masked_until_revealed() {
+        let mut entry = DataEntry::new("password".into(), b"hunter2".to_vec());
+        assert_eq!(entry.lines(), vec![MASK]);
+        entry.revealed = true;
+        assert_eq!(entry.lines(), vec!["hunter2"]);
+    }
+
+    #[rstest]
+    fn test_all() {
+        let object: DynamicObject = serde_json::from_value(json!({
+            "metadata": {"name": "creds"},
+            "data": {"password": "aHVudGVyMg==", "invalid": "%%%"},
+        }))
+        .unwrap();
+        let entries = DataEntry::all(DataKind::Secret, &object);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].key, "password");
+        assert_eq!(entries[0].value, b"hunter2");
+        assert!(!entries[0].revealed);
+
+        let entries = DataEntry::all(DataKind::ConfigMap, &object);
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|e| e.revealed));
+    }
+
+    #[rstest]
+    #[case(DataKind::ConfigMap, Some("hunter2"), json!({"data": {"k": "hunter2"}}))]
+    #[case(DataKind::Secret, Some("hunter2"), json!({"data": {"k": "aHVudGVyMg=="}}))]
+    #[case(DataKind::Secret, None, json!({"data": {"k": null}}))]
+    fn test_data_patch(
+        #[case] kind: DataKind,
+        #[case] value: Option<&str>,
+        #[case] expected: Value,
+    ) {
+        assert_eq!(data_patch(kind, "k", value), expected);
+    }
+
+    #[rstest]
+    #[case("", "value\n", "value")]
+    #[case("old", "value\n\n", "value\n")]
+    #[case("old\n", "value\n", "value\n")]
+    #[case("old", "value", "value")]
+    fn test_edited_value(#[case] original: &str, #[case] edited: &str, #[case] expected: &str) {
+        assert_eq!(edited_value(original, edited.into()), expected);
+    }
+
+    #[rstest]
+    #[case("app.properties", ".properties")]
+    #[case("config.yaml", ".yaml")]
+    #[case("password", "")]
+    fn test_editor_suffix(#[case] key: &str, #[case] expected: &str) {
+        assert_eq!(editor_suffix(key), expected);
+    }
+
+    #[rstest]
+    fn test_osc52() {
+        assert_eq!(osc52(b"hunter2"), "\x1b]52;c;aHVudGVyMg==\x07");
+    }
+}
@@ -0,0 +1,83 @@
+use similar::{ChangeTag, TextDiff};
+
+/// How many unchanged lines to show around each change.
+const CONTEXT_LINES: usize = 3;
+
+/// A line of a unified diff.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum DiffLine {
+    /// The `@@ -1,3 +1,4 @@` line that starts a hunk.
+    Hunk(String),
+    Context(String),
+    Added(String),
+    Removed(String),
+}
+
+impl DiffLine {
+    /// The line as it appears in a unified diff, with its `+`, `-` or ` ` prefix.
+    pub(crate) fn text(&self) -> String {
+        match self {
+            Self::Hunk(header) => header.clone(),
+            Self::Context(line) => format!(" {line}"),
+            Self::Added(line) => format!("+{line}"),
+            Self::Removed(line) => format!("-{line}"),
+        }
+    }
+}
+
+/// The unified diff of two texts, line by line, which is empty if they are the same.
+pub(crate) fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let diff = TextDiff::from_lines(old, new);
+    let mut lines = vec![];
+    for hunk in diff
+        .unified_diff()
+        .context_radius(CONTEXT_LINES)
+        .iter_hunks()
+    {
+        lines.push(DiffLine::Hunk(hunk.header().to_string()));
+        for change in hunk.iter_changes() {
+            let line = change.value().trim_end_matches('\n').to_string();
+            lines.push(match change.tag() {
+                ChangeTag::Equal => DiffLine::Context(line),
+                ChangeTag::Insert => DiffLine::Added(line),
+                ChangeTag::Delete => DiffLine::Removed(line),
+            });
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    fn test_diff_lines() {
+        assert_eq!(
+            diff_lines("a\nb\nc\n", "a\nB\nc\nd\n"),
+            vec![
+                DiffLine::Hunk("@@ -1,3 +1,4 @@".into()),
+                DiffLine::Context("a".into()),
+                DiffLine::Removed("b".into()),
+                DiffLine::Added("B".into()),
+                DiffLine::Context("c".into()),
+                DiffLine::Added("d".into()),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_no_diff() {
+        assert_eq!(diff_lines("a\n", "a\n"), vec![]);
+    }
+
+    #[rstest]
+    #[case(DiffLine::Context("a".into()), " a")]
+    #[case(DiffLine::Added("a".into()), "+a")]
+    #[case(DiffLine::Removed("a".into()), "-a")]
+    fn test_text(#[case] line: DiffLine, #[case] expected: &str) {
+        assert_eq!(line.text(), expected);
+    }
+}
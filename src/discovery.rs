@@ -3,9 +3,9 @@ use std::{collections::HashMap, rc::Rc};
 use http::Request;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::APIResource;
 use kube::{
-    api::ApiResource,
+    api::{ApiResource, DynamicObject},
     core::{gvk::ParseGroupVersionError, GroupVersion},
-    Client,
+    Api, Client,
 };
 
 use crate::DynResult;
@@ -58,6 +58,16 @@ impl DiscoveredAPIResource {
         })
     }
 
+    /// The name that finds this resource even when another group shadows its plural name,
+    /// like `pods.metrics.k8s.io`.
+    pub fn qualified_name(&self) -> String {
+        if self.group.is_empty() {
+            self.plural.clone()
+        } else {
+            format!("{}.{}", self.plural, self.group)
+        }
+    }
+
     pub fn url_path(&self, namespace: Option<&str>) -> String {
         let n = if let Some(ns) = namespace {
             format!("namespaces/{ns}/")
@@ -73,15 +83,56 @@ impl DiscoveredAPIResource {
         )
     }
 
-    pub fn table_request(&self, namespace: Option<&str>) -> Request<Vec<u8>> {
+    /// A dynamic [`Api`] for this resource, scoped to the namespace if the resource is namespaced.
+    pub fn api(&self, client: Client, namespace: Option<&str>) -> Api<DynamicObject> {
+        let ar = ApiResource::from(self);
+        match namespace {
+            Some(ns) if self.namespaced => Api::namespaced_with(client, ns, &ar),
+            _ => Api::all_with(client, &ar),
+        }
+    }
+
+    pub fn table_request(&self, namespace: Option<&str>, query: &TableQuery) -> Request<Vec<u8>> {
         Request::builder()
-            .uri(self.url_path(namespace))
+            .uri(format!(
+                "{}{}",
+                self.url_path(namespace),
+                query.query_string()
+            ))
             .header("Accept", "application/json;as=Table;g=meta.k8s.io;v=v1")
             .body(vec![])
             .unwrap()
     }
 }
 
+/// Query parameters for a request for a resource as a server-side Table.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableQuery<'a> {
+    /// Whether each row should carry the whole object instead of only its metadata.
+    pub include_object: bool,
+    pub label_selector: Option<&'a str>,
+    pub field_selector: Option<&'a str>,
+}
+
+impl TableQuery<'_> {
+    pub fn query_string(&self) -> String {
+        let mut qs = form_urlencoded::Serializer::new(String::new());
+        if self.include_object {
+            qs.append_pair("includeObject", "Object");
+        }
+        if let Some(s) = self.label_selector.filter(|s| !s.is_empty()) {
+            qs.append_pair("labelSelector", s);
+        }
+        if let Some(s) = self.field_selector.filter(|s| !s.is_empty()) {
+            qs.append_pair("fieldSelector", s);
+        }
+        match qs.finish() {
+            q if q.is_empty() => q,
+            q => format!("?{q}"),
+        }
+    }
+}
+
 impl From<&DiscoveredAPIResource> for ApiResource {
     fn from(value: &DiscoveredAPIResource) -> Self {
         Self {
@@ -94,71 +145,309 @@ impl From<&DiscoveredAPIResource> for ApiResource {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub(crate) struct Discovery {
     pub(crate) name_to_resource: HashMap<String, Rc<DiscoveredAPIResource>>,
+    /// Resources by the `apiVersion` and `kind` of their objects, in every served version,
+    /// for finding the resource of an object in a manifest.
+    pub(crate) kind_to_resource: HashMap<(String, String), Rc<DiscoveredAPIResource>>,
+    /// The verbs of subresources, by their `apiVersion` and name, like `apps/v1`
+    /// `deployments/scale`.
+    pub(crate) subresource_verbs: HashMap<(String, String), Vec<String>>,
 }
 
 impl Discovery {
     pub(crate) async fn discover(client: &Client) -> DynResult<Self> {
         // https://github.com/kube-rs/kube/blob/d28a7152538c2560f7af9b7339c090c7ccba9fb6/kube-client/src/discovery/mod.rs#L111-L130
-        let mut name_to_resource: HashMap<String, Rc<DiscoveredAPIResource>> = HashMap::new();
+        let mut discovery = Self::default();
 
         // Discover non-core first so that names for core resources override these names.
         let api_groups = client.list_api_groups().await?;
         for g in api_groups.groups {
-            let ver = g
+            let preferred = g
                 .preferred_version
                 .as_ref()
                 .or_else(|| g.versions.first())
                 .expect("preferred or versions exists");
-            let apis = client.list_api_group_resources(&ver.group_version).await?;
-            for api in apis.resources {
-                if !api.verbs.iter().any(|v| v == "list") {
-                    continue;
-                }
-                let discovered =
-                    DiscoveredAPIResource::parse_api_resource(&api, &ver.group_version)?;
-                let a = Rc::new(discovered);
-
-                if !a.singular.is_empty() {
-                    name_to_resource.insert(a.singular.clone(), a.clone());
-                }
-                if !a.plural.is_empty() {
-                    name_to_resource.insert(a.plural.clone(), a.clone());
-                }
-                for name in a.short_names.as_deref().unwrap_or_default() {
-                    name_to_resource.insert(name.clone(), a.clone());
-                }
+            // Every served version is discovered so that manifests may use any of them,
+            // but only the preferred version's resources are found by name.
+            for ver in &g.versions {
+                let apis = client.list_api_group_resources(&ver.group_version).await?;
+                discovery.insert_resources(
+                    &apis.resources,
+                    &ver.group_version,
+                    ver.group_version == preferred.group_version,
+                )?;
             }
         }
 
         let core_api_groups = client.list_core_api_versions().await?;
         for v in core_api_groups.versions {
             let apis = client.list_core_api_resources(&v).await?;
-            for api in apis.resources {
-                if !api.verbs.iter().any(|v| v == "list") {
-                    continue;
-                }
-                let discovered = DiscoveredAPIResource::parse_api_resource(&api, &v)?;
-                let a = Rc::new(discovered);
-
-                if !a.singular.is_empty() {
-                    name_to_resource.insert(a.singular.clone(), a.clone());
-                }
-                if !a.plural.is_empty() {
-                    name_to_resource.insert(a.plural.clone(), a.clone());
-                }
-                for name in a.short_names.as_deref().unwrap_or_default() {
-                    name_to_resource.insert(name.clone(), a.clone());
-                }
+            discovery.insert_resources(&apis.resources, &v, true)?;
+        }
+
+        Ok(discovery)
+    }
+
+    /// Index the resources of a group version by kind, and by name if it is the group's
+    /// preferred version.
+    fn insert_resources(
+        &mut self,
+        api_resources: &[APIResource],
+        group_version: &str,
+        preferred: bool,
+    ) -> Result<(), ParseGroupVersionError> {
+        for api in api_resources {
+            // Subresources are listed alongside their parents as e.g. "deployments/scale".
+            if api.name.contains('/') {
+                let gv: GroupVersion = group_version.parse()?;
+                self.subresource_verbs
+                    .insert((gv.api_version(), api.name.clone()), api.verbs.clone());
+                continue;
+            }
+            if !api.verbs.iter().any(|v| v == "list") {
+                continue;
+            }
+            let a = Rc::new(DiscoveredAPIResource::parse_api_resource(
+                api,
+                group_version,
+            )?);
+            self.kind_to_resource
+                .insert((a.api_version.clone(), a.kind.clone()), a.clone());
+            if !preferred {
+                continue;
+            }
+
+            if !a.singular.is_empty() {
+                self.name_to_resource.insert(a.singular.clone(), a.clone());
+            }
+            if !a.plural.is_empty() {
+                self.name_to_resource.insert(a.plural.clone(), a.clone());
+            }
+            for name in a.short_names.as_deref().unwrap_or_default() {
+                self.name_to_resource.insert(name.clone(), a.clone());
+            }
+            // Like kubectl, "pods.metrics.k8s.io" names a resource even when "pods" is shadowed.
+            if !a.group.is_empty() {
+                self.name_to_resource.insert(a.qualified_name(), a.clone());
             }
         }
 
-        Ok(Self { name_to_resource })
+        Ok(())
     }
 
     pub(crate) fn get(&self, name: &str) -> Option<&Rc<DiscoveredAPIResource>> {
         self.name_to_resource.get(name)
     }
+
+    /// The verbs of the resource's subresource, like `scale`, if it has one.
+    fn subresource(&self, resource: &DiscoveredAPIResource, name: &str) -> Option<&[String]> {
+        self.subresource_verbs
+            .get(&(
+                resource.api_version.clone(),
+                format!("{}/{name}", resource.plural),
+            ))
+            .map(Vec::as_slice)
+    }
+
+    /// Whether the resource can be scaled through its `scale` subresource,
+    /// like Deployments, StatefulSets and any CRD that implements scale.
+    pub(crate) fn is_scalable(&self, resource: &DiscoveredAPIResource) -> bool {
+        self.subresource(resource, "scale")
+            .is_some_and(|verbs| verbs.iter().any(|v| v == "patch"))
+    }
+
+    /// Whether the resource is a workload whose pods can be restarted by patching its pod
+    /// template, which is taken to be one that can be patched and has a `scale` subresource.
+    pub(crate) fn is_restartable(&self, resource: &DiscoveredAPIResource) -> bool {
+        resource.verbs.iter().any(|v| v == "patch") && self.subresource(resource, "scale").is_some()
+    }
+
+    /// The resource for objects of the kind, like `apps/v1` `Deployment` in a manifest.
+    pub(crate) fn resource_for(
+        &self,
+        api_version: &str,
+        kind: &str,
+    ) -> Option<&Rc<DiscoveredAPIResource>> {
+        self.kind_to_resource
+            .get(&(api_version.to_string(), kind.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(TableQuery::default(), "")]
+    #[case(TableQuery { include_object: true, ..TableQuery::default() }, "?includeObject=Object")]
+    #[case(
+        TableQuery { label_selector: Some("app=foo,tier!=web"), ..TableQuery::default() },
+        "?labelSelector=app%3Dfoo%2Ctier%21%3Dweb"
+    )]
+    #[case(
+        TableQuery {
+            include_object: true,
+            label_selector: Some(""),
+            field_selector: Some("metadata.name=foo"),
+        },
+        "?includeObject=Object&fieldSelector=metadata.name%3Dfoo"
+    )]
+    fn test_table_query_string(#[case] query: TableQuery, #[case] expected: &str) {
+        assert_eq!(query.query_string(), expected);
+    }
+
+    fn api_resource(name: &str, kind: &str) -> APIResource {
+        APIResource {
+            name: name.into(),
+            kind: kind.into(),
+            singular_name: String::new(),
+            namespaced: true,
+            verbs: vec!["list".into()],
+            ..APIResource::default()
+        }
+    }
+
+    #[rstest]
+    fn test_group_qualified_names_survive_shadowing() {
+        let mut discovery = Discovery::default();
+        discovery
+            .insert_resources(
+                &[api_resource("pods", "PodMetrics")],
+                "metrics.k8s.io/v1beta1",
+                true,
+            )
+            .unwrap();
+        discovery
+            .insert_resources(&[api_resource("pods", "Pod")], "v1", true)
+            .unwrap();
+
+        assert_eq!(discovery.get("pods").unwrap().kind, "Pod");
+        assert_eq!(
+            discovery.get("pods.metrics.k8s.io").unwrap().kind,
+            "PodMetrics"
+        );
+    }
+
+    #[rstest]
+    #[case("v1", "Pod", Some("pods"))]
+    #[case("apps/v1", "Deployment", Some("deployments"))]
+    #[case("apps/v1beta1", "Deployment", None)]
+    #[case("v1", "Deployment", None)]
+    #[case(
+        "autoscaling/v2",
+        "HorizontalPodAutoscaler",
+        Some("horizontalpodautoscalers")
+    )]
+    #[case(
+        "autoscaling/v1",
+        "HorizontalPodAutoscaler",
+        Some("horizontalpodautoscalers")
+    )]
+    fn test_resource_for(
+        #[case] api_version: &str,
+        #[case] kind: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        let mut discovery = Discovery::default();
+        discovery
+            .insert_resources(
+                &[api_resource("deployments", "Deployment")],
+                "apps/v1",
+                true,
+            )
+            .unwrap();
+        discovery
+            .insert_resources(&[api_resource("pods", "Pod")], "v1", true)
+            .unwrap();
+        let hpa = [api_resource(
+            "horizontalpodautoscalers",
+            "HorizontalPodAutoscaler",
+        )];
+        discovery
+            .insert_resources(&hpa, "autoscaling/v2", true)
+            .unwrap();
+        discovery
+            .insert_resources(&hpa, "autoscaling/v1", false)
+            .unwrap();
+
+        assert_eq!(
+            discovery
+                .resource_for(api_version, kind)
+                .map(|r| r.plural.as_str()),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn test_names_are_for_the_preferred_version() {
+        let mut discovery = Discovery::default();
+        let hpa = [api_resource(
+            "horizontalpodautoscalers",
+            "HorizontalPodAutoscaler",
+        )];
+        discovery
+            .insert_resources(&hpa, "autoscaling/v2", true)
+            .unwrap();
+        discovery
+            .insert_resources(&hpa, "autoscaling/v1", false)
+            .unwrap();
+
+        assert_eq!(
+            discovery
+                .get("horizontalpodautoscalers")
+                .unwrap()
+                .api_version,
+            "autoscaling/v2"
+        );
+        assert_eq!(
+            discovery
+                .resource_for("autoscaling/v1", "HorizontalPodAutoscaler")
+                .unwrap()
+                .api_version,
+            "autoscaling/v1"
+        );
+    }
+
+    #[rstest]
+    fn test_is_scalable() {
+        let mut discovery = Discovery::default();
+        let scale = APIResource {
+            verbs: vec!["get".into(), "patch".into(), "update".into()],
+            ..api_resource("deployments/scale", "Scale")
+        };
+        discovery
+            .insert_resources(
+                &[
+                    api_resource("deployments", "Deployment"),
+                    scale,
+                    api_resource("daemonsets", "DaemonSet"),
+                    APIResource {
+                        verbs: vec!["list".into(), "patch".into()],
+                        ..api_resource("statefulsets", "StatefulSet")
+                    },
+                    APIResource {
+                        verbs: vec!["get".into()],
+                        ..api_resource("statefulsets/scale", "Scale")
+                    },
+                ],
+                "apps/v1",
+                true,
+            )
+            .unwrap();
+
+        let deployments = discovery.get("deployments").unwrap();
+        let daemonsets = discovery.get("daemonsets").unwrap();
+        assert!(discovery.is_scalable(deployments));
+        assert!(!discovery.is_scalable(daemonsets));
+        // The test resources can only be listed, not patched.
+        assert!(!discovery.is_restartable(deployments));
+        let statefulsets = discovery.get("statefulsets").unwrap();
+        assert!(!discovery.is_scalable(statefulsets));
+        assert!(discovery.is_restartable(statefulsets));
+        assert!(discovery.get("deployments/scale").is_none());
+    }
 }
@@ -0,0 +1,135 @@
+use std::{env, fs, io::Write};
+
+use kube::{
+    api::{DynamicObject, Patch, PatchParams},
+    Api,
+};
+
+use crate::DynResult;
+
+pub const FIELD_MANAGER: &str = "flotilla";
+
+const HEADER: &str = "\
+# Please edit the object below. Lines beginning with a '#' will be ignored,
+# and an empty file will abort the edit. If an error occurs while saving this file will be
+# reopened with the relevant failures.
+#
+";
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum EditOutcome {
+    Applied,
+    Unchanged,
+    Cancelled,
+}
+
+/// Open the named object in `$EDITOR` and server-side apply the result,
+/// reopening the editor with the error annotated at the top if the apply fails.
+///
+/// The terminal must already be restored to its normal mode before calling this.
+pub(crate) async fn edit_object(api: &Api<DynamicObject>, name: &str) -> DynResult<EditOutcome> {
+    let mut object = api.get(name).await?;
+    object.metadata.managed_fields = None;
+
+    let original = serde_yaml::to_string(&object)?;
+    let mut body = original.clone();
+    let mut error: Option<String> = None;
+
+    loop {
+        let edited = strip_header(&run_editor(&annotate(&body, error.as_deref()), ".yaml")?);
+
+        if edited.lines().all(|l| l.trim().is_empty()) {
+            return Ok(EditOutcome::Cancelled);
+        }
+        if edited == original {
+            return Ok(EditOutcome::Unchanged);
+        }
+
+        body = edited;
+        error = match serde_yaml::from_str::<DynamicObject>(&body) {
+            Ok(o) => match api
+                .patch(name, &PatchParams::apply(FIELD_MANAGER), &Patch::Apply(&o))
+                .await
+            {
+                Ok(_) => return Ok(EditOutcome::Applied),
+                Err(kube::Error::Api(e)) => Some(e.message),
+                Err(e) => Some(e.to_string()),
+            },
+            Err(e) => Some(e.to_string()),
+        };
+    }
+}
+
+/// Write `contents` to a temporary file with the suffix (like `.yaml`), open it in the user's
+/// editor, and return the file's contents after the editor exits.
+pub(crate) fn run_editor(contents: &str, suffix: &str) -> DynResult<String> {
+    let mut file = tempfile::Builder::new()
+        .prefix("flotilla-")
+        .suffix(suffix)
+        .tempfile()?;
+    file.write_all(contents.as_bytes())?;
+    file.flush()?;
+
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".into());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(file.path())
+        .status()?;
+    if !status.success() {
+        return Err(format!("editor {editor:?} exited with {status}").into());
+    }
+
+    Ok(fs::read_to_string(file.path())?)
+}
+
+fn annotate(body: &str, error: Option<&str>) -> String {
+    let mut s = HEADER.to_string();
+    if let Some(e) = error {
+        for line in e.lines() {
+            s.push_str(&format!("# error: {line}\n"));
+        }
+        s.push_str("#\n");
+    }
+    s.push_str(body);
+    s
+}
+
+fn strip_header(contents: &str) -> String {
+    contents
+        .lines()
+        .skip_while(|l| l.starts_with('#'))
+        .map(|l| format!("{l}\n"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("kind: Pod\n", None)]
+    #[case("kind: Pod\n", Some("conflict"))]
+    #[case("kind: Pod\nmetadata:\n  name: foo\n", Some("first\nsecond"))]
+    fn test_strip_header_inverts_annotate(#[case] body: &str, #[case] error: Option<&str>) {
+        assert_eq!(strip_header(&annotate(body, error)), body);
+    }
+
+    #[rstest]
+    #[case("kind: Pod\n", None, HEADER.lines().count() + 1)]
+    #[case("kind: Pod\n", Some("conflict"), HEADER.lines().count() + 3)]
+    #[case("kind: Pod\n", Some("first\nsecond"), HEADER.lines().count() + 4)]
+    fn test_annotate_line_count(
+        #[case] body: &str,
+        #[case] error: Option<&str>,
+        #[case] expected: usize,
+    ) {
+        assert_eq!(annotate(body, error).lines().count(), expected);
+    }
+}
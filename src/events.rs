@@ -0,0 +1,237 @@
+use std::cmp::Reverse;
+
+use k8s_openapi::chrono::{DateTime, Utc};
+use kube::{
+    api::{DynamicObject, ListParams},
+    Client,
+};
+use serde_json::Value;
+
+use crate::{discovery::DiscoveredAPIResource, state::ObjectRef, DynResult};
+
+/// The fields of an Event that we display, normalized across
+/// the `v1` and `events.k8s.io/v1` versions of the resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EventSummary {
+    pub(crate) last_seen: Option<DateTime<Utc>>,
+    pub(crate) type_: String,
+    pub(crate) reason: String,
+    pub(crate) message: String,
+    pub(crate) count: i64,
+}
+
+impl EventSummary {
+    pub(crate) fn from_object(object: &DynamicObject) -> Self {
+        let data = &object.data;
+        let str_field = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|n| data.get(n).and_then(Value::as_str))
+                .map(String::from)
+        };
+        let timestamp = |names: &[&str]| {
+            names.iter().find_map(|n| {
+                data.get(n)
+                    .and_then(Value::as_str)
+                    .and_then(|s| s.parse::<DateTime<Utc>>().ok())
+            })
+        };
+
+        Self {
+            last_seen: timestamp(&[
+                "lastTimestamp",
+                "deprecatedLastTimestamp",
+                "eventTime",
+                "firstTimestamp",
+                "deprecatedFirstTimestamp",
+            ])
+            .or_else(|| object.metadata.creation_timestamp.as_ref().map(|t| t.0)),
+            type_: str_field(&["type"]).unwrap_or_default(),
+            reason: str_field(&["reason"]).unwrap_or_default(),
+            message: str_field(&["message", "note"]).unwrap_or_default(),
+            count: ["count", "deprecatedCount"]
+                .iter()
+                .find_map(|n| data.get(n).and_then(Value::as_i64))
+                .or_else(|| data.pointer("/series/count").and_then(Value::as_i64))
+                .unwrap_or(1),
+        }
+    }
+
+    pub(crate) fn is_warning(&self) -> bool {
+        self.type_ == "Warning"
+    }
+}
+
+pub(crate) fn is_event_resource(resource: &DiscoveredAPIResource) -> bool {
+    resource.kind == "Event" && (resource.group.is_empty() || resource.group == "events.k8s.io")
+}
+
+/// A field selector matching the events about the target object.
+fn field_selector(events: &DiscoveredAPIResource, target: &ObjectRef) -> String {
+    // events.k8s.io calls the object an event is about "regarding" instead of "involvedObject".
+    let field = if events.group == "events.k8s.io" {
+        "regarding"
+    } else {
+        "involvedObject"
+    };
+    let selector = format!(
+        "{field}.kind={kind},{field}.name={name}",
+        kind = target.resource.kind,
+        name = target.name
+    );
+    // Events are listed across namespaces, so objects of the same name in other namespaces
+    // would otherwise have their events mixed in.
+    match &target.namespace {
+        Some(namespace) if target.resource.namespaced => {
+            format!("{selector},{field}.namespace={namespace}")
+        }
+        _ => selector,
+    }
+}
+
+/// Sort events so that the most recent come first.
+pub(crate) fn sort_newest_first(events: &mut [EventSummary]) {
+    events.sort_by_key(|e| Reverse(e.last_seen));
+}
+
+/// The events about the target object, most recent first.
+pub(crate) async fn events_for(
+    client: Client,
+    events: &DiscoveredAPIResource,
+    target: &ObjectRef,
+) -> DynResult<Vec<EventSummary>> {
+    let list = events
+        .api(client, target.namespace.as_deref())
+        .list(&ListParams::default().fields(&field_selector(events, target)))
+        .await?;
+    let mut summaries: Vec<EventSummary> =
+        list.items.iter().map(EventSummary::from_object).collect();
+    sort_newest_first(&mut summaries);
+    Ok(summaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::*;
+
+    fn resource(group: &str, kind: &str, namespaced: bool) -> DiscoveredAPIResource {
+        DiscoveredAPIResource {
+            group: group.into(),
+            version: "v1".into(),
+            api_version: if group.is_empty() {
+                "v1".into()
+            } else {
+                format!("{group}/v1")
+            },
+            kind: kind.into(),
+            plural: format!("{}s", kind.to_lowercase()),
+            singular: kind.to_lowercase(),
+            short_names: None,
+            verbs: vec!["list".into()],
+            namespaced,
+        }
+    }
+
+    #[rstest]
+    #[case(
+        "",
+        resource("", "Pod", true),
+        Some("default"),
+        "involvedObject.kind=Pod,involvedObject.name=web,involvedObject.namespace=default"
+    )]
+    #[case(
+        "events.k8s.io",
+        resource("", "Pod", true),
+        Some("default"),
+        "regarding.kind=Pod,regarding.name=web,regarding.namespace=default"
+    )]
+    #[case(
+        "",
+        resource("", "Node", false),
+        None,
+        "involvedObject.kind=Node,involvedObject.name=web"
+    )]
+    fn test_field_selector(
+        #[case] events_group: &str,
+        #[case] target: DiscoveredAPIResource,
+        #[case] namespace: Option<&str>,
+        #[case] expected: &str,
+    ) {
+        let target = ObjectRef {
+            resource: Rc::new(target),
+            namespace: namespace.map(String::from),
+            name: "web".into(),
+        };
+        assert_eq!(
+            field_selector(&resource(events_group, "Event", true), &target),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(
+        json!({
+            "apiVersion": "v1",
+            "kind": "Event",
+            "metadata": {"name": "foo.1"},
+            "type": "Warning",
+            "reason": "BackOff",
+            "message": "Back-off restarting failed container",
+            "count": 5,
+            "lastTimestamp": "2025-01-01T00:05:00Z",
+        }),
+        "Warning",
+        "Back-off restarting failed container",
+        5,
+        "2025-01-01T00:05:00Z",
+    )]
+    #[case(
+        json!({
+            "apiVersion": "events.k8s.io/v1",
+            "kind": "Event",
+            "metadata": {"name": "foo.2"},
+            "type": "Normal",
+            "reason": "Pulled",
+            "note": "Successfully pulled image",
+            "eventTime": "2025-01-01T00:01:00.000000Z",
+        }),
+        "Normal",
+        "Successfully pulled image",
+        1,
+        "2025-01-01T00:01:00Z",
+    )]
+    #[case(
+        json!({
+            "apiVersion": "events.k8s.io/v1",
+            "kind": "Event",
+            "metadata": {"name": "foo.3", "creationTimestamp": "2025-01-01T00:02:00Z"},
+            "type": "Normal",
+            "reason": "Scheduled",
+            "note": "Successfully assigned",
+            "series": {"count": 3},
+        }),
+        "Normal",
+        "Successfully assigned",
+        3,
+        "2025-01-01T00:02:00Z",
+    )]
+    fn test_event_summary_from_object(
+        #[case] object: Value,
+        #[case] type_: &str,
+        #[case] message: &str,
+        #[case] count: i64,
+        #[case] last_seen: &str,
+    ) {
+        let object: DynamicObject = serde_json::from_value(object).unwrap();
+        let summary = EventSummary::from_object(&object);
+        assert_eq!(summary.type_, type_);
+        assert_eq!(summary.message, message);
+        assert_eq!(summary.count, count);
+        assert_eq!(summary.last_seen, Some(last_seen.parse().unwrap()));
+    }
+}
@@ -0,0 +1,247 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use crossterm::{
+    event,
+    event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    terminal,
+};
+use k8s_openapi::{api::core::v1::Pod, apimachinery::pkg::apis::meta::v1::Status};
+use kube::{
+    api::{AttachParams, TerminalSize},
+    Api,
+};
+use tokio::{io::AsyncWriteExt, sync::mpsc};
+
+use crate::DynResult;
+
+pub const DEFAULT_CONTAINER_ANNOTATION: &str = "kubectl.kubernetes.io/default-container";
+
+/// The names of the containers of the pod, with the default container first.
+pub(crate) fn container_names(pod: &Pod) -> Vec<String> {
+    let mut names: Vec<String> = pod
+        .spec
+        .iter()
+        .flat_map(|s| s.containers.iter().map(|c| c.name.clone()))
+        .collect();
+
+    let default = pod
+        .metadata
+        .annotations
+        .as_ref()
+        .and_then(|a| a.get(DEFAULT_CONTAINER_ANNOTATION));
+    if let Some(idx) = default.and_then(|d| names.iter().position(|n| n == d)) {
+        let name = names.remove(idx);
+        names.insert(0, name);
+    }
+
+    names
+}
+
+enum Input {
+    Bytes(Vec<u8>),
+    Resize(u16, u16),
+}
+
+/// Run an interactive shell in the container, trying each of `commands` in turn
+/// until one of them exists in the container.
+///
+/// The terminal must already be restored to its normal mode before calling this.
+pub(crate) async fn exec_shell(
+    api: &Api<Pod>,
+    pod: &str,
+    container: &str,
+    commands: &[String],
+) -> DynResult<()> {
+    terminal::enable_raw_mode()?;
+    let result = try_commands(api, pod, container, commands).await;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+async fn try_commands(
+    api: &Api<Pod>,
+    pod: &str,
+    container: &str,
+    commands: &[String],
+) -> DynResult<()> {
+    for command in commands {
+        let status = exec(api, pod, container, command).await?;
+        match status {
+            Some(s)
+                if s.status.as_deref() == Some("Failure")
+                    && s.reason.as_deref() != Some("NonZeroExitCode") =>
+            {
+                let message = s.message.unwrap_or_default();
+                if is_missing_command(&message) {
+                    continue;
+                }
+                return Err(message.into());
+            }
+            _ => return Ok(()),
+        }
+    }
+
+    Err(format!("none of {commands:?} could be run in container {container}").into())
+}
+
+fn is_missing_command(message: &str) -> bool {
+    message.contains("not found") || message.contains("no such file or directory")
+}
+
+async fn exec(
+    api: &Api<Pod>,
+    pod: &str,
+    container: &str,
+    command: &str,
+) -> DynResult<Option<Status>> {
+    let params = AttachParams::interactive_tty().container(container);
+    let mut attached = api
+        .exec(pod, command.split_whitespace().collect::<Vec<_>>(), &params)
+        .await?;
+
+    let mut stdin = attached.stdin().expect("stdin was requested");
+    let mut stdout = attached.stdout().expect("stdout was requested");
+    let mut sizes = attached.terminal_size().expect("tty was requested");
+    let status = attached.take_status().expect("status was not taken yet");
+
+    let (width, height) = terminal::size()?;
+    sizes.try_send(TerminalSize { width, height }).ok();
+
+    let output =
+        tokio::spawn(async move { tokio::io::copy(&mut stdout, &mut tokio::io::stdout()).await });
+
+    // Read input through crossterm rather than stdin directly so that the reader
+    // can be stopped when the process exits instead of swallowing the next keypress.
+    let stop = Arc::new(AtomicBool::new(false));
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let reader = {
+        let stop = stop.clone();
+        thread::spawn(move || -> std::io::Result<()> {
+            while !stop.load(Ordering::Relaxed) {
+                if !event::poll(Duration::from_millis(50))? {
+                    continue;
+                }
+                let input = match event::read()? {
+                    Event::Key(key) if key.kind != KeyEventKind::Release => {
+                        Input::Bytes(key_to_bytes(key))
+                    }
+                    Event::Paste(s) => Input::Bytes(s.into_bytes()),
+                    Event::Resize(w, h) => Input::Resize(w, h),
+                    _ => continue,
+                };
+                if tx.send(input).is_err() {
+                    break;
+                }
+            }
+            Ok(())
+        })
+    };
+
+    tokio::pin!(status);
+    let status: DynResult<_> = async {
+        loop {
+            tokio::select! {
+                status = &mut status => break Ok(status),
+                Some(input) = rx.recv() => match input {
+                    Input::Bytes(b) => {
+                        stdin.write_all(&b).await?;
+                        stdin.flush().await?;
+                    }
+                    Input::Resize(width, height) => {
+                        sizes.try_send(TerminalSize { width, height }).ok();
+                    }
+                },
+            }
+        }
+    }
+    .await;
+
+    // Stop the reader even if forwarding input failed, so it doesn't go on stealing key presses
+    // from the TUI.
+    stop.store(true, Ordering::Relaxed);
+    let read = reader.join().expect("input reader panicked");
+    let status = status?;
+    read?;
+    output.await??;
+
+    Ok(status)
+}
+
+/// The bytes a terminal would send for the key press.
+fn key_to_bytes(key: KeyEvent) -> Vec<u8> {
+    let mut bytes = match key.code {
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            match c.to_ascii_lowercase() {
+                c @ 'a'..='z' => vec![c as u8 - b'a' + 1],
+                '@' | ' ' => vec![0],
+                '[' => vec![0x1b],
+                '\\' => vec![0x1c],
+                ']' => vec![0x1d],
+                _ => c.to_string().into_bytes(),
+            }
+        }
+        KeyCode::Char(c) => c.to_string().into_bytes(),
+        KeyCode::Enter => vec![b'\r'],
+        KeyCode::Tab => vec![b'\t'],
+        KeyCode::BackTab => b"\x1b[Z".to_vec(),
+        KeyCode::Backspace => vec![0x7f],
+        KeyCode::Esc => vec![0x1b],
+        KeyCode::Up => b"\x1b[A".to_vec(),
+        KeyCode::Down => b"\x1b[B".to_vec(),
+        KeyCode::Right => b"\x1b[C".to_vec(),
+        KeyCode::Left => b"\x1b[D".to_vec(),
+        KeyCode::Home => b"\x1b[H".to_vec(),
+        KeyCode::End => b"\x1b[F".to_vec(),
+        KeyCode::Insert => b"\x1b[2~".to_vec(),
+        KeyCode::Delete => b"\x1b[3~".to_vec(),
+        KeyCode::PageUp => b"\x1b[5~".to_vec(),
+        KeyCode::PageDown => b"\x1b[6~".to_vec(),
+        _ => vec![],
+    };
+
+    if key.modifiers.contains(KeyModifiers::ALT) && !bytes.is_empty() {
+        bytes.insert(0, 0x1b);
+    }
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(KeyEvent::new(KeyCode::Char('a'), KeyModifiers::NONE), b"a".to_vec())]
+    #[case(KeyEvent::new(KeyCode::Char('A'), KeyModifiers::SHIFT), b"A".to_vec())]
+    #[case(KeyEvent::new(KeyCode::Char('é'), KeyModifiers::NONE), "é".as_bytes().to_vec())]
+    #[case(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL), vec![0x03])]
+    #[case(KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL), vec![0x04])]
+    #[case(KeyEvent::new(KeyCode::Char('b'), KeyModifiers::ALT), b"\x1bb".to_vec())]
+    #[case(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE), b"\r".to_vec())]
+    #[case(KeyEvent::new(KeyCode::Backspace, KeyModifiers::NONE), vec![0x7f])]
+    #[case(KeyEvent::new(KeyCode::Up, KeyModifiers::NONE), b"\x1b[A".to_vec())]
+    #[case(KeyEvent::new(KeyCode::F(1), KeyModifiers::NONE), vec![])]
+    fn test_key_to_bytes(#[case] key: KeyEvent, #[case] expected: Vec<u8>) {
+        assert_eq!(key_to_bytes(key), expected);
+    }
+
+    #[rstest]
+    #[case(
+        r#"exec: "/bin/bash": stat /bin/bash: no such file or directory: unknown"#,
+        true
+    )]
+    #[case(r#"executable file not found in $PATH"#, true)]
+    #[case("command terminated with non-zero exit code: error executing command [/bin/sh], exit code 1", false)]
+    fn test_is_missing_command(#[case] message: &str, #[case] expected: bool) {
+        assert_eq!(is_missing_command(message), expected);
+    }
+}
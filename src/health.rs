@@ -0,0 +1,243 @@
+use serde::{Deserialize, Serialize};
+
+use crate::table::{ColumnDefinition, ResourceRow};
+
+/// How healthy the object in a row looks, which determines the row's color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Health {
+    /// Rendered normally; useful for overriding the built-in rules.
+    Healthy,
+    Warning,
+    Error,
+    /// Finished, like a completed pod, so rendered dimly.
+    Done,
+}
+
+/// A rule that decides the health of the rows it matches. A rule matches a row if all of its
+/// criteria do.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct HealthRule {
+    pub(crate) health: Health,
+    /// The name of a table column to look at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) column: Option<String>,
+    /// Match if the column's cell contains any of these.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) values: Vec<String>,
+    /// Match if the column's cell is a ratio like `1/3` (as in Ready columns) that is short of
+    /// its total.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) partial: bool,
+    /// The type of a condition in the object's `status.conditions`. Matching conditions needs
+    /// the whole object, which is then fetched with the table.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) condition: Option<String>,
+    /// Match if the condition has this status, `True` if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) condition_status: Option<String>,
+}
+
+impl HealthRule {
+    fn column(health: Health, column: &str, values: &[&str]) -> Self {
+        Self {
+            health,
+            column: Some(column.to_string()),
+            values: values.iter().map(|v| v.to_string()).collect(),
+            partial: false,
+            condition: None,
+            condition_status: None,
+        }
+    }
+
+    /// Problems with the rule, which would otherwise never (or always) match.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        match (&self.column, self.values.is_empty(), self.partial) {
+            (None, _, _) if self.condition.is_none() => Err("needs a column or a condition".into()),
+            (None, false, _) | (None, _, true) => Err("values and partial need a column".into()),
+            (Some(_), true, false) => Err("a column needs values or partial".into()),
+            _ if self.condition.is_none() && self.condition_status.is_some() => {
+                Err("condition-status needs a condition".into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn matches(&self, row: &ResourceRow, columns: &[ColumnDefinition]) -> bool {
+        let column_matches = self.column.as_ref().is_none_or(|name| {
+            let Some(cell) = columns
+                .iter()
+                .position(|c| c.name.eq_ignore_ascii_case(name))
+                .and_then(|idx| row.cells.get(idx))
+                .map(|c| c.to_string())
+            else {
+                return false;
+            };
+            (self.values.is_empty() || self.values.iter().any(|v| cell.contains(v.as_str())))
+                && (!self.partial || is_partial(&cell))
+        });
+        let condition_matches = self.condition.as_ref().is_none_or(|condition| {
+            let status = self.condition_status.as_deref().unwrap_or("True");
+            row.object
+                .as_ref()
+                .and_then(|o| o.data["status"]["conditions"].as_array())
+                .is_some_and(|conditions| {
+                    conditions.iter().any(|c| {
+                        c["type"].as_str() == Some(condition.as_str())
+                            && c["status"].as_str() == Some(status)
+                    })
+                })
+        });
+        column_matches && condition_matches
+    }
+}
+
+/// Whether a cell like `1/3` has fewer than its total.
+fn is_partial(cell: &str) -> bool {
+    let Some((ready, total)) = cell.split_once('/') else {
+        return false;
+    };
+    match (ready.trim().parse::<u64>(), total.trim().parse::<u64>()) {
+        (Ok(ready), Ok(total)) => ready < total,
+        _ => false,
+    }
+}
+
+/// The health of a row according to the first rule that matches it, if any.
+pub(crate) fn row_health<'a>(
+    rules: impl IntoIterator<Item = &'a HealthRule>,
+    row: &ResourceRow,
+    columns: &[ColumnDefinition],
+) -> Option<Health> {
+    rules
+        .into_iter()
+        .find(|rule| rule.matches(row, columns))
+        .map(|rule| rule.health)
+}
+
+/// The built-in rules, by the name of the resource they apply to.
+pub(crate) fn default_rules() -> Vec<(&'static str, Vec<HealthRule>)> {
+    use Health::*;
+    vec![
+        (
+            "pods",
+            vec![
+                HealthRule::column(
+                    Error,
+                    "Status",
+                    &[
+                        "CrashLoopBackOff",
+                        "Error",
+                        "ErrImagePull",
+                        "ImagePullBackOff",
+                        "OOMKilled",
+                        "Evicted",
+                        "Failed",
+                    ],
+                ),
+                HealthRule::column(
+                    Warning,
+                    "Status",
+                    &[
+                        "Pending",
+                        "ContainerCreating",
+                        "Init:",
+                        "Terminating",
+                        "Unknown",
+                    ],
+                ),
+                HealthRule::column(Done, "Status", &["Completed", "Succeeded"]),
+            ],
+        ),
+        (
+            "nodes",
+            vec![HealthRule::column(
+                Error,
+                "Status",
+                &["NotReady", "Unknown"],
+            )],
+        ),
+        (
+            "deployments",
+            vec![HealthRule {
+                partial: true,
+                ..HealthRule::column(Warning, "Ready", &[])
+            }],
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+    use crate::table::ResourceTable;
+
+    fn table() -> ResourceTable {
+        serde_json::from_value(serde_json::json!({
+            "columnDefinitions": [
+                {"name": "Name", "type": "string", "description": "", "format": "name", "priority": 0},
+                {"name": "Ready", "type": "string", "description": "", "format": "", "priority": 0},
+                {"name": "Status", "type": "string", "description": "", "format": "", "priority": 0},
+            ],
+            "rows": [
+                {"cells": ["a", "1/1", "Running"]},
+                {"cells": ["b", "0/1", "CrashLoopBackOff"]},
+                {"cells": ["c", "0/1", "Pending"]},
+                {"cells": ["d", "0/1", "Completed"]},
+                {"cells": ["e", "1/1", "Running"], "object": {
+                    "metadata": {"name": "e"},
+                    "status": {"conditions": [{"type": "Degraded", "status": "True"}]},
+                }},
+            ],
+        }))
+        .unwrap()
+    }
+
+    #[rstest]
+    #[case(0, None)]
+    #[case(1, Some(Health::Error))]
+    #[case(2, Some(Health::Warning))]
+    #[case(3, Some(Health::Done))]
+    fn test_default_pod_rules(#[case] row: usize, #[case] expected: Option<Health>) {
+        let table = table();
+        let rules = default_rules().remove(0).1;
+        assert_eq!(
+            row_health(&rules, &table.rows[row], &table.column_definitions),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(0, None)]
+    #[case(1, Some(Health::Warning))]
+    #[case(4, Some(Health::Error))]
+    fn test_partial_and_condition_rules(#[case] row: usize, #[case] expected: Option<Health>) {
+        let table = table();
+        let rules = [
+            HealthRule {
+                condition: Some("Degraded".into()),
+                ..HealthRule::column(Health::Error, "Status", &["Running"])
+            },
+            HealthRule {
+                partial: true,
+                ..HealthRule::column(Health::Warning, "Ready", &[])
+            },
+        ];
+        assert_eq!(
+            row_health(&rules, &table.rows[row], &table.column_definitions),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case("1/3", true)]
+    #[case("3/3", false)]
+    #[case("0/0", false)]
+    #[case("Running", false)]
+    fn test_is_partial(#[case] cell: &str, #[case] expected: bool) {
+        assert_eq!(is_partial(cell), expected);
+    }
+}
@@ -0,0 +1,298 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// How many previous values of a field are remembered.
+const HISTORY_LEN: usize = 100;
+
+/// A single line of text being edited, with a cursor, readline-style keys and history.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct LineEditor {
+    text: String,
+    /// The position of the cursor, in chars.
+    cursor: usize,
+    /// The text before editing started, which cancelling reverts to.
+    original: String,
+    /// Previous values of the field, oldest first.
+    history: Vec<String>,
+    /// The history entry being shown, if any, and the text being edited before browsing history.
+    browsing: Option<(usize, String)>,
+}
+
+impl LineEditor {
+    /// Start editing `text`, with the cursor at its end.
+    pub(crate) fn new(text: &str, history: &[String]) -> Self {
+        Self {
+            text: text.to_string(),
+            cursor: text.chars().count(),
+            original: text.to_string(),
+            history: history.to_vec(),
+            browsing: None,
+        }
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub(crate) fn original(&self) -> &str {
+        &self.original
+    }
+
+    pub(crate) fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// The number of chars to scroll the text by to keep the cursor visible within `width`.
+    pub(crate) fn scroll(&self, width: usize) -> usize {
+        self.cursor.saturating_sub(width.saturating_sub(1))
+    }
+
+    /// Apply a key to the text, returning whether the key did anything.
+    pub(crate) fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Char('a') if ctrl => self.cursor = 0,
+            KeyCode::Char('e') if ctrl => self.cursor = self.len(),
+            KeyCode::Char('b') if ctrl => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Char('f') if ctrl => self.cursor = (self.cursor + 1).min(self.len()),
+            KeyCode::Char('b') if alt => self.cursor = self.word_start(),
+            KeyCode::Char('f') if alt => self.cursor = self.word_end(),
+            KeyCode::Char('w') if ctrl => self.delete_to(self.whitespace_word_start()),
+            KeyCode::Char('u') if ctrl => self.delete_to(0),
+            KeyCode::Char('k') if ctrl => self.delete_to(self.len()),
+            KeyCode::Char('d') if ctrl => self.delete_to((self.cursor + 1).min(self.len())),
+            KeyCode::Char('h') if ctrl => self.delete_to(self.cursor.saturating_sub(1)),
+            KeyCode::Char(c) if !ctrl && !alt => self.insert(c),
+            KeyCode::Left if ctrl => self.cursor = self.word_start(),
+            KeyCode::Right if ctrl => self.cursor = self.word_end(),
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.len()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.len(),
+            KeyCode::Backspace if ctrl || alt => self.delete_to(self.word_start()),
+            KeyCode::Backspace => self.delete_to(self.cursor.saturating_sub(1)),
+            KeyCode::Delete => self.delete_to((self.cursor + 1).min(self.len())),
+            KeyCode::Up => self.browse_history(true),
+            KeyCode::Down => self.browse_history(false),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Apply a key to the text like [`Self::handle_key`], but only type the chars that `accepts`,
+    /// for fields like port numbers.
+    pub(crate) fn handle_key_accepting(
+        &mut self,
+        key: KeyEvent,
+        accepts: impl Fn(char) -> bool,
+    ) -> bool {
+        let typed = !key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+        match key.code {
+            KeyCode::Char(c) if typed && !accepts(c) => false,
+            _ => self.handle_key(key),
+        }
+    }
+
+    /// Insert pasted text at the cursor, dropping line breaks and other control characters.
+    pub(crate) fn paste(&mut self, text: &str) {
+        for c in text.chars().filter(|c| !c.is_control()) {
+            self.insert(c);
+        }
+    }
+
+    /// Add the edited text to the end of the history, unless it is empty or a repeat.
+    pub(crate) fn remember(&self, history: &mut Vec<String>) {
+        if self.text.is_empty() || history.last() == Some(&self.text) {
+            return;
+        }
+        history.push(self.text.clone());
+        if history.len() > HISTORY_LEN {
+            history.remove(0);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    /// The byte offset of the char at `idx`.
+    fn byte_idx(&self, idx: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(idx)
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+
+    fn insert(&mut self, c: char) {
+        let idx = self.byte_idx(self.cursor);
+        self.text.insert(idx, c);
+        self.cursor += 1;
+    }
+
+    /// Delete the chars between the cursor and `idx`, leaving the cursor at the start of the gap.
+    fn delete_to(&mut self, idx: usize) {
+        let (start, end) = (self.cursor.min(idx), self.cursor.max(idx));
+        let range = self.byte_idx(start)..self.byte_idx(end);
+        self.text.replace_range(range, "");
+        self.cursor = start;
+    }
+
+    /// Where the word before the cursor starts, where words are runs of alphanumerics.
+    fn word_start(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut idx = self.cursor;
+        while idx > 0 && !chars[idx - 1].is_alphanumeric() {
+            idx -= 1;
+        }
+        while idx > 0 && chars[idx - 1].is_alphanumeric() {
+            idx -= 1;
+        }
+        idx
+    }
+
+    /// Where the word after the cursor ends, where words are runs of alphanumerics.
+    fn word_end(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut idx = self.cursor;
+        while idx < chars.len() && !chars[idx].is_alphanumeric() {
+            idx += 1;
+        }
+        while idx < chars.len() && chars[idx].is_alphanumeric() {
+            idx += 1;
+        }
+        idx
+    }
+
+    /// Where the whitespace-delimited word before the cursor starts, for Ctrl-W.
+    fn whitespace_word_start(&self) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut idx = self.cursor;
+        while idx > 0 && chars[idx - 1].is_whitespace() {
+            idx -= 1;
+        }
+        while idx > 0 && !chars[idx - 1].is_whitespace() {
+            idx -= 1;
+        }
+        idx
+    }
+
+    /// Replace the text with the previous (or next) history entry. Going past the newest entry
+    /// returns to the text that was being edited.
+    fn browse_history(&mut self, older: bool) {
+        let next = match (&self.browsing, older) {
+            (None, true) => self.history.len().checked_sub(1),
+            (None, false) => return,
+            (Some((idx, _)), true) => Some(idx.saturating_sub(1)),
+            (Some((idx, _)), false) => Some(idx + 1).filter(|&i| i < self.history.len()),
+        };
+        let draft = match self.browsing.take() {
+            Some((_, draft)) => draft,
+            None => self.text.clone(),
+        };
+        self.text = match next {
+            Some(idx) => {
+                let entry = self.history[idx].clone();
+                self.browsing = Some((idx, draft));
+                entry
+            }
+            None => draft,
+        };
+        self.cursor = self.len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    /// Type the keys into an editor of `text`, with the cursor at `|` in the text.
+    fn edit(text: &str, keys: &[KeyEvent]) -> String {
+        let cursor = text.find('|').unwrap();
+        let mut editor = LineEditor::new(&text.replace('|', ""), &[]);
+        editor.cursor = text[..cursor].chars().count();
+        for key in keys {
+            editor.handle_key(*key);
+        }
+        let idx = editor.byte_idx(editor.cursor);
+        format!("{}|{}", &editor.text[..idx], &editor.text[idx..])
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn ctrl(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::CONTROL)
+    }
+
+    fn alt(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::ALT)
+    }
+
+    #[rstest]
+    #[case("web|", &[key(KeyCode::Char('s'))], "webs|")]
+    #[case("w|b", &[key(KeyCode::Char('e'))], "we|b")]
+    #[case("web|", &[key(KeyCode::Left), key(KeyCode::Backspace)], "w|b")]
+    #[case("web|", &[key(KeyCode::Home), key(KeyCode::Delete)], "|eb")]
+    #[case("|web", &[ctrl(KeyCode::Char('e'))], "web|")]
+    #[case("kube-sys|tem", &[ctrl(KeyCode::Char('u'))], "|tem")]
+    #[case("kube-sys|tem", &[ctrl(KeyCode::Char('k'))], "kube-sys|")]
+    #[case("app=web tier=fr|", &[ctrl(KeyCode::Char('w'))], "app=web |")]
+    #[case("kube-system|", &[alt(KeyCode::Backspace)], "kube-|")]
+    #[case("kube-system|", &[ctrl(KeyCode::Left)], "kube-|system")]
+    #[case("kube-system|", &[alt(KeyCode::Char('b')), alt(KeyCode::Char('b'))], "|kube-system")]
+    #[case("|kube-system", &[ctrl(KeyCode::Right)], "kube|-system")]
+    #[case("|kube-system", &[alt(KeyCode::Char('f')), alt(KeyCode::Char('f'))], "kube-system|")]
+    #[case("né|", &[key(KeyCode::Left), key(KeyCode::Char('e'))], "ne|é")]
+    fn test_handle_key(#[case] text: &str, #[case] keys: &[KeyEvent], #[case] expected: &str) {
+        assert_eq!(edit(text, keys), expected);
+    }
+
+    #[rstest]
+    fn test_paste_drops_line_breaks() {
+        let mut editor = LineEditor::new("", &[]);
+        editor.paste("kube-system\n");
+        assert_eq!(editor.text(), "kube-system");
+        assert_eq!(editor.cursor(), 11);
+    }
+
+    #[rstest]
+    fn test_browse_history() {
+        let history = vec!["a".to_string(), "b".to_string()];
+        let mut editor = LineEditor::new("draft", &history);
+        editor.handle_key(key(KeyCode::Up));
+        assert_eq!(editor.text(), "b");
+        editor.handle_key(key(KeyCode::Up));
+        editor.handle_key(key(KeyCode::Up));
+        assert_eq!(editor.text(), "a");
+        editor.handle_key(key(KeyCode::Down));
+        editor.handle_key(key(KeyCode::Down));
+        assert_eq!(editor.text(), "draft");
+        assert_eq!(editor.original(), "draft");
+    }
+
+    #[rstest]
+    #[case(vec![], "web", vec!["web"])]
+    #[case(vec!["web"], "web", vec!["web"])]
+    #[case(vec!["web"], "", vec!["web"])]
+    fn test_remember(#[case] history: Vec<&str>, #[case] text: &str, #[case] expected: Vec<&str>) {
+        let mut history = history.into_iter().map(String::from).collect();
+        LineEditor::new(text, &[]).remember(&mut history);
+        assert_eq!(history, expected);
+    }
+
+    #[rstest]
+    #[case(3, 10, 0)]
+    #[case(12, 10, 3)]
+    fn test_scroll(#[case] cursor: usize, #[case] width: usize, #[case] expected: usize) {
+        let editor = LineEditor {
+            cursor,
+            ..LineEditor::default()
+        };
+        assert_eq!(editor.scroll(width), expected);
+    }
+}
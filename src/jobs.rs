@@ -0,0 +1,216 @@
+use kube::{
+    api::{DynamicObject, Patch, PostParams},
+    Client,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    discovery::{DiscoveredAPIResource, Discovery},
+    state::ObjectRef,
+    table::{ColumnDefinition, ResourceRowCellValue, ResourceTable},
+    workloads::patch_params,
+    DynResult,
+};
+
+/// Marks a Job created from a CronJob by hand rather than on its schedule, as kubectl does.
+pub const INSTANTIATE_ANNOTATION: &str = "cronjob.kubernetes.io/instantiate";
+
+/// The longest name an object can have that is also used as a label value, like a Job's.
+const MAX_NAME_LENGTH: usize = 63;
+
+/// How many random characters the API server appends to a `generateName`.
+const GENERATED_SUFFIX_LENGTH: usize = 5;
+
+/// Prefixes the random suffix of a Job triggered from a CronJob.
+const MANUAL_PREFIX: &str = "-manual-";
+
+fn is_batch(resource: &DiscoveredAPIResource, plural: &str) -> bool {
+    resource.group == "batch" && resource.plural == plural
+}
+
+pub(crate) fn is_cronjob(target: &ObjectRef) -> bool {
+    is_batch(&target.resource, "cronjobs")
+}
+
+pub(crate) fn is_job(target: &ObjectRef) -> bool {
+    is_job_resource(&target.resource)
+}
+
+pub(crate) fn is_job_resource(resource: &DiscoveredAPIResource) -> bool {
+    is_batch(resource, "jobs")
+}
+
+/// Whether the CronJob's schedule is suspended.
+pub(crate) async fn is_suspended(client: Client, target: &ObjectRef) -> DynResult<bool> {
+    let cronjob = target.api(client).get(&target.name).await?;
+    Ok(cronjob.data["spec"]["suspend"]
+        .as_bool()
+        .unwrap_or_default())
+}
+
+/// Suspend (or resume) the CronJob's schedule.
+pub(crate) async fn set_suspend(
+    client: Client,
+    target: &ObjectRef,
+    suspend: bool,
+) -> DynResult<()> {
+    target
+        .api(client)
+        .patch(
+            &target.name,
+            &patch_params(),
+            &Patch::Merge(json!({"spec": {"suspend": suspend}})),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Create a Job from the CronJob's job template, like `kubectl create job --from=cronjob/x`,
+/// returning the Job's name.
+pub(crate) async fn trigger(
+    client: Client,
+    discovery: &Discovery,
+    target: &ObjectRef,
+) -> DynResult<String> {
+    let jobs = discovery
+        .get("jobs.batch")
+        .ok_or("jobs are not available")?;
+    let cronjob = target.api(client.clone()).get(&target.name).await?;
+    let job = job_from_cronjob(&cronjob)?;
+    let job = jobs
+        .api(client, target.namespace.as_deref())
+        .create(&PostParams::default(), &serde_json::from_value(job)?)
+        .await?;
+    Ok(job.metadata.name.unwrap_or_default())
+}
+
+/// A Job made from the CronJob's job template, named by the server after the CronJob with a
+/// random suffix and owned by the CronJob so that it is cleaned up with it.
+fn job_from_cronjob(cronjob: &DynamicObject) -> DynResult<Value> {
+    let name = cronjob
+        .metadata
+        .name
+        .as_deref()
+        .ok_or("cronjob has no name")?;
+    let uid = cronjob
+        .metadata
+        .uid
+        .as_deref()
+        .ok_or("cronjob has no uid")?;
+    let template = &cronjob.data["spec"]["jobTemplate"];
+    let base: String = name
+        .chars()
+        .take(MAX_NAME_LENGTH - MANUAL_PREFIX.len() - GENERATED_SUFFIX_LENGTH)
+        .collect();
+
+    let mut annotations = template["metadata"]["annotations"].clone();
+    if !annotations.is_object() {
+        annotations = json!({});
+    }
+    annotations[INSTANTIATE_ANNOTATION] = "manual".into();
+
+    Ok(json!({
+        "apiVersion": "batch/v1",
+        "kind": "Job",
+        "metadata": {
+            "generateName": format!("{base}{MANUAL_PREFIX}"),
+            "namespace": cronjob.metadata.namespace,
+            "labels": template["metadata"]["labels"],
+            "annotations": annotations,
+            "ownerReferences": [{
+                "apiVersion": cronjob.types.as_ref().map_or("batch/v1", |t| t.api_version.as_str()),
+                "kind": "CronJob",
+                "name": name,
+                "uid": uid,
+                "controller": true,
+                "blockOwnerDeletion": true,
+            }],
+        },
+        "spec": template["spec"],
+    }))
+}
+
+/// Add columns for how many of each Job's pods are active and have failed, next to the server's
+/// Completions column, to a table of jobs whose rows carry their whole objects.
+pub(crate) fn add_job_columns(table: &mut ResourceTable) {
+    for (name, description, field) in [
+        ("Active", "The number of pending or running pods", "active"),
+        ("Failed", "The number of pods that failed", "failed"),
+    ] {
+        table.insert_column(ColumnDefinition::computed(name, description, 0), |row| {
+            let count = row
+                .object
+                .as_ref()
+                .and_then(|o| o.data["status"][field].as_f64())
+                .unwrap_or_default();
+            ResourceRowCellValue::Number(count)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn cronjob(name: &str) -> DynamicObject {
+        serde_json::from_value(json!({
+            "apiVersion": "batch/v1",
+            "kind": "CronJob",
+            "metadata": {"name": name, "namespace": "default", "uid": "c"},
+            "spec": {
+                "schedule": "@daily",
+                "jobTemplate": {
+                    "metadata": {"labels": {"app": "backup"}},
+                    "spec": {"template": {"spec": {"containers": [{"name": "backup", "image": "backup"}]}}},
+                },
+            },
+        }))
+        .unwrap()
+    }
+
+    #[rstest]
+    fn test_job_from_cronjob() {
+        let job = job_from_cronjob(&cronjob("backup")).unwrap();
+        assert_eq!(job["metadata"]["generateName"], "backup-manual-");
+        assert_eq!(job["metadata"]["namespace"], "default");
+        assert_eq!(job["metadata"]["labels"], json!({"app": "backup"}));
+        assert_eq!(
+            job["metadata"]["annotations"],
+            json!({INSTANTIATE_ANNOTATION: "manual"})
+        );
+        assert_eq!(job["metadata"]["ownerReferences"][0]["uid"], "c");
+        assert_eq!(
+            job["spec"]["template"]["spec"]["containers"][0]["image"],
+            "backup"
+        );
+    }
+
+    #[rstest]
+    fn test_job_name_is_truncated() {
+        let job = job_from_cronjob(&cronjob(&"x".repeat(70))).unwrap();
+        assert_eq!(
+            job["metadata"]["generateName"].as_str().unwrap().len() + GENERATED_SUFFIX_LENGTH,
+            MAX_NAME_LENGTH
+        );
+    }
+
+    #[rstest]
+    fn test_add_job_columns() {
+        let mut table: ResourceTable = serde_json::from_value(json!({
+            "columnDefinitions": [
+                {"name": "Name", "type": "string", "description": "", "format": "name", "priority": 0},
+                {"name": "Completions", "type": "string", "description": "", "format": "", "priority": 0},
+                {"name": "Age", "type": "string", "description": "", "format": "date", "priority": 0},
+            ],
+            "rows": [
+                {"cells": ["a", "0/1", "1m"], "object": {"metadata": {"name": "a"}, "status": {"active": 1, "failed": 2}}},
+            ],
+        }))
+        .unwrap();
+        add_job_columns(&mut table);
+        let cells: Vec<String> = table.rows[0].cells.iter().map(|c| c.to_string()).collect();
+        assert_eq!(cells, vec!["a", "0/1", "1", "2", "1m"]);
+    }
+}
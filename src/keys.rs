@@ -0,0 +1,579 @@
+use std::{
+    fmt::{Display, Formatter},
+    str::FromStr,
+};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// A key press along with the modifiers held while pressing it, like `ctrl-t`.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    pub const fn plain(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
+}
+
+impl From<KeyEvent> for KeyChord {
+    fn from(key: KeyEvent) -> Self {
+        let mut modifiers = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        // Shift is already reflected in the character (or in BackTab), except for keys like arrows.
+        if key.modifiers.contains(KeyModifiers::SHIFT)
+            && !matches!(key.code, KeyCode::Char(_) | KeyCode::BackTab)
+        {
+            modifiers |= KeyModifiers::SHIFT;
+        }
+        Self::new(key.code, modifiers)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = s.split('-').collect();
+        // A trailing "-" means the chord is for the minus key itself, like "ctrl--".
+        if s.ends_with("--") || s == "-" {
+            parts.truncate(parts.len() - 2);
+            parts.push("-");
+        }
+        let (key, modifier_names) = parts.split_last().ok_or("empty key")?;
+
+        let mut modifiers = KeyModifiers::NONE;
+        for m in modifier_names {
+            modifiers |= match m.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier {m:?} in key {s:?}")),
+            };
+        }
+
+        let mut chars = key.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match key.to_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" if modifiers.contains(KeyModifiers::SHIFT) => {
+                    modifiers -= KeyModifiers::SHIFT;
+                    KeyCode::BackTab
+                }
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                f if f.starts_with('f') && f[1..].parse::<u8>().is_ok() => {
+                    KeyCode::F(f[1..].parse().unwrap())
+                }
+                _ => return Err(format!("unknown key {key:?} in {s:?}")),
+            },
+        };
+
+        // Shifted characters are written as the character itself, like "R".
+        if let KeyCode::Char(c) = code {
+            if modifiers.contains(KeyModifiers::SHIFT) {
+                modifiers -= KeyModifiers::SHIFT;
+                return Ok(Self::new(KeyCode::Char(c.to_ascii_uppercase()), modifiers));
+            }
+        }
+
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl Display for KeyChord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "ctrl-"),
+            (KeyModifiers::ALT, "alt-"),
+            (KeyModifiers::SHIFT, "shift-"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{name}")?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::BackTab => write!(f, "shift-tab"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            code => write!(f, "{}", code.to_string().to_lowercase()),
+        }
+    }
+}
+
+/// Define the [`Command`] enum along with its configuration names and descriptions.
+macro_rules! commands {
+    ($($variant:ident => $name:literal, $description:literal;)*) => {
+        /// Something the user can do, which can be bound to keys.
+        #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+        pub enum Command {
+            $($variant,)*
+        }
+
+        impl Command {
+            pub const ALL: &[Command] = &[$(Command::$variant,)*];
+
+            /// The name of the command, as used in the configuration file.
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(Command::$variant => $name,)*
+                }
+            }
+
+            /// What the command does, as shown in the help overlay.
+            pub fn description(&self) -> &'static str {
+                match self {
+                    $(Command::$variant => $description,)*
+                }
+            }
+        }
+    };
+}
+
+commands! {
+    Quit => "quit", "Quit flotilla";
+    Help => "help", "Show the keys available here";
+    Palette => "command-palette", "Run a command, or switch resource, namespace or context";
+    Refresh => "refresh", "Fetch the displayed resources again";
+    NewTab => "new-tab", "Open a new tab";
+    CloseTab => "close-tab", "Close the current tab";
+    DuplicateTab => "duplicate-tab", "Open a copy of the current tab";
+    RenameTab => "rename-tab", "Set the title of the current tab";
+    MoveTabLeft => "move-tab-left", "Move the current tab left";
+    MoveTabRight => "move-tab-right", "Move the current tab right";
+    GoToTab1 => "go-to-tab-1", "Switch to tab 1";
+    GoToTab2 => "go-to-tab-2", "Switch to tab 2";
+    GoToTab3 => "go-to-tab-3", "Switch to tab 3";
+    GoToTab4 => "go-to-tab-4", "Switch to tab 4";
+    GoToTab5 => "go-to-tab-5", "Switch to tab 5";
+    GoToTab6 => "go-to-tab-6", "Switch to tab 6";
+    GoToTab7 => "go-to-tab-7", "Switch to tab 7";
+    GoToTab8 => "go-to-tab-8", "Switch to tab 8";
+    GoToTab9 => "go-to-tab-9", "Switch to tab 9";
+    NextTab => "next-tab", "Switch to the next tab";
+    PreviousTab => "previous-tab", "Switch to the previous tab";
+    SplitRight => "split-right", "Show a copy of the current tab in a new pane to the right";
+    SplitDown => "split-down", "Show a copy of the current tab in a new pane below";
+    NextPane => "next-pane", "Move focus to the next pane";
+    PreviousPane => "previous-pane", "Move focus to the previous pane";
+    ClosePane => "close-pane", "Close the focused pane, keeping its tab open";
+    EditFilter => "edit-filter", "Edit the name filter";
+    EditResource => "edit-resource", "Edit the resource";
+    EditNamespace => "edit-namespace", "Edit the namespace";
+    FinishEditing => "finish-editing", "Stop editing the field";
+    CancelEditing => "cancel-editing", "Stop editing the field, restoring its previous value";
+    SelectNext => "select-next", "Select the next row";
+    SelectPrevious => "select-previous", "Select the previous row";
+    Back => "back", "Return to the previous view";
+    Edit => "edit", "Edit the selected object in $EDITOR";
+    ToggleEvents => "toggle-events", "Show or hide events for the selected object";
+    Scale => "scale", "Scale the selected workload";
+    RolloutRestart => "rollout-restart", "Restart the selected workload's pods";
+    Exec => "exec", "Open a shell in the selected pod";
+    PortForward => "port-forward", "Forward a local port to the selected pod or service";
+    FocusPortForwards => "focus-port-forwards", "Move focus to or from the port-forwards panel";
+    StopPortForward => "stop-port-forward", "Stop the selected port-forward";
+    ToggleTree => "toggle-tree", "Switch between the table and owner tree views";
+    ToggleWide => "toggle-wide", "Show or hide the table's extra columns";
+    SortNextColumn => "sort-next-column", "Sort the table by the next column";
+    SortPreviousColumn => "sort-previous-column", "Sort the table by the previous column";
+    ReverseSort => "reverse-sort", "Reverse the order the table is sorted in";
+    Expand => "expand", "Expand the selected tree node";
+    Collapse => "collapse", "Collapse the selected tree node";
+    GoTo => "go-to", "Jump to an object related to the selected object";
+    ToggleMark => "toggle-mark", "Mark or unmark the selected row, to act on several objects at once";
+    ClearMarks => "clear-marks", "Unmark every row";
+    EditMetadata => "edit-metadata", "Edit the labels and annotations of the marked (or selected) objects";
+    Cordon => "cordon", "Mark the selected (or marked) nodes as unschedulable";
+    Uncordon => "uncordon", "Mark the selected (or marked) nodes as schedulable";
+    Drain => "drain", "Cordon the selected (or marked) nodes and evict their pods";
+    RolloutHistory => "rollout-history", "Show the revisions and rollout progress of the selected deployment";
+    ToggleSuspend => "toggle-suspend", "Suspend or resume the selected cron job";
+    Trigger => "trigger", "Create a job from the selected cron job now";
+    Logs => "logs", "Show the logs of the selected pod, or of the selected job's pods";
+    ViewData => "view-data", "View or edit the keys of the selected config map or secret";
+    ShowOverview => "overview", "Show the overview of the current cluster";
+    Open => "open", "Open the selected overview item in a new tab";
+}
+
+impl Command {
+    /// The index of the tab that the command switches to, if it is one of the `go-to-tab-N` commands.
+    pub fn tab_index(&self) -> Option<usize> {
+        let idx = match self {
+            Command::GoToTab1 => 0,
+            Command::GoToTab2 => 1,
+            Command::GoToTab3 => 2,
+            Command::GoToTab4 => 3,
+            Command::GoToTab5 => 4,
+            Command::GoToTab6 => 5,
+            Command::GoToTab7 => 6,
+            Command::GoToTab8 => 7,
+            Command::GoToTab9 => 8,
+            _ => return None,
+        };
+        Some(idx)
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Command::ALL
+            .iter()
+            .find(|c| c.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown command {s:?}"))
+    }
+}
+
+/// What the user is interacting with, which determines the keys that are available.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Mode {
+    /// Keys that work in every mode except [`Mode::Editing`].
+    Global,
+    Table,
+    /// The owner tree view of a tab, which also has the keys of [`Mode::Table`].
+    Tree,
+    /// The cluster overview, which also has the keys of [`Mode::Table`].
+    Overview,
+    PortForwards,
+    /// Typing into a text field, where unbound keys are typed into the field.
+    Editing,
+}
+
+impl Mode {
+    pub const ALL: &[Mode] = &[
+        Mode::Global,
+        Mode::Table,
+        Mode::Tree,
+        Mode::Overview,
+        Mode::PortForwards,
+        Mode::Editing,
+    ];
+
+    /// The name of the mode, as used in the configuration file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Global => "global",
+            Mode::Table => "table",
+            Mode::Tree => "tree",
+            Mode::Overview => "overview",
+            Mode::PortForwards => "port-forwards",
+            Mode::Editing => "editing",
+        }
+    }
+
+    /// The mode whose bindings apply in this mode, unless this mode binds the same chord.
+    fn parent(&self) -> Option<Mode> {
+        match self {
+            Mode::Global | Mode::Editing => None,
+            Mode::Table | Mode::PortForwards => Some(Mode::Global),
+            Mode::Tree | Mode::Overview => Some(Mode::Table),
+        }
+    }
+
+    /// This mode followed by the modes it inherits bindings from.
+    fn lineage(self) -> impl Iterator<Item = Mode> {
+        std::iter::successors(Some(self), Mode::parent)
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Mode::ALL
+            .iter()
+            .find(|m| m.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown mode {s:?}"))
+    }
+}
+
+/// Bindings from key chords to the commands they run, in each mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    bindings: Vec<(Mode, KeyChord, Command)>,
+}
+
+impl Keymap {
+    /// The command bound to the chord in the mode, or in the modes it inherits from.
+    pub fn get(&self, mode: Mode, chord: &KeyChord) -> Option<Command> {
+        mode.lineage().find_map(|m| {
+            self.bindings
+                .iter()
+                .find(|(bm, c, _)| *bm == m && c == chord)
+                .map(|(_, _, command)| *command)
+        })
+    }
+
+    /// Every command available in the mode, with the chords bound to it, in binding order.
+    pub fn available(&self, mode: Mode) -> Vec<(Command, Vec<KeyChord>)> {
+        let mut available: Vec<(Command, Vec<KeyChord>)> = vec![];
+        for m in mode.lineage() {
+            for (_, chord, command) in self.bindings.iter().filter(|(bm, ..)| *bm == m) {
+                // Skip chords that are shadowed by a binding in a more specific mode.
+                if self.get(mode, chord) != Some(*command) {
+                    continue;
+                }
+                match available.iter_mut().find(|(c, _)| c == command) {
+                    Some((_, chords)) => chords.push(*chord),
+                    None => available.push((*command, vec![*chord])),
+                }
+            }
+        }
+        available
+    }
+
+    /// Replace the bindings of `command` in `mode` with `chords`,
+    /// unbinding those chords from any other command in that mode.
+    pub fn rebind(&mut self, mode: Mode, command: Command, chords: &[KeyChord]) {
+        self.bindings
+            .retain(|(m, chord, c)| *m != mode || (*c != command && !chords.contains(chord)));
+        self.bindings
+            .extend(chords.iter().map(|chord| (mode, *chord, command)));
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use Command::*;
+        use KeyCode::*;
+        use Mode::*;
+
+        let ctrl = |c| KeyChord::new(Char(c), KeyModifiers::CONTROL);
+        let key = |c| KeyChord::plain(Char(c));
+
+        Self {
+            bindings: vec![
+                (Global, ctrl('c'), Quit),
+                (Global, key('?'), Help),
+                (Global, key(':'), Palette),
+                (Global, ctrl('r'), Refresh),
+                (Global, ctrl('t'), NewTab),
+                (Global, ctrl('w'), CloseTab),
+                (Global, ctrl('d'), DuplicateTab),
+                (Global, key('T'), RenameTab),
+                (Global, key('{'), MoveTabLeft),
+                (Global, key('}'), MoveTabRight),
+                (Global, key('1'), GoToTab1),
+                (Global, key('2'), GoToTab2),
+                (Global, key('3'), GoToTab3),
+                (Global, key('4'), GoToTab4),
+                (Global, key('5'), GoToTab5),
+                (Global, key('6'), GoToTab6),
+                (Global, key('7'), GoToTab7),
+                (Global, key('8'), GoToTab8),
+                (Global, key('9'), GoToTab9),
+                (Global, KeyChord::plain(Tab), NextTab),
+                (Global, KeyChord::plain(BackTab), PreviousTab),
+                (Global, key('|'), SplitRight),
+                (Global, key('_'), SplitDown),
+                (Global, ctrl('n'), NextPane),
+                (Global, ctrl('p'), PreviousPane),
+                (Global, ctrl('x'), ClosePane),
+                (Global, key('f'), EditFilter),
+                (Global, key('r'), EditResource),
+                (Global, key('n'), EditNamespace),
+                (Global, key('e'), Edit),
+                (Global, key('E'), ToggleEvents),
+                (Global, key('s'), Scale),
+                (Global, key('R'), RolloutRestart),
+                (Global, key('x'), Exec),
+                (Global, key('p'), PortForward),
+                (Global, key('F'), FocusPortForwards),
+                (Global, key('o'), ToggleTree),
+                (Global, key('w'), ToggleWide),
+                (Global, key('g'), GoTo),
+                (Global, key('v'), ViewData),
+                (Global, key('L'), EditMetadata),
+                (Global, key('C'), Cordon),
+                (Global, key('U'), Uncordon),
+                (Global, key('D'), Drain),
+                (Global, key('H'), RolloutHistory),
+                (Global, key('Z'), ToggleSuspend),
+                (Global, key('t'), Trigger),
+                (Global, key('V'), Logs),
+                (Global, key('O'), ShowOverview),
+                (Table, KeyChord::plain(Down), SelectNext),
+                (Table, key('j'), SelectNext),
+                (Table, KeyChord::plain(Up), SelectPrevious),
+                (Table, key('k'), SelectPrevious),
+                (Table, KeyChord::plain(Esc), Back),
+                (Table, key('>'), SortNextColumn),
+                (Table, key('<'), SortPreviousColumn),
+                (Table, key('!'), ReverseSort),
+                (Table, key(' '), ToggleMark),
+                (Table, key('u'), ClearMarks),
+                (Tree, KeyChord::plain(Right), Expand),
+                (Tree, key('l'), Expand),
+                (Tree, KeyChord::plain(Left), Collapse),
+                (Tree, key('h'), Collapse),
+                (Overview, KeyChord::plain(Enter), Open),
+                (PortForwards, KeyChord::plain(Down), SelectNext),
+                (PortForwards, key('j'), SelectNext),
+                (PortForwards, KeyChord::plain(Up), SelectPrevious),
+                (PortForwards, key('k'), SelectPrevious),
+                (PortForwards, KeyChord::plain(Esc), Back),
+                (PortForwards, key('d'), StopPortForward),
+                (PortForwards, KeyChord::plain(Delete), StopPortForward),
+                (Editing, ctrl('c'), Quit),
+                (Editing, KeyChord::plain(Enter), FinishEditing),
+                (Editing, KeyChord::plain(Esc), CancelEditing),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("a", KeyChord::plain(KeyCode::Char('a')))]
+    #[case("R", KeyChord::plain(KeyCode::Char('R')))]
+    #[case("shift-r", KeyChord::plain(KeyCode::Char('R')))]
+    #[case("ctrl-t", KeyChord::new(KeyCode::Char('t'), KeyModifiers::CONTROL))]
+    #[case("Ctrl-Alt-x", KeyChord::new(KeyCode::Char('x'), KeyModifiers::CONTROL | KeyModifiers::ALT))]
+    #[case("ctrl--", KeyChord::new(KeyCode::Char('-'), KeyModifiers::CONTROL))]
+    #[case("-", KeyChord::plain(KeyCode::Char('-')))]
+    #[case("tab", KeyChord::plain(KeyCode::Tab))]
+    #[case("shift-tab", KeyChord::plain(KeyCode::BackTab))]
+    #[case("shift-up", KeyChord::new(KeyCode::Up, KeyModifiers::SHIFT))]
+    #[case("space", KeyChord::plain(KeyCode::Char(' ')))]
+    #[case("f5", KeyChord::plain(KeyCode::F(5)))]
+    #[case("Enter", KeyChord::plain(KeyCode::Enter))]
+    fn test_parse_key_chord(#[case] s: &str, #[case] expected: KeyChord) {
+        assert_eq!(s.parse::<KeyChord>(), Ok(expected));
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("hyper-a")]
+    #[case("ctrl-")]
+    #[case("florp")]
+    #[case("ctrl-florp")]
+    fn test_parse_invalid_key_chord(#[case] s: &str) {
+        assert!(s.parse::<KeyChord>().is_err());
+    }
+
+    #[rstest]
+    #[case("a")]
+    #[case("R")]
+    #[case("ctrl-t")]
+    #[case("ctrl-alt-x")]
+    #[case("shift-tab")]
+    #[case("shift-up")]
+    #[case("space")]
+    #[case("f5")]
+    #[case("enter")]
+    #[case("esc")]
+    #[case("pagedown")]
+    fn test_key_chord_display_round_trips(#[case] s: &str) {
+        assert_eq!(s.parse::<KeyChord>().unwrap().to_string(), s);
+    }
+
+    #[rstest]
+    #[case(KeyEvent::new(KeyCode::Char('R'), KeyModifiers::SHIFT), "R")]
+    #[case(KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT), "shift-tab")]
+    #[case(KeyEvent::new(KeyCode::Up, KeyModifiers::SHIFT), "shift-up")]
+    #[case(KeyEvent::new(KeyCode::Char('t'), KeyModifiers::CONTROL), "ctrl-t")]
+    fn test_key_chord_from_key_event(#[case] key: KeyEvent, #[case] expected: &str) {
+        assert_eq!(KeyChord::from(key).to_string(), expected);
+    }
+
+    #[rstest]
+    fn test_rebind_steals_chords() {
+        let mut keymap = Keymap::default();
+        let e = KeyChord::plain(KeyCode::Char('e'));
+        keymap.rebind(Mode::Global, Command::Exec, &[e]);
+        assert_eq!(keymap.get(Mode::Global, &e), Some(Command::Exec));
+        assert!(keymap.bindings.iter().all(|(_, _, c)| *c != Command::Edit));
+        assert_eq!(
+            keymap.get(Mode::Global, &KeyChord::plain(KeyCode::Char('x'))),
+            None
+        );
+    }
+
+    #[rstest]
+    #[case(Mode::Table, "j", Some(Command::SelectNext))]
+    #[case(Mode::Table, "ctrl-t", Some(Command::NewTab))]
+    #[case(Mode::Table, "l", None)]
+    #[case(Mode::Tree, "l", Some(Command::Expand))]
+    #[case(Mode::Tree, "j", Some(Command::SelectNext))]
+    #[case(Mode::Tree, "tab", Some(Command::NextTab))]
+    #[case(Mode::PortForwards, "d", Some(Command::StopPortForward))]
+    #[case(Mode::Editing, "ctrl-t", None)]
+    #[case(Mode::Editing, "tab", None)]
+    #[case(Mode::Editing, "j", None)]
+    #[case(Mode::Editing, "enter", Some(Command::FinishEditing))]
+    #[case(Mode::Editing, "esc", Some(Command::CancelEditing))]
+    #[case(Mode::Editing, "ctrl-w", None)]
+    fn test_get_inherits_bindings(
+        #[case] mode: Mode,
+        #[case] chord: &str,
+        #[case] expected: Option<Command>,
+    ) {
+        assert_eq!(
+            Keymap::default().get(mode, &chord.parse().unwrap()),
+            expected
+        );
+    }
+
+    #[rstest]
+    fn test_available_skips_shadowed_chords() {
+        let mut keymap = Keymap::default();
+        let o = KeyChord::plain(KeyCode::Char('o'));
+        keymap.rebind(Mode::Tree, Command::Collapse, &[o]);
+
+        let available = keymap.available(Mode::Tree);
+        let chords = |command| {
+            available
+                .iter()
+                .find(|(c, _)| *c == command)
+                .map(|(_, chords)| chords.clone())
+        };
+        assert_eq!(chords(Command::Collapse), Some(vec![o]));
+        assert_eq!(chords(Command::ToggleTree), None);
+        assert_eq!(
+            chords(Command::SelectNext),
+            Some(vec![
+                KeyChord::plain(KeyCode::Down),
+                KeyChord::plain(KeyCode::Char('j'))
+            ])
+        );
+        assert_eq!(
+            keymap.available(Mode::Table).first().map(|(c, _)| *c),
+            Some(Command::SelectNext)
+        );
+    }
+}
@@ -0,0 +1,110 @@
+use std::{
+    env,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use k8s_openapi::api::core::v1::Pod;
+use kube::{
+    api::{ListParams, LogParams},
+    Api, Client,
+};
+
+use crate::{
+    exec::container_names, jobs::is_job, portforward::label_selector, state::ObjectRef, DynResult,
+};
+
+/// How many of the most recent lines of each container's logs to show.
+const TAIL_LINES: i64 = 1000;
+
+/// Whether the target has logs: a pod, or a Job with pods.
+pub(crate) fn has_logs(target: &ObjectRef) -> bool {
+    (target.resource.group.is_empty() && target.resource.plural == "pods") || is_job(target)
+}
+
+/// The recent logs of every container of the target's pods, each under a heading if there
+/// are several.
+pub(crate) async fn logs(client: Client, target: &ObjectRef) -> DynResult<String> {
+    let namespace = target.namespace.as_deref().unwrap_or_default();
+    let api: Api<Pod> = Api::namespaced(client.clone(), namespace);
+    let pods = if is_job(target) {
+        let job = target.api(client).get(&target.name).await?;
+        let labels = serde_json::from_value(job.data["spec"]["selector"]["matchLabels"].clone())?;
+        api.list(&ListParams::default().labels(&label_selector(&labels)))
+            .await?
+            .items
+    } else {
+        vec![api.get(&target.name).await?]
+    };
+
+    let mut streams = vec![];
+    for pod in &pods {
+        let name = pod.metadata.name.as_deref().unwrap_or_default();
+        for container in container_names(pod) {
+            let params = LogParams {
+                container: Some(container.clone()),
+                tail_lines: Some(TAIL_LINES),
+                ..LogParams::default()
+            };
+            let logs = api
+                .logs(name, &params)
+                .await
+                .unwrap_or_else(|e| format!("error getting logs: {e}\n"));
+            streams.push((format!("{name}/{container}"), logs));
+        }
+    }
+    Ok(join_streams(streams))
+}
+
+fn join_streams(streams: Vec<(String, String)>) -> String {
+    match streams.as_slice() {
+        [] => "No pods\n".into(),
+        [(_, logs)] => logs.clone(),
+        _ => streams
+            .iter()
+            .map(|(source, logs)| format!("==> {source} <==\n{logs}\n"))
+            .collect(),
+    }
+}
+
+/// Show the text in the user's pager.
+///
+/// The terminal must already be restored to its normal mode before calling this.
+pub(crate) fn page(text: &str) -> DynResult<()> {
+    let pager = env::var("PAGER").unwrap_or_else(|_| "less".into());
+    let mut words = pager.split_whitespace();
+    let program = words.next().unwrap_or("less");
+
+    let mut child = Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager may quit before reading everything, which closes the pipe.
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    child.wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case(vec![], "No pods\n")]
+    #[case(vec![("p/c", "hello\n")], "hello\n")]
+    #[case(
+        vec![("p/a", "one\n"), ("p/b", "two\n")],
+        "==> p/a <==\none\n\n==> p/b <==\ntwo\n\n"
+    )]
+    fn test_join_streams(#[case] streams: Vec<(&str, &str)>, #[case] expected: &str) {
+        let streams = streams
+            .into_iter()
+            .map(|(s, l)| (s.to_string(), l.to_string()))
+            .collect();
+        assert_eq!(join_streams(streams), expected);
+    }
+}
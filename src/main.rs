@@ -1,24 +1,76 @@
+mod config;
+mod data;
+mod diff;
 mod discovery;
+mod edit;
+mod events;
+mod exec;
+mod health;
+mod input;
+mod jobs;
+mod keys;
+mod logs;
+mod manifest;
+mod metadata;
+mod metrics;
+mod nodes;
+mod overview;
+mod palette;
+mod portforward;
+mod relations;
+mod rollout;
+mod session;
 mod state;
 mod table;
+mod tree;
 mod ui;
+mod workloads;
 
-use clap::Parser;
-use kube::Client;
+use std::{
+    cmp::Reverse,
+    future::Future,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use clap::{Parser, Subcommand};
+use crossterm::{
+    event::{DisableBracketedPaste, EnableBracketedPaste},
+    execute,
+};
+use itertools::Itertools;
+use k8s_openapi::api::core::v1::{Namespace, Pod};
+use kube::{api::ListParams, config::Kubeconfig, Api, Client};
 use ratatui::{
     layout::{
         Constraint::{Length, Min, Ratio},
-        Layout,
+        Layout, Rect,
     },
-    style::{palette::tailwind::SLATE, Color, Styled, Stylize},
-    widgets::{Block, Cell, Paragraph, Row, Table, Tabs},
+    style::{Color, Modifier, Styled, Stylize},
+    widgets::{Block, Cell, Paragraph, Row, Table, TableState, Tabs},
     DefaultTerminal,
 };
 
 use crate::{
-    state::{Action, App, Editing, KubeState, UIState},
-    table::ResourceTable,
-    ui::table_column_constraints,
+    config::Config,
+    data::DataKind,
+    discovery::{DiscoveredAPIResource, Discovery, TableQuery},
+    edit::EditOutcome,
+    events::EventSummary,
+    health::{Health, HealthRule},
+    input::LineEditor,
+    metrics::MetricsKind,
+    overview::{Overview, MIN_OVERVIEW_REFRESH_INTERVAL, OVERVIEW},
+    palette::{Completions, PaletteCommand},
+    session::{session_path, Session, DEFAULT_SESSION},
+    state::{Action, App, Dialog, Editing, Focus, KubeState, Tab, TabView, UIState},
+    table::{ResourceTable, SortBy},
+    tree::{OwnerTree, TreeQuery},
+    ui::{
+        health_style, overview_table, owner_tree_table, render_dialog, render_events,
+        render_port_forwards, table_column_constraints,
+    },
 };
 
 #[derive(Parser, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
@@ -26,6 +78,44 @@ use crate::{
 struct Cli {
     #[arg(long)]
     discovery: bool,
+
+    /// Path to the configuration file, instead of `$XDG_CONFIG_HOME/flotilla/config.toml`.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Name of the session to restore on launch and save on quit, or the path to a session file.
+    /// The tabs from the configuration file are only opened if the session hasn't been saved yet.
+    #[arg(long, default_value = DEFAULT_SESSION)]
+    session: String,
+
+    /// Command to run when exec'ing into a container; may be repeated to
+    /// give fallbacks, which are tried in order until one exists in the container.
+    /// Overrides `exec-commands` from the configuration file.
+    #[arg(long = "exec-command")]
+    exec_commands: Vec<String>,
+
+    #[command(subcommand)]
+    command: Option<CliCommand>,
+}
+
+#[derive(Subcommand, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum CliCommand {
+    /// Show what applying a manifest would change, without changing anything.
+    /// Exits with status 1 if anything would change.
+    Diff {
+        /// The manifest, which may hold several YAML documents, a directory of manifests,
+        /// or - for standard input.
+        #[arg(short = 'f', long = "filename")]
+        file: PathBuf,
+    },
+    /// Server-side apply a manifest, Namespaces and CustomResourceDefinitions first.
+    /// Exits with status 1 if any object couldn't be applied.
+    Apply {
+        /// The manifest, which may hold several YAML documents, a directory of manifests,
+        /// or - for standard input.
+        #[arg(short = 'f', long = "filename")]
+        file: PathBuf,
+    },
 }
 
 pub type DynResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
@@ -36,7 +126,7 @@ async fn main() -> DynResult<()> {
 
     if cli.discovery {
         let client = Client::try_default().await?;
-        let discovery = discovery::Discovery::discover(&client).await?;
+        let discovery = Discovery::discover(&client).await?;
 
         for (name, resource) in discovery.name_to_resource {
             println!("{name} -> {resource:?}");
@@ -45,114 +135,438 @@ async fn main() -> DynResult<()> {
         return Ok(());
     }
 
+    match cli.command {
+        Some(CliCommand::Diff { file }) => {
+            let objects = manifest::read(&file)?;
+            let client = Client::try_default().await?;
+            let discovery = Discovery::discover(&client).await?;
+            let diffs = manifest::diff(client, &discovery, &objects).await;
+            let changed =
+                manifest::write_diffs(&mut io::stdout(), &diffs, io::stdout().is_terminal())?;
+            if changed {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(CliCommand::Apply { file }) => {
+            let objects = manifest::read(&file)?;
+            let client = Client::try_default().await?;
+            let mut discovery = Discovery::discover(&client).await?;
+            let applies = manifest::apply(client, &mut discovery, &objects).await;
+            let ok =
+                manifest::write_applies(&mut io::stdout(), &applies, io::stdout().is_terminal())?;
+            if !ok {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        None => {}
+    }
+
+    let mut config = Config::load(cli.config.as_deref())?;
+    if !cli.exec_commands.is_empty() {
+        config.exec_commands = cli.exec_commands;
+    }
+
+    let session_path = session_path(&cli.session).ok_or("could not find a home directory")?;
+    let mut ui = UIState::new(config);
+    // A saved session takes the place of the configured tabs, which are where a new session starts.
+    if let Some(session) = Session::load(&session_path)? {
+        session.restore(&mut ui);
+    }
+
+    let terminal = init_terminal()?;
+    let app_result = run(terminal, ui, &session_path).await;
+    restore_terminal();
+    app_result
+}
+
+/// Take over the terminal, with bracketed paste so that pasted text arrives as a single event.
+fn init_terminal() -> io::Result<DefaultTerminal> {
     let mut terminal = ratatui::init();
+    execute!(io::stdout(), EnableBracketedPaste)?;
     terminal.clear()?;
-    let app_result = run(terminal).await;
+    Ok(terminal)
+}
+
+fn restore_terminal() {
+    // Restoring is best-effort, like ratatui::restore.
+    let _ = execute!(io::stdout(), DisableBracketedPaste);
     ratatui::restore();
-    app_result
 }
 
-async fn run(mut terminal: DefaultTerminal) -> DynResult<()> {
-    let client = Client::try_default().await?;
+/// The indices of the columns of the table to display for the tab, in display order.
+fn visible_columns(
+    table: &ResourceTable,
+    tab: &Tab,
+    config: &Config,
+    resource: &DiscoveredAPIResource,
+) -> Vec<usize> {
+    let names: Vec<String> = table
+        .column_definitions
+        .iter()
+        .map(|cd| cd.name.clone())
+        .collect();
+    let preference = tab
+        .columns
+        .as_ref()
+        .or_else(|| config.columns_for(resource));
+    let mut columns = match preference {
+        Some(preference) => preference.visible_columns(&names),
+        None => (0..names.len()).collect(),
+    };
+    // Columns that are explicitly shown are shown even if they are low-priority.
+    if !tab.wide && preference.is_none_or(|p| p.show.is_empty()) {
+        columns.retain(|&idx| table.column_definitions[idx].priority == 0);
+    }
+    columns
+}
 
-    let mut app = App::new(KubeState::new(&client).await?, UIState::default());
+/// Fetch the table that the tab displays, or `None` if its resource is unknown.
+async fn tab_table(
+    kube: &mut KubeState,
+    tab: &Tab,
+    config: &Config,
+    status: &mut Option<String>,
+) -> DynResult<Option<Table<'static>>> {
+    let client = kube.client.clone();
+    let theme = &config.theme;
 
-    let mut table = Table::default();
+    if tab.is_overview() {
+        let stale = kube.overview.as_ref().is_none_or(|(fetched, _)| {
+            fetched.elapsed() >= config.refresh_interval.max(MIN_OVERVIEW_REFRESH_INTERVAL)
+        });
+        if stale {
+            let overview = Overview::fetch(client, &kube.discovery).await;
+            if let Some(summary) = overview.unreadable_summary() {
+                *status = Some(summary);
+            }
+            kube.overview = Some((Instant::now(), overview));
+        }
+        let items = kube
+            .overview
+            .as_ref()
+            .map_or(vec![], |(_, o)| o.items(tab.context.as_deref()));
+        return Ok(Some(overview_table(&items, theme)));
+    }
 
-    loop {
-        let tab = app.ui.active_tab();
+    let Some(r) = kube.discovery.get(&tab.resource).cloned() else {
+        return Ok(None);
+    };
 
-        let res = app.kube.discovery.get(&tab.resource);
+    if tab.view == TabView::Tree {
+        let key = TreeQuery::of(tab, &r);
+        let stale = kube
+            .owner_trees
+            .get(&key)
+            .is_none_or(|(built, _)| built.elapsed() >= config.refresh_interval);
+        if stale {
+            let lists = tree::list_tree_resources(client, &kube.discovery, &r, &key).await?;
+            kube.owner_trees
+                .insert(key.clone(), (Instant::now(), OwnerTree::build(lists, &r)));
+        }
+        return Ok(Some(owner_tree_table(
+            &kube.owner_trees[&key].1,
+            &tab.expanded,
+            &tab.filter,
+            theme,
+        )));
+    }
 
-        if let Some(r) = res {
-            // TODO: get resources tables in the background on a regular interval instead of on redraw
-            let resource_table: ResourceTable = client
-                .request(r.table_request(tab.namespace.as_deref()))
-                .await?;
+    // TODO: get resources tables in the background on a regular interval instead of on redraw
+    let is_events = events::is_event_resource(&r);
+    let is_jobs = jobs::is_job_resource(&r);
+    let metrics_kind = MetricsKind::of(&r);
+    let usage = match metrics_kind {
+        Some(kind) => {
+            metrics::usage(
+                client.clone(),
+                &kube.discovery,
+                kind,
+                tab.namespace.as_deref(),
+            )
+            .await
+        }
+        None => None,
+    };
+    let health_rules: Vec<&HealthRule> = config.health_rules_for(&r).collect();
+    let mut resource_table: ResourceTable = client
+        .request(r.table_request(
+            tab.namespace.as_deref(),
+            &TableQuery {
+                // Usage percentages need the pods' requests and limits, or the nodes' allocatable,
+                // and jobs' failures and health rules for conditions need the objects' status.
+                include_object: is_events
+                    || is_jobs
+                    || usage.is_some()
+                    || health_rules.iter().any(|rule| rule.condition.is_some()),
+                label_selector: tab.label_selector.as_deref(),
+                field_selector: tab.field_selector.as_deref(),
+            },
+        ))
+        .await?;
+    resource_table
+        .rows
+        .retain(|row| row.name().is_none_or(|n| n.contains(&tab.filter)));
+    if let (Some(kind), Some(usage)) = (metrics_kind, &usage) {
+        metrics::add_usage_columns(&mut resource_table, kind, usage);
+    }
+    if is_jobs {
+        jobs::add_job_columns(&mut resource_table);
+    }
+    if let Some(sort) = &tab.sort {
+        resource_table.sort_by(sort);
+    } else if is_events {
+        resource_table.rows.sort_by_cached_key(|row| {
+            Reverse(
+                row.object
+                    .as_ref()
+                    .and_then(|o| EventSummary::from_object(o).last_seen),
+            )
+        });
+    }
+    let resource_table = kube
+        .resources
+        .entry(tab.id)
+        .insert_entry(resource_table)
+        .into_mut();
 
-            // https://ratatui.rs/examples/widgets/table/
-            let header_strings: Vec<String> = resource_table
-                .column_definitions
+    // https://ratatui.rs/examples/widgets/table/
+    let columns = visible_columns(resource_table, tab, config, &r);
+    let header_strings: Vec<String> = columns
+        .iter()
+        .map(|&idx| {
+            let name = &resource_table.column_definitions[idx].name;
+            match &tab.sort {
+                Some(sort) if sort.column.eq_ignore_ascii_case(name) => {
+                    format!("{name} {}", if sort.descending { "▼" } else { "▲" })
+                }
+                _ => name.clone(),
+            }
+        })
+        .collect();
+    let row_strings: Vec<Vec<String>> = resource_table
+        .rows
+        .iter()
+        .map(|row| {
+            columns
                 .iter()
-                .map(|cd| cd.name.clone())
-                .collect::<Vec<String>>();
-            let row_strings: Vec<Vec<String>> = resource_table
-                .rows
-                .iter()
-                // TODO: filter on resource name
-                .map(|row| {
+                .map(|&idx| {
                     row.cells
-                        .iter()
-                        .map(|cell| cell.to_string())
-                        .collect::<Vec<String>>()
+                        .get(idx)
+                        .map(|c| c.to_string())
+                        .unwrap_or_default()
                 })
-                .collect();
+                .collect::<Vec<String>>()
+        })
+        .collect();
 
-            let header_row = header_strings
-                .iter()
+    let header_row = header_strings
+        .iter()
+        .map(|s| Cell::from(s.clone()))
+        .collect::<Row>()
+        .bold()
+        .bg(theme.header_background);
+    let rows = row_strings
+        .iter()
+        .zip(&resource_table.rows)
+        .map(|(r, row)| {
+            let is_warning = is_events
+                && row
+                    .object
+                    .as_ref()
+                    .is_some_and(|o| EventSummary::from_object(o).is_warning());
+            let health = if is_warning {
+                Some(Health::Warning)
+            } else {
+                health::row_health(
+                    health_rules.iter().copied(),
+                    row,
+                    &resource_table.column_definitions,
+                )
+            };
+            let style = health_style(health, theme);
+            let marked = row.uid().is_some_and(|uid| tab.marked.contains(uid));
+            r.iter()
                 .map(|s| Cell::from(s.clone()))
                 .collect::<Row>()
-                .bold()
-                .bg(SLATE.c800);
-            let rows = row_strings
-                .iter()
-                .map(|r| r.iter().map(|s| Cell::from(s.clone())).collect::<Row>());
+                .style(
+                    if marked {
+                        style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+                    } else {
+                        style
+                    },
+                )
+        })
+        .collect::<Vec<Row>>();
+
+    let constraints = crate::table_column_constraints(&header_strings, &row_strings);
+
+    Ok(Some(
+        Table::new(rows, constraints)
+            .header(header_row)
+            .row_highlight_style(Modifier::REVERSED)
+            .column_spacing(2),
+    ))
+}
+
+/// Hand the terminal over to `f` in its normal (non-TUI) mode, then take it back.
+async fn suspended<T>(terminal: &mut DefaultTerminal, f: impl Future<Output = T>) -> io::Result<T> {
+    restore_terminal();
+    let t = f.await;
+    *terminal = init_terminal()?;
+    Ok(t)
+}
 
-            let constraints = crate::table_column_constraints(&header_strings, &row_strings);
+async fn run(mut terminal: DefaultTerminal, ui: UIState, session_path: &Path) -> DynResult<()> {
+    let mut app = App::new(ui);
 
-            table = Table::new(rows, constraints)
-                .header(header_row)
-                .block(Block::bordered())
-                .column_spacing(2);
+    loop {
+        app.ui.sync_panes();
+        let pane_tabs = app.ui.pane_tabs();
+        for &idx in &pane_tabs {
+            let context = app.ui.tabs[idx].context.clone();
+            app.connect(context.as_deref()).await?;
+        }
+
+        // Each pane fetches its own tab, so panes can show different resources and clusters.
+        let mut tables = Vec::with_capacity(pane_tabs.len());
+        for &idx in &pane_tabs {
+            let tab = &app.ui.tabs[idx];
+            let kube = app
+                .clusters
+                .get_mut(&tab.context)
+                .expect("the pane's cluster is connected");
+            tables.push(
+                tab_table(kube, tab, &app.ui.config, &mut app.ui.status)
+                    .await?
+                    .unwrap_or_default(),
+            );
         }
 
+        let client = app.kube().client.clone();
+        // Keep the progress of a rollout being watched up to date.
+        if let Some(Dialog::Rollout {
+            target,
+            rollout,
+            fetched,
+            ..
+        }) = &mut app.ui.dialog
+        {
+            if fetched.elapsed() >= app.ui.config.refresh_interval {
+                if let Ok(latest) = rollout::fetch(client.clone(), target).await {
+                    *rollout = latest;
+                }
+                *fetched = Instant::now();
+            }
+        }
+        let resource_known = app.ui.active_tab().is_overview()
+            || app
+                .kube()
+                .discovery
+                .get(&app.ui.active_tab().resource)
+                .is_some();
+        let tab = app.ui.active_tab();
+        let theme = &app.ui.config.theme;
+
+        let selected_events = match (
+            app.ui.show_events,
+            app.kube().discovery.get("events"),
+            app.selected_object(),
+        ) {
+            (true, Some(events_resource), Some(target)) => Some(
+                events::events_for(client.clone(), events_resource, &target)
+                    .await
+                    .map_err(|e| e.to_string()),
+            ),
+            (true, ..) => Some(Ok(vec![])),
+            _ => None,
+        };
+
+        let mut table_states: Vec<TableState> = pane_tabs
+            .iter()
+            .map(|&idx| app.ui.tabs[idx].table_state.clone())
+            .collect();
+        let mut port_forwards_state = app.ui.port_forwards_state.clone();
+
         terminal.draw(|frame| {
-            let [tabs_area, meta, _resources_layout] =
-                Layout::vertical([Length(1), Length(3), Min(0)]).areas(frame.area());
+            let port_forwards_height = match app.port_forwards.forwards.len() {
+                0 => 0,
+                n => n as u16 + 3,
+            };
+            let [tabs_area, meta, resources_area, port_forwards_area] =
+                Layout::vertical([Length(1), Length(3), Min(0), Length(port_forwards_height)])
+                    .areas(frame.area());
+            let [_resources_layout, events_area] = Layout::vertical([
+                Min(0),
+                Length(if selected_events.is_some() { 12 } else { 0 }),
+            ])
+            .areas(resources_area);
             let [namespace_selector, resource_selector, name_filter] =
                 Layout::horizontal([Ratio(1, 3), Ratio(1, 3), Ratio(1, 3)]).areas(meta);
 
-            let namespace_p = Paragraph::new(tab.namespace.clone().unwrap_or("".into())).block(
-                Block::bordered().title("Namespace").set_style(
-                    if let Some(Editing::Namespace) = app.ui.editing {
-                        Color::LightCyan
-                    } else {
-                        Color::White
-                    },
-                ),
-            );
+            let editing = |field: Editing| match &app.ui.editing {
+                Some((f, editor)) if *f == field => Some(editor),
+                _ => None,
+            };
+            let field_style = |field| {
+                if editing(field).is_some() {
+                    theme.focused
+                } else {
+                    theme.unfocused
+                }
+            };
+            // The field being edited scrolls to keep its cursor in view.
+            let scroll = |field, area: Rect| {
+                editing(field).map_or(0, |e| e.scroll(area.width.saturating_sub(2).into()) as u16)
+            };
+
+            let namespace_p = Paragraph::new(tab.namespace.clone().unwrap_or("".into()))
+                .scroll((0, scroll(Editing::Namespace, namespace_selector)))
+                .block(
+                    Block::bordered()
+                        .title("Namespace")
+                        .set_style(field_style(Editing::Namespace)),
+                );
             let resource_p = Paragraph::new(tab.resource.clone())
                 .set_style(
-                    if res.is_some() {
-                        Color::White
+                    if resource_known {
+                        theme.unfocused
                     } else {
-                        Color::Red
+                        theme.error
                     },
                 )
-                .block(Block::bordered().title("Resource").border_style(
-                    if let Some(Editing::Resource) = app.ui.editing {
-                        Color::LightCyan
-                    } else {
-                        Color::White
-                    },
-                ));
-            let filter_p = Paragraph::new(tab.filter.clone()).block(
-                Block::bordered().title("Filter").set_style(
-                    if let Some(Editing::Filter) = app.ui.editing {
-                        Color::LightCyan
-                    } else {
-                        Color::White
-                    },
-                ),
-            );
+                .scroll((0, scroll(Editing::Resource, resource_selector)))
+                .block(
+                    Block::bordered()
+                        .title("Resource")
+                        .border_style(field_style(Editing::Resource)),
+                );
+            let filter_p = Paragraph::new(tab.filter.clone())
+                .scroll((0, scroll(Editing::Filter, name_filter)))
+                .block(
+                    Block::bordered()
+                        .title("Filter")
+                        .set_style(field_style(Editing::Filter)),
+                );
 
-            let highlight_style = (Color::default(), Color::Cyan);
+            if let Some((field, editor)) = &app.ui.editing {
+                let area = match field {
+                    Editing::Namespace => namespace_selector,
+                    Editing::Resource => resource_selector,
+                    Editing::Filter => name_filter,
+                };
+                let column = editor.cursor() - scroll(*field, area) as usize;
+                frame.set_cursor_position((area.x + 1 + column as u16, area.y + 1));
+            }
+
+            let highlight_style = (Color::default(), theme.tab_highlight);
             let tabs = Tabs::new(
                 app.ui
                     .tabs
                     .iter()
                     .enumerate()
-                    .map(|(idx, t)| format!("{idx} {}", t.resource)),
+                    .map(|(idx, t)| format!("{} {}", idx + 1, t.label())),
             )
             .highlight_style(highlight_style)
             .select(app.ui.active_tab_idx)
@@ -163,11 +577,734 @@ async fn run(mut terminal: DefaultTerminal) -> DynResult<()> {
             frame.render_widget(namespace_p, namespace_selector);
             frame.render_widget(resource_p, resource_selector);
             frame.render_widget(filter_p, name_filter);
-            frame.render_widget(&table, _resources_layout);
+            let pane_areas = Layout::default()
+                .direction(app.ui.split)
+                .constraints(vec![Ratio(1, pane_tabs.len() as u32); pane_tabs.len()])
+                .split(_resources_layout);
+            for (pane, ((&idx, table), table_state)) in pane_tabs
+                .iter()
+                .zip(tables)
+                .zip(&mut table_states)
+                .enumerate()
+            {
+                let t = &app.ui.tabs[idx];
+                let focused = pane == app.ui.focused_pane;
+                // With more than one pane, the title says which tab each pane shows.
+                let label = (pane_tabs.len() > 1).then(|| t.label());
+                let block = Block::bordered()
+                    .title(
+                        [&label, &t.label_selector, &t.field_selector]
+                            .into_iter()
+                            .flatten()
+                            .join(" "),
+                    )
+                    .border_style(
+                        if focused && pane_tabs.len() > 1 {
+                            theme.focused
+                        } else {
+                            theme.unfocused
+                        },
+                    );
+                let block = if focused {
+                    block.title_bottom(app.ui.status.clone().unwrap_or_default())
+                } else {
+                    block
+                };
+                frame.render_stateful_widget(table.block(block), pane_areas[pane], table_state);
+            }
+
+            if let Some(events) = &selected_events {
+                render_events(frame, events_area, events, theme);
+            }
+
+            if !app.port_forwards.forwards.is_empty() {
+                render_port_forwards(
+                    frame,
+                    port_forwards_area,
+                    &app.port_forwards,
+                    &mut port_forwards_state,
+                    app.ui.focus == Focus::PortForwards,
+                    theme,
+                );
+            }
+
+            if let Some(dialog) = &app.ui.dialog {
+                render_dialog(frame, dialog, theme);
+            }
         })?;
 
-        if let Ok(Action::Quit) = app.ui.handle_events() {
-            return Ok(());
+        for (&idx, table_state) in pane_tabs.iter().zip(table_states) {
+            app.ui.tabs[idx].table_state = table_state;
+        }
+        app.ui.port_forwards_state = port_forwards_state;
+
+        let action = app.ui.handle_events();
+        let client = app.kube().client.clone();
+        match action {
+            // Dropping the app tears down any running port-forwards.
+            Ok(Action::Quit) => return Session::of(&app.ui).save(session_path),
+            Ok(Action::Edit) => {
+                let Some(target) = app.selected_object() else {
+                    continue;
+                };
+                let api = target.api(client.clone());
+                let outcome =
+                    suspended(&mut terminal, edit::edit_object(&api, &target.name)).await?;
+
+                app.ui.status = Some(match outcome {
+                    Ok(EditOutcome::Applied) => format!("{} edited", target.name),
+                    Ok(EditOutcome::Unchanged) => "Edit cancelled, no changes made".into(),
+                    Ok(EditOutcome::Cancelled) => "Edit cancelled, empty file".into(),
+                    Err(e) => format!("Error editing {}: {e}", target.name),
+                });
+            }
+            Ok(Action::PromptScale) => {
+                let Some(target) = app.selected_object() else {
+                    continue;
+                };
+                if !app.kube().discovery.is_scalable(&target.resource) {
+                    app.ui.status = Some(format!("{} cannot be scaled", target.resource.plural));
+                    continue;
+                }
+                match workloads::current_replicas(client.clone(), &target).await {
+                    Ok(replicas) => {
+                        app.ui.dialog = Some(Dialog::Scale {
+                            target,
+                            replicas: LineEditor::new(&replicas.to_string(), &[]),
+                        })
+                    }
+                    Err(e) => app.ui.status = Some(format!("Error getting scale: {e}")),
+                }
+            }
+            Ok(Action::Scale { target, replicas }) => {
+                app.ui.status = Some(
+                    match workloads::scale(client.clone(), &target, replicas).await {
+                        Ok(()) => format!("{} scaled to {replicas}", target.name),
+                        Err(e) => format!("Error scaling {}: {e}", target.name),
+                    },
+                );
+            }
+            Ok(Action::PromptRolloutRestart) => {
+                let Some(target) = app.selected_object() else {
+                    continue;
+                };
+                if !app.kube().discovery.is_restartable(&target.resource) {
+                    app.ui.status = Some(format!("{} cannot be restarted", target.resource.plural));
+                    continue;
+                }
+                app.ui.dialog = Some(Dialog::Confirm {
+                    prompt: format!("Restart {} {}?", target.resource.kind, target.name),
+                    action: Action::RolloutRestart(target),
+                });
+            }
+            Ok(Action::RolloutRestart(target)) => {
+                app.ui.status = Some(
+                    match workloads::rollout_restart(client.clone(), &target).await {
+                        Ok(()) => format!("{} restarted", target.name),
+                        Err(e) => format!("Error restarting {}: {e}", target.name),
+                    },
+                );
+            }
+            Ok(Action::PromptExec) => {
+                let Some(target) = app.selected_object() else {
+                    continue;
+                };
+                if !(target.resource.group.is_empty() && target.resource.plural == "pods") {
+                    app.ui.status = Some(format!("Cannot exec into {}", target.resource.plural));
+                    continue;
+                }
+                let api: Api<Pod> = Api::namespaced(
+                    client.clone(),
+                    target.namespace.as_deref().unwrap_or_default(),
+                );
+                match api.get(&target.name).await {
+                    Ok(pod) => match exec::container_names(&pod).as_slice() {
+                        [] => app.ui.status = Some(format!("{} has no containers", target.name)),
+                        [container] => {
+                            app.ui.pending = Some(Action::Exec {
+                                target,
+                                container: container.clone(),
+                            })
+                        }
+                        containers => {
+                            app.ui.dialog = Some(Dialog::SelectContainer {
+                                target,
+                                containers: containers.to_vec(),
+                                selected: 0,
+                            })
+                        }
+                    },
+                    Err(e) => app.ui.status = Some(format!("Error getting {}: {e}", target.name)),
+                }
+            }
+            Ok(Action::Exec { target, container }) => {
+                let api: Api<Pod> = Api::namespaced(
+                    client.clone(),
+                    target.namespace.as_deref().unwrap_or_default(),
+                );
+                let result = suspended(
+                    &mut terminal,
+                    exec::exec_shell(&api, &target.name, &container, &app.ui.config.exec_commands),
+                )
+                .await?;
+                app.ui.status = Some(match result {
+                    Ok(()) => format!("Exited {}/{container}", target.name),
+                    Err(e) => format!("Error exec'ing into {}/{container}: {e}", target.name),
+                });
+            }
+            Ok(Action::PromptPortForward) => {
+                let Some(target) = app.selected_object() else {
+                    continue;
+                };
+                if !portforward::can_port_forward(&target) {
+                    app.ui.status =
+                        Some(format!("Cannot port-forward to {}", target.resource.plural));
+                    continue;
+                }
+                match portforward::suggested_ports(client.clone(), &target).await {
+                    Ok(suggestions) => {
+                        let port = suggestions.first().map(u16::to_string).unwrap_or_default();
+                        let port = LineEditor::new(&port, &[]);
+                        app.ui.dialog = Some(Dialog::PortForward {
+                            target,
+                            suggestions,
+                            local: port.clone(),
+                            remote: port,
+                            editing_local: true,
+                        })
+                    }
+                    Err(e) => app.ui.status = Some(format!("Error getting {}: {e}", target.name)),
+                }
+            }
+            Ok(Action::PortForward {
+                target,
+                local,
+                remote,
+            }) => {
+                app.ui.status = Some(
+                    match app
+                        .port_forwards
+                        .start(client.clone(), &target, local, remote)
+                        .await
+                    {
+                        Ok(()) => format!("Forwarding {} to {}:{remote}", local, target.name),
+                        Err(e) => format!("Error port-forwarding to {}: {e}", target.name),
+                    },
+                );
+            }
+            Ok(Action::StopPortForward(idx)) => {
+                if let Some(pf) = app.port_forwards.stop(idx) {
+                    app.ui.status = Some(format!("Stopped forwarding localhost:{}", pf.local_port));
+                }
+                if app.port_forwards.forwards.is_empty() {
+                    app.ui.focus = Focus::Table;
+                }
+            }
+            Ok(Action::SetExpanded(expand)) => {
+                let Some(uid) = app.selected_owner_node().map(|n| n.uid().to_string()) else {
+                    continue;
+                };
+                let tab = app.ui.active_tab_mut();
+                if expand {
+                    tab.expanded.insert(uid);
+                } else {
+                    tab.expanded.remove(&uid);
+                }
+            }
+            Ok(Action::PromptJump) => {
+                let Some(target) = app.selected_object() else {
+                    continue;
+                };
+                match relations::related(client.clone(), &app.kube().discovery, &target).await {
+                    Ok(jumps) => app.ui.dialog = Some(Dialog::Jump { jumps, selected: 0 }),
+                    Err(e) => {
+                        app.ui.status = Some(format!(
+                            "Error finding objects related to {}: {e}",
+                            target.name
+                        ))
+                    }
+                }
+            }
+            Ok(Action::PromptPalette) => {
+                let namespaces = Api::<Namespace>::all(client.clone())
+                    .list_metadata(&ListParams::default())
+                    .await
+                    .map(|list| {
+                        list.items
+                            .into_iter()
+                            .filter_map(|n| n.metadata.name)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let contexts = Kubeconfig::read()
+                    .map(|k| k.contexts.into_iter().map(|c| c.name).collect())
+                    .unwrap_or_default();
+                app.ui.dialog = Some(Dialog::Palette {
+                    input: LineEditor::default(),
+                    completions: Completions {
+                        resources: app
+                            .kube()
+                            .discovery
+                            .name_to_resource
+                            .keys()
+                            .cloned()
+                            .chain([OVERVIEW.to_string()])
+                            .collect(),
+                        namespaces,
+                        contexts,
+                    },
+                    selected: 0,
+                });
+            }
+            Ok(Action::RunPalette(input)) => match palette::parse(&input) {
+                Err(e) => app.ui.status = Some(e),
+                Ok(PaletteCommand::Run(command)) => {
+                    app.ui.pending = Some(app.ui.run_command(command));
+                }
+                Ok(PaletteCommand::Context(context)) => match app.connect(Some(&context)).await {
+                    Ok(()) => {
+                        let tab = app.ui.active_tab_mut();
+                        tab.context = Some(context);
+                        tab.table_state.select_first();
+                    }
+                    Err(e) => {
+                        app.ui.status = Some(format!("Error connecting to context {context}: {e}"))
+                    }
+                },
+                Ok(PaletteCommand::Diff(path)) => match manifest::read(&path) {
+                    Ok(objects) => {
+                        let diffs =
+                            manifest::diff(client.clone(), &app.kube().discovery, &objects).await;
+                        app.ui.dialog = Some(Dialog::Diff {
+                            path,
+                            diffs,
+                            scroll: 0,
+                        });
+                    }
+                    Err(e) => app.ui.status = Some(format!("Error diffing: {e}")),
+                },
+                Ok(PaletteCommand::Apply(path)) => {
+                    app.ui.dialog = Some(Dialog::Confirm {
+                        prompt: format!("Apply {}?", path.display()),
+                        action: Action::Apply(path),
+                    });
+                }
+                Ok(PaletteCommand::Namespace(namespace)) => {
+                    let tab = app.ui.active_tab_mut();
+                    tab.namespace = namespace;
+                    tab.table_state.select_first();
+                }
+                Ok(PaletteCommand::Resource {
+                    name,
+                    namespace,
+                    label_selector,
+                }) => {
+                    let resource = match app.kube().discovery.get(&name) {
+                        Some(r) => r.plural.clone(),
+                        None if name == OVERVIEW => name,
+                        None => {
+                            app.ui.status = Some(format!("Unknown resource {name}"));
+                            continue;
+                        }
+                    };
+                    let tab = app.ui.active_tab_mut();
+                    tab.resource = resource;
+                    if let Some(namespace) = namespace {
+                        tab.namespace = namespace;
+                    }
+                    tab.label_selector = label_selector;
+                    tab.field_selector = None;
+                    tab.filter.clear();
+                    tab.table_state.select_first();
+                }
+            },
+            Ok(Action::CycleSort { forward }) => {
+                let Some((r, table)) = app
+                    .kube()
+                    .discovery
+                    .get(&app.ui.active_tab().resource)
+                    .and_then(|r| Some((r, app.kube().resources.get(&app.ui.active_tab().id)?)))
+                else {
+                    continue;
+                };
+                let names: Vec<String> =
+                    visible_columns(table, app.ui.active_tab(), &app.ui.config, r)
+                        .into_iter()
+                        .map(|idx| table.column_definitions[idx].name.clone())
+                        .collect();
+                let tab = app.ui.active_tab_mut();
+                let current = tab
+                    .sort
+                    .as_ref()
+                    .and_then(|s| names.iter().position(|n| n.eq_ignore_ascii_case(&s.column)));
+                // Cycling past the last column (or before the first) turns sorting off.
+                let next = match (current, forward) {
+                    (None, true) => Some(0),
+                    (None, false) => names.len().checked_sub(1),
+                    (Some(idx), true) => Some(idx + 1).filter(|&i| i < names.len()),
+                    (Some(idx), false) => idx.checked_sub(1),
+                };
+                tab.sort = next.map(|idx| SortBy {
+                    column: names[idx].clone(),
+                    descending: false,
+                });
+            }
+            Ok(Action::OpenOverviewItem) => {
+                let tab = app.ui.active_tab();
+                let item = app.kube().overview.as_ref().and_then(|(_, o)| {
+                    o.items(tab.context.as_deref())
+                        .into_iter()
+                        .nth(tab.table_state.selected()?)
+                });
+                if let Some(tab) = item.and_then(|i| i.tab) {
+                    app.ui.open_tab(tab);
+                }
+            }
+            Ok(Action::PromptData) => {
+                let Some(target) = app.selected_object() else {
+                    continue;
+                };
+                match DataKind::of(&target.resource) {
+                    Some(kind) => {
+                        app.ui.pending = Some(Action::ShowData {
+                            target,
+                            kind,
+                            select: None,
+                        })
+                    }
+                    None => {
+                        app.ui.status =
+                            Some(format!("{} is not a config map or secret", target.name))
+                    }
+                }
+            }
+            Ok(Action::ShowData {
+                target,
+                kind,
+                select,
+            }) => match data::fetch(client.clone(), kind, &target).await {
+                Ok(entries) => {
+                    let selected = select
+                        .and_then(|key| entries.iter().position(|e| e.key == key))
+                        .unwrap_or_default();
+                    app.ui.dialog = Some(Dialog::Data {
+                        target,
+                        kind,
+                        entries,
+                        selected,
+                        new_key: None,
+                    })
+                }
+                Err(e) => app.ui.status = Some(format!("Error getting {}: {e}", target.name)),
+            },
+            Ok(Action::EditData {
+                target,
+                kind,
+                key,
+                value,
+            }) => {
+                let original = value.unwrap_or_default();
+                let suffix = data::editor_suffix(&key);
+                let edited = suspended(&mut terminal, async {
+                    edit::run_editor(&original, &suffix)
+                })
+                .await?;
+                match edited.map(|edited| data::edited_value(&original, edited)) {
+                    Ok(edited) if edited == original => {
+                        app.ui.status = Some(format!("No changes to {key}"));
+                        app.ui.pending = Some(Action::ShowData {
+                            target,
+                            kind,
+                            select: Some(key),
+                        });
+                    }
+                    Ok(edited) => {
+                        app.ui.pending = Some(Action::SetData {
+                            target,
+                            kind,
+                            key,
+                            value: Some(edited),
+                        })
+                    }
+                    Err(e) => app.ui.status = Some(format!("Error editing {key}: {e}")),
+                }
+            }
+            Ok(Action::SetData {
+                target,
+                kind,
+                key,
+                value,
+            }) => match data::set(client.clone(), kind, &target, &key, value.as_deref()).await {
+                Ok(()) => {
+                    app.ui.status = Some(match value {
+                        Some(_) => format!("{key} saved to {}", target.name),
+                        None => format!("{key} deleted from {}", target.name),
+                    });
+                    app.ui.pending = Some(Action::ShowData {
+                        target,
+                        kind,
+                        select: Some(key),
+                    });
+                }
+                Err(e) => app.ui.status = Some(format!("Error saving {key}: {e}")),
+            },
+            Ok(Action::ToggleSuspend) => {
+                let Some(target) = app.selected_object() else {
+                    continue;
+                };
+                if !jobs::is_cronjob(&target) {
+                    app.ui.status = Some(format!("{} is not a cron job", target.name));
+                    continue;
+                }
+                app.ui.status = Some(match jobs::is_suspended(client.clone(), &target).await {
+                    Ok(suspended) => {
+                        let (done, doing) = if suspended {
+                            ("resumed", "resuming")
+                        } else {
+                            ("suspended", "suspending")
+                        };
+                        match jobs::set_suspend(client.clone(), &target, !suspended).await {
+                            Ok(()) => format!("{} {done}", target.name),
+                            Err(e) => format!("Error {doing} {}: {e}", target.name),
+                        }
+                    }
+                    Err(e) => format!("Error getting {}: {e}", target.name),
+                });
+            }
+            Ok(Action::PromptTrigger) => {
+                let Some(target) = app.selected_object() else {
+                    continue;
+                };
+                if !jobs::is_cronjob(&target) {
+                    app.ui.status = Some(format!("{} is not a cron job", target.name));
+                    continue;
+                }
+                app.ui.dialog = Some(Dialog::Confirm {
+                    prompt: format!("Create a job from {} now?", target.name),
+                    action: Action::Trigger(target),
+                });
+            }
+            Ok(Action::Trigger(target)) => {
+                app.ui.status = Some(
+                    match jobs::trigger(client.clone(), &app.kube().discovery, &target).await {
+                        Ok(job) => format!("Created job {job}"),
+                        Err(e) => format!("Error creating a job from {}: {e}", target.name),
+                    },
+                );
+            }
+            Ok(Action::Logs) => {
+                let Some(target) = app.selected_object() else {
+                    continue;
+                };
+                if !logs::has_logs(&target) {
+                    app.ui.status = Some(format!("{} has no logs", target.name));
+                    continue;
+                }
+                let result = match logs::logs(client.clone(), &target).await {
+                    Ok(text) => suspended(&mut terminal, async { logs::page(&text) }).await?,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = result {
+                    app.ui.status = Some(format!("Error showing logs of {}: {e}", target.name));
+                }
+            }
+            Ok(Action::Apply(path)) => match manifest::read(&path) {
+                Ok(objects) => {
+                    let applies =
+                        manifest::apply(client.clone(), &mut app.kube_mut().discovery, &objects)
+                            .await;
+                    app.ui.dialog = Some(Dialog::Apply {
+                        path,
+                        applies,
+                        scroll: 0,
+                    });
+                }
+                Err(e) => app.ui.status = Some(format!("Error applying: {e}")),
+            },
+            Ok(Action::PromptRollout) => {
+                let Some(target) = app.selected_object() else {
+                    continue;
+                };
+                if !rollout::is_deployment(&target) {
+                    app.ui.status = Some(format!("{} is not a deployment", target.name));
+                    continue;
+                }
+                app.ui.pending = Some(Action::ShowRollout(target));
+            }
+            Ok(Action::ShowRollout(target)) => {
+                match rollout::fetch(client.clone(), &target).await {
+                    Ok(rollout) => {
+                        app.ui.dialog = Some(Dialog::Rollout {
+                            target,
+                            rollout,
+                            fetched: Instant::now(),
+                            selected: 0,
+                            diff: None,
+                        })
+                    }
+                    Err(e) => {
+                        app.ui.status =
+                            Some(format!("Error getting rollout of {}: {e}", target.name))
+                    }
+                }
+            }
+            Ok(Action::Rollback { target, revision }) => {
+                app.ui.status = Some(
+                    match rollout::rollback(client.clone(), &target, &revision).await {
+                        Ok(()) => format!(
+                            "Rolling {} back to revision {}",
+                            target.name, revision.number
+                        ),
+                        Err(e) => format!("Error rolling back {}: {e}", target.name),
+                    },
+                );
+                app.ui.pending = Some(Action::ShowRollout(target));
+            }
+            Ok(Action::Cordon(unschedulable)) => {
+                let targets = app.selected_objects();
+                if !targets.iter().all(nodes::is_node) {
+                    app.ui.status = Some("Only nodes can be cordoned".into());
+                    continue;
+                }
+                let results = futures::future::join_all(
+                    targets
+                        .iter()
+                        .map(|t| nodes::cordon(client.clone(), &t.name, unschedulable)),
+                )
+                .await;
+                let verb = if unschedulable { "cordon" } else { "uncordon" };
+                app.ui.status = Some(
+                    match targets
+                        .iter()
+                        .zip(results)
+                        .find_map(|(t, r)| r.err().map(|e| (t, e)))
+                    {
+                        None => format!("{}ed {}", verb, state::describe_targets(&targets)),
+                        Some((t, e)) => format!("Error {verb}ing {}: {e}", t.name),
+                    },
+                );
+            }
+            Ok(Action::PromptDrain) => {
+                let targets = app.selected_objects();
+                if targets.is_empty() {
+                    continue;
+                }
+                if !targets.iter().all(nodes::is_node) {
+                    app.ui.status = Some("Only nodes can be drained".into());
+                    continue;
+                }
+                app.ui.dialog = Some(Dialog::Confirm {
+                    prompt: format!(
+                        "Cordon and evict the pods of {}?",
+                        state::describe_targets(&targets)
+                    ),
+                    action: Action::Drain(targets.into_iter().map(|t| t.name).collect()),
+                });
+            }
+            Ok(Action::Drain(node_names)) => match nodes::drain(client.clone(), node_names).await {
+                Ok(drain) => app.ui.dialog = Some(Dialog::Drain { drain, scroll: 0 }),
+                Err(e) => app.ui.status = Some(format!("Error draining: {e}")),
+            },
+            Ok(Action::ToggleMark) => {
+                let uid = app
+                    .selected_row()
+                    .and_then(|(_, row)| row.uid())
+                    .map(String::from);
+                let tab = app.ui.active_tab_mut();
+                if let Some(uid) = uid {
+                    if !tab.marked.remove(&uid) {
+                        tab.marked.insert(uid);
+                    }
+                }
+                tab.table_state.select_next();
+            }
+            Ok(Action::PromptMetadata) => {
+                let targets = app.selected_objects();
+                if !targets.is_empty() {
+                    app.ui.pending = Some(Action::ShowMetadata {
+                        targets,
+                        select: None,
+                    });
+                }
+            }
+            Ok(Action::ShowMetadata { targets, select }) => {
+                match metadata::fetch(client.clone(), &targets).await {
+                    Ok(entries) => {
+                        let selected = select
+                            .and_then(|(section, key)| {
+                                entries
+                                    .iter()
+                                    .position(|e| e.section == section && e.key == key)
+                            })
+                            .unwrap_or_default();
+                        app.ui.dialog = Some(Dialog::Metadata {
+                            targets,
+                            entries,
+                            selected,
+                            input: None,
+                        })
+                    }
+                    Err(e) => {
+                        app.ui.status = Some(format!(
+                            "Error getting {}: {e}",
+                            state::describe_targets(&targets)
+                        ))
+                    }
+                }
+            }
+            Ok(Action::SetMetadata {
+                targets,
+                section,
+                key,
+                value,
+            }) => {
+                let errors =
+                    metadata::set(client.clone(), &targets, section, &key, value.as_deref()).await;
+                let described = state::describe_targets(&targets);
+                app.ui.status = Some(match (errors.as_slice(), &value) {
+                    ([], Some(value)) => format!("Set {key}={value} on {described}"),
+                    ([], None) => format!("Removed {key} from {described}"),
+                    ([error], _) if targets.len() == 1 => format!("Error updating {error}"),
+                    ([error, ..], _) => format!(
+                        "Error updating {} of {}, like {error}",
+                        errors.len(),
+                        targets.len()
+                    ),
+                });
+                app.ui.pending = Some(Action::ShowMetadata {
+                    targets,
+                    select: Some((section, key)),
+                });
+            }
+            Ok(Action::Copy { label, value }) => {
+                let mut stdout = io::stdout();
+                app.ui.status = Some(
+                    match stdout
+                        .write_all(data::osc52(&value).as_bytes())
+                        .and_then(|()| stdout.flush())
+                    {
+                        Ok(()) => format!("Copied {label} to the clipboard"),
+                        Err(e) => format!("Error copying {label}: {e}"),
+                    },
+                );
+            }
+            Ok(Action::Refresh) => {
+                let context = app.ui.active_tab().context.clone();
+                let kube = app
+                    .clusters
+                    .get_mut(&context)
+                    .expect("the active tab's cluster is connected");
+                kube.owner_trees.clear();
+                kube.overview = None;
+                app.ui.status = Some(match Discovery::discover(&client).await {
+                    Ok(discovery) => {
+                        kube.discovery = discovery;
+                        "Refreshed".into()
+                    }
+                    Err(e) => format!("Error discovering resources: {e}"),
+                });
+            }
+            _ => {}
         }
     }
 }
@@ -0,0 +1,468 @@
+use std::{
+    fmt::{Display, Formatter},
+    fs, io,
+    path::Path,
+};
+
+use crossterm::style::{ContentStyle, Stylize};
+use kube::{
+    api::{DynamicObject, Patch, PatchParams},
+    Api, Client, ResourceExt,
+};
+use serde::Deserialize;
+
+use crate::{
+    diff::{diff_lines, DiffLine},
+    discovery::{DiscoveredAPIResource, Discovery},
+    edit::FIELD_MANAGER,
+    DynResult,
+};
+
+/// The extensions of the files in a directory that are read as manifests, like kubectl's.
+const EXTENSIONS: &[&str] = &["yaml", "yml", "json"];
+
+/// Read the objects in a YAML manifest, which may hold several documents separated by `---`;
+/// in each manifest in a directory, in order of their names; or in standard input, for `-`.
+pub(crate) fn read(path: &Path) -> DynResult<Vec<DynamicObject>> {
+    if path == Path::new("-") {
+        let text = io::read_to_string(io::stdin())?;
+        return parse(&text).map_err(|e| format!("could not parse standard input: {e}").into());
+    }
+    if !path.is_dir() {
+        return read_file(path);
+    }
+
+    let mut files = vec![];
+    for entry in fs::read_dir(path)? {
+        let file = entry?.path();
+        if file.is_file()
+            && file
+                .extension()
+                .is_some_and(|e| EXTENSIONS.iter().any(|ext| e == *ext))
+        {
+            files.push(file);
+        }
+    }
+    files.sort();
+    let mut objects = vec![];
+    for file in files {
+        objects.extend(read_file(&file)?);
+    }
+    Ok(objects)
+}
+
+fn read_file(path: &Path) -> DynResult<Vec<DynamicObject>> {
+    let text =
+        fs::read_to_string(path).map_err(|e| format!("could not read {}: {e}", path.display()))?;
+    parse(&text).map_err(|e| format!("could not parse {}: {e}", path.display()).into())
+}
+
+fn parse(text: &str) -> DynResult<Vec<DynamicObject>> {
+    let mut objects = vec![];
+    for document in serde_yaml::Deserializer::from_str(text) {
+        let value = serde_yaml::Value::deserialize(document)?;
+        // Empty documents, like a trailing `---` or one that is all comments, hold nothing.
+        if !value.is_null() {
+            objects.push(serde_yaml::from_value(value)?);
+        }
+    }
+    Ok(objects)
+}
+
+/// The differences that applying an object from a manifest would make to the live object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ObjectDiff {
+    /// The object, like `Deployment default/web`.
+    pub(crate) object: String,
+    /// The diff, which is empty if applying it would change nothing, or why it couldn't be made.
+    pub(crate) diff: Result<Vec<DiffLine>, String>,
+}
+
+/// Diff each object against the live object, like `kubectl diff --server-side`.
+pub(crate) async fn diff(
+    client: Client,
+    discovery: &Discovery,
+    objects: &[DynamicObject],
+) -> Vec<ObjectDiff> {
+    let mut diffs = vec![];
+    for object in objects {
+        diffs.push(ObjectDiff {
+            object: describe(object),
+            diff: diff_object(client.clone(), discovery, object)
+                .await
+                .map_err(|e| e.to_string()),
+        });
+    }
+    diffs
+}
+
+fn describe(object: &DynamicObject) -> String {
+    let kind = object.types.as_ref().map_or("", |t| t.kind.as_str());
+    match &object.metadata.namespace {
+        Some(namespace) => format!("{kind} {namespace}/{}", object.name_any()),
+        None => format!("{kind} {}", object.name_any()),
+    }
+}
+
+/// The resource of the object, from its `apiVersion` and `kind`.
+pub(crate) fn resource<'a>(
+    discovery: &'a Discovery,
+    object: &DynamicObject,
+) -> DynResult<&'a DiscoveredAPIResource> {
+    let types = object.types.as_ref().ok_or("missing apiVersion and kind")?;
+    Ok(discovery
+        .resource_for(&types.api_version, &types.kind)
+        .ok_or_else(|| format!("unknown kind {} in {}", types.kind, types.api_version))?)
+}
+
+/// The API for the object of the resource and the object's name.
+/// Objects without a namespace go in the client's default namespace.
+fn target<'a>(
+    client: Client,
+    resource: &DiscoveredAPIResource,
+    object: &'a DynamicObject,
+) -> DynResult<(Api<DynamicObject>, &'a str)> {
+    let namespace = object
+        .metadata
+        .namespace
+        .clone()
+        .unwrap_or_else(|| client.default_namespace().to_string());
+    let name = object.metadata.name.as_deref().ok_or("missing name")?;
+    Ok((resource.api(client, Some(&namespace)), name))
+}
+
+/// Compare the live object to the result of a server-side dry-run apply of the object, which
+/// includes defaults and the changes of admission webhooks, as applying it for real would.
+async fn diff_object(
+    client: Client,
+    discovery: &Discovery,
+    object: &DynamicObject,
+) -> DynResult<Vec<DiffLine>> {
+    let (api, name) = target(client, resource(discovery, object)?, object)?;
+    let live = api.get_opt(name).await?;
+    let applied = api
+        .patch(
+            name,
+            &PatchParams::apply(FIELD_MANAGER).dry_run(),
+            &Patch::Apply(object),
+        )
+        .await?;
+    Ok(diff_lines(&comparable(live)?, &comparable(Some(applied))?))
+}
+
+/// The object as YAML without the fields that applying it doesn't set but that change anyway,
+/// or nothing if there is no object.
+fn comparable(object: Option<DynamicObject>) -> DynResult<String> {
+    let Some(mut object) = object else {
+        return Ok(String::new());
+    };
+    object.metadata.managed_fields = None;
+    if let Some(data) = object.data.as_object_mut() {
+        data.remove("status");
+    }
+    Ok(serde_yaml::to_string(&object)?)
+}
+
+/// What applying an object from a manifest did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ApplyOutcome {
+    Created,
+    Configured,
+    Unchanged,
+}
+
+impl Display for ApplyOutcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Created => write!(f, "created"),
+            Self::Configured => write!(f, "configured"),
+            Self::Unchanged => write!(f, "unchanged"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ObjectApply {
+    /// The object, like `Deployment default/web`.
+    pub(crate) object: String,
+    pub(crate) outcome: Result<ApplyOutcome, String>,
+}
+
+/// The order to apply objects in: Namespaces and CustomResourceDefinitions before the objects
+/// that may be in them or of their kinds, and everything else in the order it was given.
+fn apply_order(object: &DynamicObject) -> u8 {
+    match object.types.as_ref().map(|t| t.kind.as_str()) {
+        Some("Namespace") => 0,
+        Some("CustomResourceDefinition") => 1,
+        _ => 2,
+    }
+}
+
+/// Server-side apply each object, like `kubectl apply --server-side`, Namespaces and CRDs first.
+///
+/// Objects of kinds that the applied CRDs define aren't known until the cluster is discovered
+/// again, which happens at most once, after the CRDs are applied.
+pub(crate) async fn apply(
+    client: Client,
+    discovery: &mut Discovery,
+    objects: &[DynamicObject],
+) -> Vec<ObjectApply> {
+    let mut objects: Vec<&DynamicObject> = objects.iter().collect();
+    objects.sort_by_key(|o| apply_order(o));
+
+    let mut applied_crds = false;
+    let mut applies = vec![];
+    for object in objects {
+        if applied_crds && resource(discovery, object).is_err() {
+            applied_crds = false;
+            if let Ok(rediscovered) = Discovery::discover(&client).await {
+                *discovery = rediscovered;
+            }
+        }
+        let outcome = apply_object(client.clone(), discovery, object).await;
+        if outcome.is_ok() && apply_order(object) == 1 {
+            applied_crds = true;
+        }
+        applies.push(ObjectApply {
+            object: describe(object),
+            outcome: outcome.map_err(|e| e.to_string()),
+        });
+    }
+    applies
+}
+
+/// Apply the object, telling whether it changed by whether its resource version did.
+async fn apply_object(
+    client: Client,
+    discovery: &Discovery,
+    object: &DynamicObject,
+) -> DynResult<ApplyOutcome> {
+    let (api, name) = target(client, resource(discovery, object)?, object)?;
+    let live = api.get_opt(name).await?;
+    let applied = api
+        .patch(
+            name,
+            &PatchParams::apply(FIELD_MANAGER),
+            &Patch::Apply(object),
+        )
+        .await?;
+    Ok(match live {
+        None => ApplyOutcome::Created,
+        Some(live) if live.resource_version() == applied.resource_version() => {
+            ApplyOutcome::Unchanged
+        }
+        Some(_) => ApplyOutcome::Configured,
+    })
+}
+
+/// Write what applying each object did, colorizing errors if `color`,
+/// returning whether every object was applied.
+pub(crate) fn write_applies(
+    out: &mut impl io::Write,
+    applies: &[ObjectApply],
+    color: bool,
+) -> io::Result<bool> {
+    let mut ok = true;
+    for ObjectApply { object, outcome } in applies {
+        match outcome {
+            Ok(outcome) => writeln!(out, "{object} {outcome}")?,
+            Err(e) => {
+                ok = false;
+                let error = format!("error: {object}: {e}");
+                let style = if color {
+                    ContentStyle::new().red()
+                } else {
+                    ContentStyle::new()
+                };
+                writeln!(out, "{}", style.apply(error))?;
+            }
+        }
+    }
+    Ok(ok)
+}
+
+/// Write the diffs of the objects that would change, colorized if `color`,
+/// returning whether any would.
+pub(crate) fn write_diffs(
+    out: &mut impl io::Write,
+    diffs: &[ObjectDiff],
+    color: bool,
+) -> io::Result<bool> {
+    let paint = |style: ContentStyle, text: String| {
+        if color { style } else { ContentStyle::new() }.apply(text)
+    };
+    let mut changed = false;
+    for ObjectDiff { object, diff } in diffs {
+        match diff {
+            Ok(lines) if lines.is_empty() => {}
+            Ok(lines) => {
+                changed = true;
+                writeln!(
+                    out,
+                    "{}",
+                    paint(ContentStyle::new().bold(), format!("diff {object}"))
+                )?;
+                for line in lines {
+                    let style = match line {
+                        DiffLine::Hunk(_) => ContentStyle::new().cyan(),
+                        DiffLine::Context(_) => ContentStyle::new(),
+                        DiffLine::Added(_) => ContentStyle::new().green(),
+                        DiffLine::Removed(_) => ContentStyle::new().red(),
+                    };
+                    writeln!(out, "{}", paint(style, line.text()))?;
+                }
+            }
+            Err(e) => {
+                let error = format!("error: {object}: {e}");
+                writeln!(out, "{}", paint(ContentStyle::new().red(), error))?;
+            }
+        }
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::*;
+
+    #[rstest]
+    fn test_parse() {
+        let objects = parse(
+            "---\n\
+             apiVersion: v1\nkind: Namespace\nmetadata:\n  name: web\n\
+             ---\n\
+             # nothing here\n\
+             ---\n\
+             apiVersion: apps/v1\nkind: Deployment\nmetadata:\n  name: web\n  namespace: web\n",
+        )
+        .unwrap();
+        let described: Vec<String> = objects.iter().map(describe).collect();
+        assert_eq!(described, vec!["Namespace web", "Deployment web/web"]);
+    }
+
+    #[rstest]
+    fn test_parse_error() {
+        assert!(parse("apiVersion: v1\nkind: [\n").is_err());
+    }
+
+    #[rstest]
+    fn test_comparable_strips_managed_fields_and_status() {
+        let object: DynamicObject = serde_json::from_value(json!({
+            "apiVersion": "v1",
+            "kind": "ConfigMap",
+            "metadata": {
+                "name": "web",
+                "managedFields": [{"manager": "flotilla", "operation": "Apply"}],
+            },
+            "data": {"a": "b"},
+            "status": {"phase": "Active"},
+        }))
+        .unwrap();
+        assert_eq!(
+            comparable(Some(object)).unwrap(),
+            "apiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: web\ndata:\n  a: b\n"
+        );
+        assert_eq!(comparable(None).unwrap(), "");
+    }
+
+    #[rstest]
+    fn test_write_diffs() {
+        let diffs = vec![
+            ObjectDiff {
+                object: "ConfigMap default/same".into(),
+                diff: Ok(vec![]),
+            },
+            ObjectDiff {
+                object: "ConfigMap default/web".into(),
+                diff: Ok(diff_lines("a: b\n", "a: c\n")),
+            },
+            ObjectDiff {
+                object: "Widget default/web".into(),
+                diff: Err("unknown kind".into()),
+            },
+        ];
+        let mut out = vec![];
+        assert!(write_diffs(&mut out, &diffs, false).unwrap());
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "diff ConfigMap default/web\n\
+             @@ -1 +1 @@\n\
+             -a: b\n\
+             +a: c\n\
+             error: Widget default/web: unknown kind\n"
+        );
+        assert!(!write_diffs(&mut vec![], &diffs[..1], false).unwrap());
+    }
+
+    #[rstest]
+    fn test_read_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let manifest = |kind: &str, name: &str| {
+            format!("apiVersion: v1\nkind: {kind}\nmetadata:\n  name: {name}\n")
+        };
+        fs::write(dir.path().join("b.yaml"), manifest("ConfigMap", "b")).unwrap();
+        fs::write(
+            dir.path().join("a.yml"),
+            format!(
+                "{}---\n{}",
+                manifest("ConfigMap", "a1"),
+                manifest("Secret", "a2")
+            ),
+        )
+        .unwrap();
+        fs::write(dir.path().join("notes.txt"), "not a manifest").unwrap();
+
+        let objects = read(dir.path()).unwrap();
+        let described: Vec<String> = objects.iter().map(describe).collect();
+        assert_eq!(described, vec!["ConfigMap a1", "Secret a2", "ConfigMap b"]);
+    }
+
+    #[rstest]
+    #[case("Namespace", 0)]
+    #[case("CustomResourceDefinition", 1)]
+    #[case("Deployment", 2)]
+    fn test_apply_order(#[case] kind: &str, #[case] expected: u8) {
+        let object: DynamicObject = serde_json::from_value(json!({
+            "apiVersion": "v1",
+            "kind": kind,
+            "metadata": {"name": "web"},
+        }))
+        .unwrap();
+        assert_eq!(apply_order(&object), expected);
+    }
+
+    #[rstest]
+    fn test_write_applies() {
+        let applies = vec![
+            ObjectApply {
+                object: "Namespace web".into(),
+                outcome: Ok(ApplyOutcome::Created),
+            },
+            ObjectApply {
+                object: "Deployment web/web".into(),
+                outcome: Ok(ApplyOutcome::Configured),
+            },
+            ObjectApply {
+                object: "ConfigMap web/web".into(),
+                outcome: Ok(ApplyOutcome::Unchanged),
+            },
+            ObjectApply {
+                object: "Widget web/web".into(),
+                outcome: Err("unknown kind".into()),
+            },
+        ];
+        let mut out = vec![];
+        assert!(!write_applies(&mut out, &applies, false).unwrap());
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "Namespace web created\n\
+             Deployment web/web configured\n\
+             ConfigMap web/web unchanged\n\
+             error: Widget web/web: unknown kind\n"
+        );
+        assert!(write_applies(&mut vec![], &applies[..3], false).unwrap());
+    }
+}
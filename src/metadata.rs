@@ -0,0 +1,198 @@
+use std::collections::{BTreeMap, HashSet};
+
+use futures::future::join_all;
+use kube::{
+    api::{DynamicObject, Patch},
+    Client,
+};
+use serde_json::{json, Value};
+
+use crate::{state::ObjectRef, workloads::patch_params, DynResult};
+
+/// Where in an object's metadata an entry lives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum Section {
+    Labels,
+    Annotations,
+}
+
+impl Section {
+    /// The name of the section's field in `metadata`.
+    pub(crate) fn field(self) -> &'static str {
+        match self {
+            Self::Labels => "labels",
+            Self::Annotations => "annotations",
+        }
+    }
+
+    pub(crate) fn title(self) -> &'static str {
+        match self {
+            Self::Labels => "Labels",
+            Self::Annotations => "Annotations",
+        }
+    }
+}
+
+/// A label or annotation of one or more objects, with its value on each of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct MetadataEntry {
+    pub(crate) section: Section,
+    pub(crate) key: String,
+    /// The entry's value on each object, or `None` for objects that don't have it.
+    pub(crate) values: Vec<Option<String>>,
+}
+
+impl MetadataEntry {
+    /// The value of the entry if every object has the same one.
+    pub(crate) fn common(&self) -> Option<&str> {
+        let first = self.values.first()?.as_deref()?;
+        self.values
+            .iter()
+            .all(|v| v.as_deref() == Some(first))
+            .then_some(first)
+    }
+
+    /// How the objects differ, for entries without a common value.
+    pub(crate) fn difference(&self) -> String {
+        let set = self.values.iter().flatten().count();
+        let distinct = self.values.iter().flatten().collect::<HashSet<_>>().len();
+        match (set, distinct) {
+            (set, 1) => format!("set on {set} of {}", self.values.len()),
+            (set, distinct) => format!("{distinct} values, set on {set} of {}", self.values.len()),
+        }
+    }
+}
+
+/// The labels and annotations of the objects, ordered by section and key.
+pub(crate) fn entries(objects: &[DynamicObject]) -> Vec<MetadataEntry> {
+    let mut entries: BTreeMap<(Section, String), Vec<Option<String>>> = BTreeMap::new();
+    for (idx, object) in objects.iter().enumerate() {
+        for (section, map) in [
+            (Section::Labels, &object.metadata.labels),
+            (Section::Annotations, &object.metadata.annotations),
+        ] {
+            for (key, value) in map.iter().flatten() {
+                entries
+                    .entry((section, key.clone()))
+                    .or_insert_with(|| vec![None; objects.len()])[idx] = Some(value.clone());
+            }
+        }
+    }
+    entries
+        .into_iter()
+        .map(|((section, key), values)| MetadataEntry {
+            section,
+            key,
+            values,
+        })
+        .collect()
+}
+
+/// The labels and annotations of the targets.
+pub(crate) async fn fetch(client: Client, targets: &[ObjectRef]) -> DynResult<Vec<MetadataEntry>> {
+    let objects = join_all(
+        targets
+            .iter()
+            .map(|target| async { target.api(client.clone()).get(&target.name).await }),
+    )
+    .await
+    .into_iter()
+    .collect::<Result<Vec<_>, _>>()?;
+    Ok(entries(&objects))
+}
+
+/// Set (or, given `None`, remove) an entry on every target, returning the errors for the targets
+/// where that failed.
+pub(crate) async fn set(
+    client: Client,
+    targets: &[ObjectRef],
+    section: Section,
+    key: &str,
+    value: Option<&str>,
+) -> Vec<String> {
+    let patch = metadata_patch(section, key, value);
+    join_all(targets.iter().map(|target| {
+        let (client, patch) = (client.clone(), &patch);
+        async move {
+            target
+                .api(client)
+                .patch(&target.name, &patch_params(), &Patch::Merge(patch))
+                .await
+                .map_err(|e| format!("{}: {e}", target.name))
+        }
+    }))
+    .await
+    .into_iter()
+    .filter_map(Result::err)
+    .collect()
+}
+
+/// A JSON merge patch of a single entry, in which `null` removes the entry.
+fn metadata_patch(section: Section, key: &str, value: Option<&str>) -> Value {
+    json!({"metadata": {section.field(): {key: value}}})
+}
+
+/// Split a new entry typed as `key=value` into its key and value.
+pub(crate) fn parse_entry(input: &str) -> Option<(&str, &str)> {
+    let (key, value) = input.split_once('=')?;
+    let key = key.trim();
+    (!key.is_empty()).then_some((key, value.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn object(labels: Value) -> DynamicObject {
+        serde_json::from_value(json!({
+            "metadata": {"name": "x", "labels": labels, "annotations": {"note": "hi"}},
+        }))
+        .unwrap()
+    }
+
+    #[rstest]
+    fn test_entries() {
+        let entries = entries(&[
+            object(json!({"app": "web", "tier": "a"})),
+            object(json!({"app": "web", "tier": "b", "canary": "true"})),
+        ]);
+        let summary: Vec<(Section, &str, Option<&str>)> = entries
+            .iter()
+            .map(|e| (e.section, e.key.as_str(), e.common()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Section::Labels, "app", Some("web")),
+                (Section::Labels, "canary", None),
+                (Section::Labels, "tier", None),
+                (Section::Annotations, "note", Some("hi")),
+            ]
+        );
+        assert_eq!(entries[1].difference(), "set on 1 of 2");
+        assert_eq!(entries[2].difference(), "2 values, set on 2 of 2");
+    }
+
+    #[rstest]
+    #[case(Section::Labels, Some("web"), json!({"metadata": {"labels": {"app": "web"}}}))]
+    #[case(Section::Annotations, None, json!({"metadata": {"annotations": {"app": null}}}))]
+    fn test_metadata_patch(
+        #[case] section: Section,
+        #[case] value: Option<&str>,
+        #[case] expected: Value,
+    ) {
+        assert_eq!(metadata_patch(section, "app", value), expected);
+    }
+
+    #[rstest]
+    #[case("app=web", Some(("app", "web")))]
+    #[case("app=", Some(("app", "")))]
+    #[case(" app = a=b", Some(("app", "a=b")))]
+    #[case("=web", None)]
+    #[case("app", None)]
+    fn test_parse_entry(#[case] input: &str, #[case] expected: Option<(&str, &str)>) {
+        assert_eq!(parse_entry(input), expected);
+    }
+}
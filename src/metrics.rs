@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+
+use kube::{
+    api::{DynamicObject, ListParams},
+    Client,
+};
+use serde_json::Value;
+
+use crate::{
+    discovery::{DiscoveredAPIResource, Discovery},
+    table::{ColumnDefinition, ResourceRow, ResourceRowCellValue, ResourceTable},
+};
+
+/// The resources whose tables get usage columns from `metrics.k8s.io`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MetricsKind {
+    Pods,
+    Nodes,
+}
+
+impl MetricsKind {
+    pub(crate) fn of(resource: &DiscoveredAPIResource) -> Option<Self> {
+        match (resource.group.as_str(), resource.plural.as_str()) {
+            ("", "pods") => Some(Self::Pods),
+            ("", "nodes") => Some(Self::Nodes),
+            _ => None,
+        }
+    }
+
+    /// The name of the corresponding `metrics.k8s.io` resource, which is only discovered if
+    /// metrics-server (or another metrics API) is installed.
+    fn metrics_resource(self) -> &'static str {
+        match self {
+            Self::Pods => "pods.metrics.k8s.io",
+            Self::Nodes => "nodes.metrics.k8s.io",
+        }
+    }
+}
+
+/// CPU (in cores) and memory (in bytes).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct Usage {
+    pub(crate) cpu: f64,
+    pub(crate) memory: f64,
+}
+
+impl Usage {
+    fn of(&self, resource: &str) -> f64 {
+        match resource {
+            "cpu" => self.cpu,
+            _ => self.memory,
+        }
+    }
+}
+
+/// The usage of each object of the kind, by namespace and name, or `None` if metrics are
+/// unavailable, in which case the usage columns are left out.
+pub(crate) async fn usage(
+    client: Client,
+    discovery: &Discovery,
+    kind: MetricsKind,
+    namespace: Option<&str>,
+) -> Option<HashMap<(Option<String>, String), Usage>> {
+    let resource = discovery.get(kind.metrics_resource())?;
+    let list = resource
+        .api(client, namespace)
+        .list(&ListParams::default())
+        .await
+        .ok()?;
+    Some(
+        list.items
+            .into_iter()
+            .filter_map(|m| {
+                let usage = match kind {
+                    MetricsKind::Pods => m.data["containers"]
+                        .as_array()?
+                        .iter()
+                        .filter_map(|c| resource_usage(&c["usage"]))
+                        .fold(Usage::default(), |a, b| Usage {
+                            cpu: a.cpu + b.cpu,
+                            memory: a.memory + b.memory,
+                        }),
+                    MetricsKind::Nodes => resource_usage(&m.data["usage"])?,
+                };
+                Some(((m.metadata.namespace, m.metadata.name?), usage))
+            })
+            .collect(),
+    )
+}
+
+fn resource_usage(resources: &Value) -> Option<Usage> {
+    Some(Usage {
+        cpu: parse_quantity(resources["cpu"].as_str()?)?,
+        memory: parse_quantity(resources["memory"].as_str()?)?,
+    })
+}
+
+/// Add usage columns to a table of the kind, whose rows carry their whole objects.
+pub(crate) fn add_usage_columns(
+    table: &mut ResourceTable,
+    kind: MetricsKind,
+    usage: &HashMap<(Option<String>, String), Usage>,
+) {
+    let usage_of = |row: &ResourceRow| {
+        usage
+            .get(&(row.namespace().map(String::from), row.name()?.to_string()))
+            .copied()
+    };
+    table.insert_column(
+        ColumnDefinition::computed("CPU", "CPU usage in millicores", 0),
+        |row| usage_of(row).map_or_else(not_available, |u| cpu(u.cpu)),
+    );
+    table.insert_column(
+        ColumnDefinition::computed("MEM", "Memory usage in MiB", 0),
+        |row| usage_of(row).map_or_else(not_available, |u| memory(u.memory)),
+    );
+
+    let columns: &[(&str, &str, u8, &str, Total)] = match kind {
+        MetricsKind::Pods => &[
+            ("%CPU/R", "CPU usage of requests", 0, "cpu", requests),
+            ("%MEM/R", "Memory usage of requests", 0, "memory", requests),
+            ("%CPU/L", "CPU usage of limits", 1, "cpu", limits),
+            ("%MEM/L", "Memory usage of limits", 1, "memory", limits),
+        ],
+        MetricsKind::Nodes => &[
+            ("%CPU", "CPU usage of allocatable", 0, "cpu", allocatable),
+            (
+                "%MEM",
+                "Memory usage of allocatable",
+                0,
+                "memory",
+                allocatable,
+            ),
+        ],
+    };
+    for (name, description, priority, resource, total) in columns {
+        table.insert_column(
+            ColumnDefinition::computed(name, description, *priority),
+            |row| {
+                let used = usage_of(row).map(|u| u.of(resource));
+                let total = row.object.as_ref().and_then(|o| total(o, resource));
+                match (used, total) {
+                    (Some(used), Some(total)) if total > 0.0 => percent(used / total),
+                    _ => not_available(),
+                }
+            },
+        );
+    }
+}
+
+/// How much of a resource (`cpu` or `memory`) an object has, to measure its usage against.
+type Total = fn(&DynamicObject, &str) -> Option<f64>;
+
+fn requests(pod: &DynamicObject, resource: &str) -> Option<f64> {
+    pod_total(pod, "requests", resource)
+}
+
+fn limits(pod: &DynamicObject, resource: &str) -> Option<f64> {
+    pod_total(pod, "limits", resource)
+}
+
+/// The sum of a resource's requests (or limits) over a pod's containers. Limits are only
+/// defined if every container has one, since a container without a limit can use anything.
+fn pod_total(pod: &DynamicObject, field: &str, resource: &str) -> Option<f64> {
+    let containers = pod.data["spec"]["containers"].as_array()?;
+    let values = containers.iter().map(|c| {
+        c["resources"][field][resource]
+            .as_str()
+            .and_then(parse_quantity)
+    });
+    if field == "limits" {
+        values.sum()
+    } else {
+        Some(values.flatten().sum())
+    }
+}
+
+fn allocatable(node: &DynamicObject, resource: &str) -> Option<f64> {
+    parse_quantity(node.data["status"]["allocatable"][resource].as_str()?)
+}
+
+fn cpu(cores: f64) -> ResourceRowCellValue {
+    ResourceRowCellValue::Measured {
+        value: cores,
+        display: format!("{:.0}m", cores * 1000.0),
+    }
+}
+
+fn memory(bytes: f64) -> ResourceRowCellValue {
+    ResourceRowCellValue::Measured {
+        value: bytes,
+        display: format!("{:.0}Mi", bytes / (1 << 20) as f64),
+    }
+}
+
+fn percent(fraction: f64) -> ResourceRowCellValue {
+    ResourceRowCellValue::Measured {
+        value: fraction,
+        display: format!("{:.0}%", fraction * 100.0),
+    }
+}
+
+fn not_available() -> ResourceRowCellValue {
+    ResourceRowCellValue::String("n/a".into())
+}
+
+/// Parse a Kubernetes quantity, like `250m`, `1.5Gi` or `129e6`, into a number.
+pub(crate) fn parse_quantity(quantity: &str) -> Option<f64> {
+    let quantity = quantity.trim();
+    let idx = quantity
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '+' || c == '-'))
+        .unwrap_or(quantity.len());
+    let (number, suffix) = quantity.split_at(idx);
+    let multiplier = match suffix {
+        "" => 1.0,
+        "n" => 1e-9,
+        "u" => 1e-6,
+        "m" => 1e-3,
+        "k" => 1e3,
+        "M" => 1e6,
+        "G" => 1e9,
+        "T" => 1e12,
+        "P" => 1e15,
+        "E" => 1e18,
+        "Ki" => 2f64.powi(10),
+        "Mi" => 2f64.powi(20),
+        "Gi" => 2f64.powi(30),
+        "Ti" => 2f64.powi(40),
+        "Pi" => 2f64.powi(50),
+        "Ei" => 2f64.powi(60),
+        // A decimal exponent, like 129e6.
+        e if e.starts_with(['e', 'E']) => 10f64.powi(e[1..].parse().ok()?),
+        _ => return None,
+    };
+    Some(number.parse::<f64>().ok()? * multiplier)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("250m", Some(0.25))]
+    #[case("2", Some(2.0))]
+    #[case("1.5", Some(1.5))]
+    #[case("123456789n", Some(0.123456789))]
+    #[case("128Mi", Some(134217728.0))]
+    #[case("1Gi", Some(1073741824.0))]
+    #[case("1k", Some(1000.0))]
+    #[case("129e6", Some(129e6))]
+    #[case("1E", Some(1e18))]
+    #[case("12x", None)]
+    #[case("", None)]
+    fn test_parse_quantity(#[case] quantity: &str, #[case] expected: Option<f64>) {
+        match (parse_quantity(quantity), expected) {
+            (Some(q), Some(e)) => assert!((q - e).abs() < 1e-9 * e.abs().max(1.0), "{q} != {e}"),
+            (q, e) => assert_eq!(q, e),
+        }
+    }
+
+    fn pods() -> ResourceTable {
+        serde_json::from_value(serde_json::json!({
+            "columnDefinitions": [
+                {"name": "Name", "type": "string", "description": "", "format": "name", "priority": 0},
+                {"name": "Age", "type": "string", "description": "", "format": "date", "priority": 0},
+            ],
+            "rows": [
+                {"cells": ["web", "5m"], "object": {
+                    "metadata": {"name": "web", "namespace": "default"},
+                    "spec": {"containers": [
+                        {"resources": {"requests": {"cpu": "100m", "memory": "64Mi"}, "limits": {"cpu": "1"}}},
+                        {"resources": {"requests": {"cpu": "100m"}}},
+                    ]},
+                }},
+                {"cells": ["pending", "1m"], "object": {
+                    "metadata": {"name": "pending", "namespace": "default"},
+                    "spec": {"containers": [{}]},
+                }},
+            ],
+        }))
+        .unwrap()
+    }
+
+    #[rstest]
+    fn test_add_usage_columns() {
+        let mut table = pods();
+        let usage = HashMap::from([(
+            (Some("default".to_string()), "web".to_string()),
+            Usage {
+                cpu: 0.05,
+                memory: 32.0 * (1 << 20) as f64,
+            },
+        )]);
+        add_usage_columns(&mut table, MetricsKind::Pods, &usage);
+
+        let names: Vec<&str> = table
+            .column_definitions
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["Name", "CPU", "MEM", "%CPU/R", "%MEM/R", "%CPU/L", "%MEM/L", "Age"]
+        );
+        let cells = |idx: usize| -> Vec<String> {
+            table.rows[idx]
+                .cells
+                .iter()
+                .map(|c| c.to_string())
+                .collect()
+        };
+        assert_eq!(
+            cells(0),
+            vec!["web", "50m", "32Mi", "25%", "50%", "n/a", "n/a", "5m"]
+        );
+        assert_eq!(
+            cells(1),
+            vec!["pending", "n/a", "n/a", "n/a", "n/a", "n/a", "n/a", "1m"]
+        );
+    }
+}
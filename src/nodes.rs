@@ -0,0 +1,318 @@
+use std::{
+    fmt::{Display, Formatter},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use k8s_openapi::api::core::v1::{Node, Pod};
+use kube::{
+    api::{EvictParams, ListParams, Patch},
+    Api, Client,
+};
+use serde_json::json;
+use tokio::task::{AbortHandle, JoinSet};
+
+use crate::{state::ObjectRef, workloads::patch_params, DynResult};
+
+/// How long to wait before retrying an eviction that a PodDisruptionBudget doesn't allow yet.
+const EVICTION_RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How many times to try an eviction before giving up on it, which is five minutes of retries.
+/// A disruption budget may never allow it, like one with `minAvailable: 1` for a single replica.
+const MAX_EVICTION_ATTEMPTS: u32 = 60;
+
+/// How often to check whether an evicted pod is gone.
+const DELETION_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+const MIRROR_POD_ANNOTATION: &str = "kubernetes.io/config.mirror";
+
+pub(crate) fn is_node(target: &ObjectRef) -> bool {
+    target.resource.group.is_empty() && target.resource.plural == "nodes"
+}
+
+/// Mark the node as unschedulable (or schedulable again), like `kubectl cordon`.
+pub(crate) async fn cordon(client: Client, node: &str, unschedulable: bool) -> DynResult<()> {
+    Api::<Node>::all(client)
+        .patch(
+            node,
+            &patch_params(),
+            &Patch::Merge(json!({"spec": {"unschedulable": unschedulable}})),
+        )
+        .await?;
+    Ok(())
+}
+
+/// The progress of evicting the pods from some nodes.
+#[derive(Debug, Default)]
+pub(crate) struct Drain {
+    pub(crate) nodes: Vec<String>,
+    pub(crate) evictions: Vec<Eviction>,
+    /// Pods that are left alone, with the reason why.
+    pub(crate) skipped: Vec<(String, &'static str)>,
+    pub(crate) finished: bool,
+}
+
+/// A drain running in the background, which is cancelled when this is dropped,
+/// like when its dialog is closed.
+#[derive(Debug)]
+pub(crate) struct Draining {
+    pub(crate) progress: Arc<Mutex<Drain>>,
+    task: AbortHandle,
+}
+
+impl Drop for Draining {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl Drain {
+    pub(crate) fn summary(&self) -> String {
+        let gone = self
+            .evictions
+            .iter()
+            .filter(|e| e.status == EvictionStatus::Deleted)
+            .count();
+        let failed = self
+            .evictions
+            .iter()
+            .filter(|e| matches!(e.status, EvictionStatus::Failed(_)))
+            .count();
+        let state = if self.finished { "done" } else { "draining" };
+        match failed {
+            0 => format!("{state}: {gone} of {} pods evicted", self.evictions.len()),
+            failed => format!(
+                "{state}: {gone} of {} pods evicted, {failed} failed",
+                self.evictions.len()
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Eviction {
+    /// The pod, as `namespace/name`.
+    pub(crate) pod: String,
+    pub(crate) status: EvictionStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum EvictionStatus {
+    Pending,
+    /// A PodDisruptionBudget doesn't allow the eviction yet, so it is retried.
+    Blocked {
+        attempts: u32,
+        reason: String,
+    },
+    /// The eviction was accepted, and the pod is shutting down.
+    Evicted,
+    Deleted,
+    Failed(String),
+}
+
+impl Display for EvictionStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Pending => write!(f, "pending"),
+            Self::Blocked { attempts, reason } => {
+                write!(f, "blocked after {attempts} attempts, retrying: {reason}")
+            }
+            Self::Evicted => write!(f, "evicted, terminating"),
+            Self::Deleted => write!(f, "gone"),
+            Self::Failed(e) => write!(f, "failed: {e}"),
+        }
+    }
+}
+
+/// Why a drain leaves the pod alone, if it does: DaemonSets would recreate their pods
+/// on the node anyway, static pods are managed by the kubelet rather than the API,
+/// and pods that have finished aren't running anything to move.
+fn skip_reason(pod: &Pod) -> Option<&'static str> {
+    let metadata = &pod.metadata;
+    let phase = pod.status.as_ref().and_then(|s| s.phase.as_deref());
+    if matches!(phase, Some("Succeeded" | "Failed")) {
+        Some("finished")
+    } else if metadata
+        .owner_references
+        .iter()
+        .flatten()
+        .any(|o| o.kind == "DaemonSet" && o.controller == Some(true))
+    {
+        Some("DaemonSet")
+    } else if metadata
+        .annotations
+        .as_ref()
+        .is_some_and(|a| a.contains_key(MIRROR_POD_ANNOTATION))
+    {
+        Some("static pod")
+    } else {
+        None
+    }
+}
+
+/// Cordon the nodes, then evict their pods through the Eviction API in the background, which
+/// respects PodDisruptionBudgets by refusing evictions they don't allow (with a 429) until
+/// enough other pods are ready.
+pub(crate) async fn drain(client: Client, nodes: Vec<String>) -> DynResult<Draining> {
+    let mut drain = Drain::default();
+    let mut pods = vec![];
+    for node in &nodes {
+        cordon(client.clone(), node, true).await?;
+        let list = Api::<Pod>::all(client.clone())
+            .list(&ListParams::default().fields(&format!("spec.nodeName={node}")))
+            .await?;
+        for pod in list.items {
+            let (Some(namespace), Some(name)) =
+                (pod.metadata.namespace.clone(), pod.metadata.name.clone())
+            else {
+                continue;
+            };
+            let label = format!("{namespace}/{name}");
+            match skip_reason(&pod) {
+                Some(reason) => drain.skipped.push((label, reason)),
+                None => {
+                    drain.evictions.push(Eviction {
+                        pod: label,
+                        status: EvictionStatus::Pending,
+                    });
+                    pods.push((namespace, name, pod.metadata.uid));
+                }
+            }
+        }
+    }
+    drain.nodes = nodes;
+
+    let drain = Arc::new(Mutex::new(drain));
+    let progress = drain.clone();
+    // Aborting the task drops its evictions, which aborts them too.
+    let task = tokio::spawn(async move {
+        let mut evictions = JoinSet::new();
+        for (idx, (namespace, name, uid)) in pods.into_iter().enumerate() {
+            let api: Api<Pod> = Api::namespaced(client.clone(), &namespace);
+            let progress = progress.clone();
+            evictions.spawn(async move {
+                let status = evict(&api, &name, uid.as_deref(), |status| {
+                    progress.lock().unwrap().evictions[idx].status = status
+                })
+                .await;
+                progress.lock().unwrap().evictions[idx].status = status;
+            });
+        }
+        while evictions.join_next().await.is_some() {}
+        progress.lock().unwrap().finished = true;
+    });
+    Ok(Draining {
+        progress: drain,
+        task: task.abort_handle(),
+    })
+}
+
+/// Evict the pod, retrying while a disruption budget doesn't allow it (up to
+/// [`MAX_EVICTION_ATTEMPTS`] times), and wait for it to be gone, reporting the status along the
+/// way and returning the final status.
+async fn evict(
+    api: &Api<Pod>,
+    name: &str,
+    uid: Option<&str>,
+    report: impl Fn(EvictionStatus),
+) -> EvictionStatus {
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        match api.evict(name, &EvictParams::default()).await {
+            Ok(_) => break,
+            Err(kube::Error::Api(e)) if e.code == 404 => return EvictionStatus::Deleted,
+            Err(kube::Error::Api(e)) if e.code == 429 && attempts >= MAX_EVICTION_ATTEMPTS => {
+                return EvictionStatus::Failed(format!(
+                    "still blocked after {attempts} attempts: {}",
+                    e.message
+                ));
+            }
+            Err(kube::Error::Api(e)) if e.code == 429 => {
+                report(EvictionStatus::Blocked {
+                    attempts,
+                    reason: e.message,
+                });
+                tokio::time::sleep(EVICTION_RETRY_INTERVAL).await;
+            }
+            Err(e) => return EvictionStatus::Failed(e.to_string()),
+        }
+    }
+
+    report(EvictionStatus::Evicted);
+    loop {
+        match api.get_opt(name).await {
+            // A pod with the same name but another uid has replaced it, like in a StatefulSet.
+            Ok(Some(pod)) if pod.metadata.uid.as_deref() == uid => {
+                tokio::time::sleep(DELETION_POLL_INTERVAL).await
+            }
+            Ok(_) => return EvictionStatus::Deleted,
+            Err(e) => return EvictionStatus::Failed(e.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn pod(metadata: serde_json::Value) -> Pod {
+        serde_json::from_value(json!({ "metadata": metadata })).unwrap()
+    }
+
+    #[rstest]
+    #[case("Running", None)]
+    #[case("Succeeded", Some("finished"))]
+    #[case("Failed", Some("finished"))]
+    fn test_skip_finished_pods(#[case] phase: &str, #[case] expected: Option<&str>) {
+        let pod: Pod = serde_json::from_value(
+            json!({"metadata": {"name": "job-abc"}, "status": {"phase": phase}}),
+        )
+        .unwrap();
+        assert_eq!(skip_reason(&pod), expected);
+    }
+
+    #[rstest]
+    #[case(json!({"name": "web"}), None)]
+    #[case(
+        json!({"name": "fluentd", "ownerReferences": [
+            {"apiVersion": "apps/v1", "kind": "DaemonSet", "name": "fluentd", "uid": "1", "controller": true},
+        ]}),
+        Some("DaemonSet")
+    )]
+    #[case(
+        json!({"name": "web", "ownerReferences": [
+            {"apiVersion": "apps/v1", "kind": "ReplicaSet", "name": "web", "uid": "1", "controller": true},
+        ]}),
+        None
+    )]
+    #[case(
+        json!({"name": "etcd", "annotations": {MIRROR_POD_ANNOTATION: "abc"}}),
+        Some("static pod")
+    )]
+    fn test_skip_reason(#[case] metadata: serde_json::Value, #[case] expected: Option<&str>) {
+        assert_eq!(skip_reason(&pod(metadata)), expected);
+    }
+
+    #[rstest]
+    fn test_summary() {
+        let eviction = |status| Eviction {
+            pod: "ns/pod".into(),
+            status,
+        };
+        let mut drain = Drain {
+            evictions: vec![
+                eviction(EvictionStatus::Deleted),
+                eviction(EvictionStatus::Evicted),
+                eviction(EvictionStatus::Failed("nope".into())),
+            ],
+            ..Drain::default()
+        };
+        assert_eq!(drain.summary(), "draining: 1 of 3 pods evicted, 1 failed");
+        drain.evictions.pop();
+        drain.finished = true;
+        assert_eq!(drain.summary(), "done: 1 of 2 pods evicted");
+    }
+}
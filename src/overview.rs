@@ -0,0 +1,476 @@
+use std::{cmp::Reverse, collections::BTreeMap, future::Future, time::Duration};
+
+use itertools::Itertools;
+use k8s_openapi::api::{
+    apps::v1::{DaemonSet, Deployment, StatefulSet},
+    core::v1::{Node, Pod},
+};
+use kube::{
+    api::{DynamicObject, ListParams},
+    Api, Client,
+};
+
+use crate::{discovery::Discovery, events::EventSummary, health::Health, state::Tab};
+
+/// The name of the pseudo-resource that tabs show the overview of their cluster as.
+pub(crate) const OVERVIEW: &str = "overview";
+
+/// How many of the most recent Warning events the overview shows.
+const RECENT_WARNINGS: usize = 10;
+
+/// The overview lists every pod in the cluster, so it is refreshed at most this often.
+pub(crate) const MIN_OVERVIEW_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A summary of a cluster.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Overview {
+    pub(crate) version: String,
+    pub(crate) nodes_ready: usize,
+    pub(crate) nodes: usize,
+    /// The number of pods in each phase, by namespace.
+    pub(crate) pods: BTreeMap<String, BTreeMap<String, usize>>,
+    pub(crate) failing: Vec<FailingWorkload>,
+    /// The most recent Warning events, newest first.
+    pub(crate) warnings: Vec<Warning>,
+    /// The sections that couldn't be read, like `nodes`, and why.
+    pub(crate) unreadable: BTreeMap<&'static str, String>,
+}
+
+/// A workload with fewer ready replicas than it wants.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FailingWorkload {
+    /// The plural name of the workload's resource, like `deployments`.
+    pub(crate) resource: &'static str,
+    pub(crate) namespace: String,
+    pub(crate) name: String,
+    pub(crate) ready: i32,
+    pub(crate) desired: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Warning {
+    pub(crate) namespace: Option<String>,
+    /// The object the event is about, like `Pod/web-1`.
+    pub(crate) object: String,
+    pub(crate) name: String,
+    pub(crate) summary: EventSummary,
+}
+
+/// A line of the overview, which may open a tab showing what it summarizes.
+#[derive(Debug, Clone)]
+pub(crate) struct OverviewItem {
+    pub(crate) label: String,
+    pub(crate) detail: String,
+    pub(crate) health: Option<Health>,
+    pub(crate) tab: Option<Tab>,
+}
+
+impl Overview {
+    /// Fetch the overview, listing everything it summarizes concurrently.
+    /// Sections whose lists fail, like nodes for a user who may only read their namespace,
+    /// are left empty and recorded as unreadable, so the rest of the overview is still shown.
+    pub(crate) async fn fetch(client: Client, discovery: &Discovery) -> Self {
+        let lp = ListParams::default();
+        let nodes = Api::<Node>::all(client.clone());
+        let pods = Api::<Pod>::all(client.clone());
+        let deployments = Api::<Deployment>::all(client.clone());
+        let statefulsets = Api::<StatefulSet>::all(client.clone());
+        let daemonsets = Api::<DaemonSet>::all(client.clone());
+        let warnings = async {
+            match discovery.get("events") {
+                Some(events) => {
+                    let lp = ListParams::default().fields("type=Warning");
+                    Ok(events.api(client.clone(), None).list(&lp).await?.items)
+                }
+                None => Ok::<_, kube::Error>(vec![]),
+            }
+        };
+        let (version, nodes, pods, deployments, statefulsets, daemonsets, warnings) = tokio::join!(
+            client.apiserver_version(),
+            section(async { Ok(nodes.list(&lp).await?.items) }),
+            section(async { Ok(pods.list(&lp).await?.items) }),
+            section(async { Ok(deployments.list(&lp).await?.items) }),
+            section(async { Ok(statefulsets.list(&lp).await?.items) }),
+            section(async { Ok(daemonsets.list(&lp).await?.items) }),
+            section(warnings),
+        );
+        let mut unreadable = BTreeMap::new();
+        let version = version.map_or_else(
+            |e| {
+                unreadable.insert("version", e.to_string());
+                String::new()
+            },
+            |v| v.git_version,
+        );
+        let mut overview = Self::build(
+            version,
+            &nodes.0,
+            &pods.0,
+            &deployments.0,
+            &statefulsets.0,
+            &daemonsets.0,
+            &warnings.0,
+        );
+        for (name, error) in [
+            ("nodes", nodes.1),
+            ("pods", pods.1),
+            ("deployments", deployments.1),
+            ("statefulsets", statefulsets.1),
+            ("daemonsets", daemonsets.1),
+            ("events", warnings.1),
+        ] {
+            if let Some(error) = error {
+                unreadable.insert(name, error);
+            }
+        }
+        overview.unreadable = unreadable;
+        overview
+    }
+
+    /// A one-line description of the sections that couldn't be read, if any.
+    pub(crate) fn unreadable_summary(&self) -> Option<String> {
+        let (_, error) = self.unreadable.first_key_value()?;
+        Some(format!(
+            "Couldn't read {} for the overview: {error}",
+            self.unreadable.keys().join(", ")
+        ))
+    }
+
+    pub(crate) fn build(
+        version: String,
+        nodes: &[Node],
+        pods: &[Pod],
+        deployments: &[Deployment],
+        statefulsets: &[StatefulSet],
+        daemonsets: &[DaemonSet],
+        warnings: &[DynamicObject],
+    ) -> Self {
+        let nodes_ready = nodes
+            .iter()
+            .filter(|n| {
+                n.status
+                    .iter()
+                    .flat_map(|s| s.conditions.iter().flatten())
+                    .any(|c| c.type_ == "Ready" && c.status == "True")
+            })
+            .count();
+
+        let mut pod_phases: BTreeMap<String, BTreeMap<String, usize>> = BTreeMap::new();
+        for pod in pods {
+            let phase = pod
+                .status
+                .as_ref()
+                .and_then(|s| s.phase.clone())
+                .unwrap_or_else(|| "Unknown".into());
+            *pod_phases
+                .entry(pod.metadata.namespace.clone().unwrap_or_default())
+                .or_default()
+                .entry(phase)
+                .or_default() += 1;
+        }
+
+        let workload =
+            |resource, namespace: &Option<String>, name: &Option<String>, ready, desired| {
+                (ready < desired).then(|| FailingWorkload {
+                    resource,
+                    namespace: namespace.clone().unwrap_or_default(),
+                    name: name.clone().unwrap_or_default(),
+                    ready,
+                    desired,
+                })
+            };
+        let mut failing: Vec<FailingWorkload> = deployments
+            .iter()
+            .filter_map(|d| {
+                let status = d.status.as_ref();
+                workload(
+                    "deployments",
+                    &d.metadata.namespace,
+                    &d.metadata.name,
+                    status.and_then(|s| s.available_replicas).unwrap_or(0),
+                    d.spec.as_ref().and_then(|s| s.replicas).unwrap_or(1),
+                )
+            })
+            .chain(statefulsets.iter().filter_map(|s| {
+                workload(
+                    "statefulsets",
+                    &s.metadata.namespace,
+                    &s.metadata.name,
+                    s.status
+                        .as_ref()
+                        .and_then(|s| s.ready_replicas)
+                        .unwrap_or(0),
+                    s.spec.as_ref().and_then(|s| s.replicas).unwrap_or(1),
+                )
+            }))
+            .chain(daemonsets.iter().filter_map(|d| {
+                let status = d.status.as_ref();
+                workload(
+                    "daemonsets",
+                    &d.metadata.namespace,
+                    &d.metadata.name,
+                    status.map_or(0, |s| s.number_ready),
+                    status.map_or(0, |s| s.desired_number_scheduled),
+                )
+            }))
+            .collect();
+        failing.sort_by(|a, b| (&a.namespace, &a.name).cmp(&(&b.namespace, &b.name)));
+
+        let mut warnings: Vec<Warning> = warnings
+            .iter()
+            .map(|e| {
+                let involved = &e.data["involvedObject"];
+                let regarding = if involved.is_object() {
+                    involved
+                } else {
+                    &e.data["regarding"]
+                };
+                let name = regarding["name"].as_str().unwrap_or_default().to_string();
+                Warning {
+                    namespace: e.metadata.namespace.clone(),
+                    object: format!("{}/{name}", regarding["kind"].as_str().unwrap_or_default()),
+                    name,
+                    summary: EventSummary::from_object(e),
+                }
+            })
+            .collect();
+        warnings.sort_by_key(|w| Reverse(w.summary.last_seen));
+        warnings.truncate(RECENT_WARNINGS);
+
+        Self {
+            version,
+            nodes_ready,
+            nodes: nodes.len(),
+            pods: pod_phases,
+            failing,
+            warnings,
+            unreadable: BTreeMap::new(),
+        }
+    }
+
+    /// The lines of the overview, for tabs on the cluster of `context`.
+    pub(crate) fn items(&self, context: Option<&str>) -> Vec<OverviewItem> {
+        let tab = |resource: &str, namespace: Option<&str>| Tab {
+            context: context.map(String::from),
+            resource: resource.to_string(),
+            namespace: namespace.map(String::from),
+            ..Tab::default()
+        };
+
+        // A section that couldn't be read is a single line saying why, in place of its contents.
+        let unreadable = |section: &str, label: &str, tab: Option<Tab>| {
+            self.unreadable.get(section).map(|error| OverviewItem {
+                label: label.into(),
+                detail: format!("couldn't be read: {error}"),
+                health: Some(Health::Warning),
+                tab,
+            })
+        };
+
+        let mut items = vec![
+            unreadable("version", "API server", None).unwrap_or_else(|| OverviewItem {
+                label: "API server".into(),
+                detail: self.version.clone(),
+                health: None,
+                tab: None,
+            }),
+            unreadable("nodes", "Nodes", Some(tab("nodes", None))).unwrap_or_else(|| {
+                OverviewItem {
+                    label: "Nodes".into(),
+                    detail: format!("{}/{} ready", self.nodes_ready, self.nodes),
+                    health: (self.nodes_ready < self.nodes).then_some(Health::Error),
+                    tab: Some(tab("nodes", None)),
+                }
+            }),
+        ];
+
+        items.extend(unreadable("pods", "Pods", Some(tab("pods", None))));
+        items.extend(self.pods.iter().map(|(namespace, phases)| {
+            let unhealthy = phases.keys().any(|p| p != "Running" && p != "Succeeded");
+            OverviewItem {
+                label: format!("Pods in {namespace}"),
+                detail: phases
+                    .iter()
+                    .map(|(phase, count)| format!("{phase} {count}"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                health: unhealthy.then_some(Health::Warning),
+                tab: Some(tab("pods", Some(namespace))),
+            }
+        }));
+
+        items.extend(
+            ["deployments", "statefulsets", "daemonsets"]
+                .into_iter()
+                .filter_map(|r| unreadable(r, &format!("Failing {r}"), Some(tab(r, None)))),
+        );
+        items.extend(self.failing.iter().map(|w| OverviewItem {
+            label: format!("Failing {} {}/{}", w.resource, w.namespace, w.name),
+            detail: format!("{}/{} ready", w.ready, w.desired),
+            health: Some(Health::Error),
+            tab: Some(Tab {
+                filter: w.name.clone(),
+                ..tab(w.resource, Some(&w.namespace))
+            }),
+        }));
+
+        items.extend(unreadable(
+            "events",
+            "Warnings",
+            Some(Tab {
+                field_selector: Some("type=Warning".into()),
+                ..tab("events", None)
+            }),
+        ));
+        items.extend(self.warnings.iter().map(|w| OverviewItem {
+            label: format!(
+                "Warning {}{}",
+                w.namespace
+                    .as_deref()
+                    .map(|n| format!("{n}/"))
+                    .unwrap_or_default(),
+                w.object
+            ),
+            detail: format!("{}: {}", w.summary.reason, w.summary.message),
+            health: Some(Health::Warning),
+            tab: Some(Tab {
+                field_selector: Some(format!("type=Warning,involvedObject.name={}", w.name)),
+                ..tab("events", w.namespace.as_deref())
+            }),
+        }));
+
+        items
+    }
+}
+
+/// Await one section's list, turning a failure into its message.
+async fn section<T>(
+    list: impl Future<Output = Result<Vec<T>, kube::Error>>,
+) -> (Vec<T>, Option<String>) {
+    match list.await {
+        Ok(items) => (items, None),
+        Err(e) => (vec![], Some(e.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::*;
+
+    fn from_json<T: serde::de::DeserializeOwned>(value: serde_json::Value) -> T {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn overview() -> Overview {
+        let nodes: Vec<Node> = vec![
+            from_json(
+                json!({"metadata": {"name": "a"}, "status": {"conditions": [{"type": "Ready", "status": "True"}]}}),
+            ),
+            from_json(
+                json!({"metadata": {"name": "b"}, "status": {"conditions": [{"type": "Ready", "status": "False"}]}}),
+            ),
+        ];
+        let pods: Vec<Pod> = vec![
+            from_json(
+                json!({"metadata": {"name": "a", "namespace": "default"}, "status": {"phase": "Running"}}),
+            ),
+            from_json(
+                json!({"metadata": {"name": "b", "namespace": "default"}, "status": {"phase": "Running"}}),
+            ),
+            from_json(
+                json!({"metadata": {"name": "c", "namespace": "kube-system"}, "status": {"phase": "Pending"}}),
+            ),
+        ];
+        let deployments: Vec<Deployment> = vec![
+            from_json(
+                json!({"metadata": {"name": "ok", "namespace": "default"}, "spec": {"replicas": 2, "selector": {}, "template": {}}, "status": {"availableReplicas": 2}}),
+            ),
+            from_json(
+                json!({"metadata": {"name": "web", "namespace": "default"}, "spec": {"replicas": 3, "selector": {}, "template": {}}, "status": {"availableReplicas": 1}}),
+            ),
+        ];
+        let warnings: Vec<DynamicObject> = vec![from_json(json!({
+            "apiVersion": "v1",
+            "kind": "Event",
+            "metadata": {"name": "web-1.abc", "namespace": "default"},
+            "involvedObject": {"kind": "Pod", "name": "web-1"},
+            "type": "Warning",
+            "reason": "BackOff",
+            "message": "Back-off restarting failed container",
+        }))];
+        Overview::build(
+            "v1.31.0".into(),
+            &nodes,
+            &pods,
+            &deployments,
+            &[],
+            &[],
+            &warnings,
+        )
+    }
+
+    #[rstest]
+    fn test_build() {
+        let overview = overview();
+        assert_eq!((overview.nodes_ready, overview.nodes), (1, 2));
+        assert_eq!(overview.pods["default"]["Running"], 2);
+        assert_eq!(overview.pods["kube-system"]["Pending"], 1);
+        assert_eq!(overview.failing.len(), 1);
+        assert_eq!(overview.failing[0].name, "web");
+        assert_eq!(overview.warnings[0].object, "Pod/web-1");
+    }
+
+    #[rstest]
+    fn test_items() {
+        let items = overview().items(Some("prod"));
+        let lines: Vec<(&str, &str)> = items
+            .iter()
+            .map(|i| (i.label.as_str(), i.detail.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("API server", "v1.31.0"),
+                ("Nodes", "1/2 ready"),
+                ("Pods in default", "Running 2"),
+                ("Pods in kube-system", "Pending 1"),
+                ("Failing deployments default/web", "1/3 ready"),
+                (
+                    "Warning default/Pod/web-1",
+                    "BackOff: Back-off restarting failed container"
+                ),
+            ]
+        );
+
+        let failing = items[4].tab.as_ref().unwrap();
+        assert_eq!(failing.context.as_deref(), Some("prod"));
+        assert_eq!(failing.resource, "deployments");
+        assert_eq!(failing.namespace.as_deref(), Some("default"));
+        assert_eq!(failing.filter, "web");
+        assert!(items[0].tab.is_none());
+        assert_eq!(items[3].health, Some(Health::Warning));
+    }
+
+    #[rstest]
+    fn test_unreadable_sections() {
+        let overview = Overview {
+            unreadable: BTreeMap::from([
+                ("nodes", "forbidden".to_string()),
+                ("pods", "forbidden".to_string()),
+            ]),
+            ..overview()
+        };
+        let items = overview.items(None);
+        assert_eq!(items[1].label, "Nodes");
+        assert_eq!(items[1].detail, "couldn't be read: forbidden");
+        assert_eq!(items[2].label, "Pods");
+        assert_eq!(
+            overview.unreadable_summary().as_deref(),
+            Some("Couldn't read nodes, pods for the overview: forbidden")
+        );
+        assert_eq!(Overview::default().unreadable_summary(), None);
+    }
+}
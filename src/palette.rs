@@ -0,0 +1,226 @@
+use std::path::PathBuf;
+
+use crate::keys::Command;
+
+/// What the command palette can do with its input, besides running a [`Command`] by name.
+const VERBS: &[&str] = &["ctx", "ns", "diff", "apply"];
+
+/// Aliases for the names of commands, for muscle memory from other tools.
+const ALIASES: &[(&str, Command)] = &[("q", Command::Quit)];
+
+/// A parsed line of command palette input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum PaletteCommand {
+    Run(Command),
+    /// Switch the active tab to a kubeconfig context.
+    Context(String),
+    /// Switch the active tab to a namespace, or to all namespaces if `None`.
+    Namespace(Option<String>),
+    /// Show what applying a manifest would change, like `diff -f manifest.yaml`.
+    Diff(PathBuf),
+    /// Apply a manifest, or the manifests in a directory, like `apply -f manifests/`.
+    Apply(PathBuf),
+    /// Switch the active tab to a resource, like `deploy -n kube-system -l app=foo`.
+    Resource {
+        name: String,
+        /// The namespace to switch to, if given; `Some(None)` for all namespaces.
+        namespace: Option<Option<String>>,
+        label_selector: Option<String>,
+    },
+}
+
+pub(crate) fn parse(input: &str) -> Result<PaletteCommand, String> {
+    let input = input.trim().trim_start_matches(':');
+    let mut words = input.split_whitespace();
+    let Some(first) = words.next() else {
+        return Err("Empty command".into());
+    };
+    let rest: Vec<&str> = words.collect();
+
+    if let Some((_, command)) = ALIASES.iter().find(|(alias, _)| *alias == first) {
+        return Ok(PaletteCommand::Run(*command));
+    }
+    if let Ok(command) = first.parse::<Command>() {
+        return match rest.as_slice() {
+            [] => Ok(PaletteCommand::Run(command)),
+            _ => Err(format!("{first} takes no arguments")),
+        };
+    }
+
+    match (first, rest.as_slice()) {
+        ("ctx" | "context", [context]) => Ok(PaletteCommand::Context(context.to_string())),
+        ("ctx" | "context", _) => Err(format!("Usage: {first} CONTEXT")),
+        ("ns" | "namespace", ["-A" | "--all-namespaces"]) => Ok(PaletteCommand::Namespace(None)),
+        ("ns" | "namespace", [namespace]) => {
+            Ok(PaletteCommand::Namespace(Some(namespace.to_string())))
+        }
+        ("ns" | "namespace", _) => Err(format!("Usage: {first} NAMESPACE|-A")),
+        ("diff", args) => manifest_path(first, args).map(PaletteCommand::Diff),
+        ("apply", args) => manifest_path(first, args).map(PaletteCommand::Apply),
+        (resource, args) => {
+            let mut namespace = None;
+            let mut label_selector = None;
+            let mut args = args.iter();
+            while let Some(arg) = args.next() {
+                match *arg {
+                    "-n" | "--namespace" => {
+                        let ns = args.next().ok_or(format!("{arg} needs a namespace"))?;
+                        namespace = Some(Some(ns.to_string()));
+                    }
+                    "-A" | "--all-namespaces" => namespace = Some(None),
+                    "-l" | "--selector" => {
+                        let selector = args.next().ok_or(format!("{arg} needs a selector"))?;
+                        label_selector = Some(selector.to_string());
+                    }
+                    _ => return Err(format!("Unexpected argument {arg:?}")),
+                }
+            }
+            Ok(PaletteCommand::Resource {
+                name: resource.to_string(),
+                namespace,
+                label_selector,
+            })
+        }
+    }
+}
+
+/// The path in the arguments of a verb that reads manifests, which can't be standard input
+/// because the terminal is in use.
+fn manifest_path(verb: &str, args: &[&str]) -> Result<PathBuf, String> {
+    match args {
+        ["-f" | "--filename", "-"] | ["-"] => Err(format!("{verb} can't read standard input here")),
+        ["-f" | "--filename", path] | [path] => Ok(path.into()),
+        _ => Err(format!("Usage: {verb} [-f] FILE|DIRECTORY")),
+    }
+}
+
+/// The names that the command palette completes.
+#[derive(Debug, Default)]
+pub(crate) struct Completions {
+    /// The names of discovered resources, including their short names.
+    pub(crate) resources: Vec<String>,
+    pub(crate) namespaces: Vec<String>,
+    /// The contexts in the kubeconfig.
+    pub(crate) contexts: Vec<String>,
+}
+
+impl Completions {
+    /// The candidates for the last word of the input, in order.
+    pub(crate) fn candidates(&self, input: &str) -> Vec<String> {
+        let (previous, last) = split_last_word(input);
+        let previous: Vec<&str> = previous.split_whitespace().collect();
+
+        let pool: Vec<&str> = match previous.as_slice() {
+            [] => Command::ALL
+                .iter()
+                .map(|c| c.name())
+                .chain(VERBS.iter().copied())
+                .chain(self.resources.iter().map(String::as_str))
+                .collect(),
+            ["ctx" | "context"] => self.contexts.iter().map(String::as_str).collect(),
+            ["ns" | "namespace"] | [.., "-n" | "--namespace"] => {
+                self.namespaces.iter().map(String::as_str).collect()
+            }
+            _ => vec![],
+        };
+
+        let mut candidates: Vec<String> = pool
+            .into_iter()
+            .filter(|c| c.starts_with(last) && *c != last)
+            .map(String::from)
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        candidates
+    }
+}
+
+/// The input with its last word replaced by the completion, ready for the next word.
+pub(crate) fn complete(input: &str, completion: &str) -> String {
+    let (previous, _) = split_last_word(input);
+    format!("{previous}{completion} ")
+}
+
+/// Split the input before its last word, which is empty if the input ends with whitespace.
+fn split_last_word(input: &str) -> (&str, &str) {
+    let idx = input.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+    input.split_at(idx)
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("quit", Ok(PaletteCommand::Run(Command::Quit)))]
+    #[case(":q", Ok(PaletteCommand::Run(Command::Quit)))]
+    #[case("new-tab", Ok(PaletteCommand::Run(Command::NewTab)))]
+    #[case("ctx prod", Ok(PaletteCommand::Context("prod".into())))]
+    #[case("ns kube-system", Ok(PaletteCommand::Namespace(Some("kube-system".into()))))]
+    #[case("ns -A", Ok(PaletteCommand::Namespace(None)))]
+    #[case(
+        "deploy",
+        Ok(PaletteCommand::Resource { name: "deploy".into(), namespace: None, label_selector: None })
+    )]
+    #[case(
+        ":deploy -n kube-system",
+        Ok(PaletteCommand::Resource {
+            name: "deploy".into(),
+            namespace: Some(Some("kube-system".into())),
+            label_selector: None,
+        })
+    )]
+    #[case(
+        "pods -A -l app=web",
+        Ok(PaletteCommand::Resource {
+            name: "pods".into(),
+            namespace: Some(None),
+            label_selector: Some("app=web".into()),
+        })
+    )]
+    #[case("diff -f web.yaml", Ok(PaletteCommand::Diff("web.yaml".into())))]
+    #[case("diff web.yaml", Ok(PaletteCommand::Diff("web.yaml".into())))]
+    #[case("diff", Err("Usage: diff [-f] FILE|DIRECTORY".into()))]
+    #[case("apply -f manifests/", Ok(PaletteCommand::Apply("manifests/".into())))]
+    #[case("apply -f -", Err("apply can't read standard input here".into()))]
+    #[case("apply a.yaml b.yaml", Err("Usage: apply [-f] FILE|DIRECTORY".into()))]
+    #[case("", Err("Empty command".into()))]
+    #[case("quit now", Err("quit takes no arguments".into()))]
+    #[case("ctx", Err("Usage: ctx CONTEXT".into()))]
+    #[case("pods -n", Err("-n needs a namespace".into()))]
+    #[case("pods --wide", Err("Unexpected argument \"--wide\"".into()))]
+    fn test_parse(#[case] input: &str, #[case] expected: Result<PaletteCommand, String>) {
+        assert_eq!(parse(input), expected);
+    }
+
+    fn completions() -> Completions {
+        Completions {
+            resources: vec!["deploy".into(), "deployments".into(), "pods".into()],
+            namespaces: vec!["default".into(), "kube-system".into()],
+            contexts: vec!["dev".into(), "prod".into()],
+        }
+    }
+
+    #[rstest]
+    #[case("dep", vec!["deploy", "deployments"])]
+    #[case("new", vec!["new-tab"])]
+    #[case("ctx ", vec!["dev", "prod"])]
+    #[case("ctx p", vec!["prod"])]
+    #[case("ns k", vec!["kube-system"])]
+    #[case("deploy -n ", vec!["default", "kube-system"])]
+    #[case("deploy -l ", vec![])]
+    #[case("pods", vec![])]
+    fn test_candidates(#[case] input: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(completions().candidates(input), expected);
+    }
+
+    #[rstest]
+    #[case("dep", "deployments", "deployments ")]
+    #[case("deploy -n kube", "kube-system", "deploy -n kube-system ")]
+    #[case("ctx ", "prod", "ctx prod ")]
+    fn test_complete(#[case] input: &str, #[case] completion: &str, #[case] expected: &str) {
+        assert_eq!(complete(input, completion), expected);
+    }
+}
@@ -0,0 +1,262 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use k8s_openapi::{
+    api::core::v1::{Pod, Service, ServicePort},
+    apimachinery::pkg::util::intstr::IntOrString,
+};
+use kube::{api::ListParams, Api, Client};
+use tokio::{net::TcpListener, task::JoinSet};
+
+use crate::{state::ObjectRef, DynResult};
+
+/// A running port-forward from a local port to a port on a pod.
+#[derive(Debug)]
+pub(crate) struct PortForward {
+    /// What the forward was started from, like `service/foo`.
+    pub(crate) source: String,
+    pub(crate) namespace: String,
+    pub(crate) pod: String,
+    pub(crate) local_port: u16,
+    pub(crate) remote_port: u16,
+    /// The most recent error encountered while forwarding a connection.
+    pub(crate) error: Arc<Mutex<Option<String>>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Drop for PortForward {
+    fn drop(&mut self) {
+        // Aborting the listener task also drops (and so aborts) all of its connection tasks.
+        self.task.abort();
+    }
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct PortForwards {
+    pub(crate) forwards: Vec<PortForward>,
+}
+
+impl PortForwards {
+    /// Start forwarding `local_port` to `remote_port` of the target, which may be a Pod or a Service.
+    pub(crate) async fn start(
+        &mut self,
+        client: Client,
+        target: &ObjectRef,
+        local_port: u16,
+        remote_port: u16,
+    ) -> DynResult<()> {
+        let namespace = target.namespace.clone().unwrap_or_default();
+        let (pod, pod_port) = resolve(client.clone(), target, remote_port).await?;
+
+        let listener = TcpListener::bind(("127.0.0.1", local_port)).await?;
+        let local_port = listener.local_addr()?.port();
+
+        let error = Arc::new(Mutex::new(None));
+        let task = {
+            let api: Api<Pod> = Api::namespaced(client, &namespace);
+            let pod = pod.clone();
+            let error = error.clone();
+            tokio::spawn(async move {
+                let mut connections = JoinSet::new();
+                while let Ok((mut conn, _)) = listener.accept().await {
+                    let api = api.clone();
+                    let pod = pod.clone();
+                    let error = error.clone();
+                    connections.spawn(async move {
+                        let result: DynResult<()> = async {
+                            let mut forwarder = api.portforward(&pod, &[pod_port]).await?;
+                            let mut stream = forwarder
+                                .take_stream(pod_port)
+                                .ok_or("port not available")?;
+                            tokio::io::copy_bidirectional(&mut conn, &mut stream).await?;
+                            drop(stream);
+                            forwarder.join().await?;
+                            Ok(())
+                        }
+                        .await;
+                        if let Err(e) = result {
+                            *error.lock().unwrap() = Some(e.to_string());
+                        }
+                    });
+                    // Reap finished connections so the set doesn't grow forever.
+                    while connections.try_join_next().is_some() {}
+                }
+            })
+        };
+
+        self.forwards.push(PortForward {
+            source: format!("{}/{}", target.resource.singular, target.name),
+            namespace,
+            pod,
+            local_port,
+            remote_port,
+            error,
+            task,
+        });
+
+        Ok(())
+    }
+
+    pub(crate) fn stop(&mut self, idx: usize) -> Option<PortForward> {
+        (idx < self.forwards.len()).then(|| self.forwards.remove(idx))
+    }
+}
+
+fn is_kind(target: &ObjectRef, plural: &str) -> bool {
+    target.resource.group.is_empty() && target.resource.plural == plural
+}
+
+pub(crate) fn can_port_forward(target: &ObjectRef) -> bool {
+    is_kind(target, "pods") || is_kind(target, "services")
+}
+
+/// The ports that a forward to the target would most likely want to use.
+pub(crate) async fn suggested_ports(client: Client, target: &ObjectRef) -> DynResult<Vec<u16>> {
+    let namespace = target.namespace.as_deref().unwrap_or_default();
+    if is_kind(target, "services") {
+        let service = Api::<Service>::namespaced(client, namespace)
+            .get(&target.name)
+            .await?;
+        Ok(service
+            .spec
+            .and_then(|s| s.ports)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|p| u16::try_from(p.port).ok())
+            .collect())
+    } else {
+        let pod = Api::<Pod>::namespaced(client, namespace)
+            .get(&target.name)
+            .await?;
+        Ok(container_ports(&pod).map(|(_, port)| port).collect())
+    }
+}
+
+/// Find the pod and pod port that traffic to `port` on the target should go to.
+async fn resolve(client: Client, target: &ObjectRef, port: u16) -> DynResult<(String, u16)> {
+    if !is_kind(target, "services") {
+        return Ok((target.name.clone(), port));
+    }
+
+    let namespace = target.namespace.as_deref().unwrap_or_default();
+    let service = Api::<Service>::namespaced(client.clone(), namespace)
+        .get(&target.name)
+        .await?;
+    let spec = service.spec.unwrap_or_default();
+    let service_port = spec
+        .ports
+        .iter()
+        .flatten()
+        .find(|p| p.port == i32::from(port))
+        .ok_or_else(|| format!("service {} has no port {port}", target.name))?;
+
+    let selector = spec
+        .selector
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| format!("service {} has no selector", target.name))?;
+    let pods = Api::<Pod>::namespaced(client, namespace)
+        .list(&ListParams::default().labels(&label_selector(&selector)))
+        .await?;
+    let pod = pods
+        .items
+        .into_iter()
+        .find(|p| p.status.as_ref().and_then(|s| s.phase.as_deref()) == Some("Running"))
+        .ok_or_else(|| format!("no running pods for service {}", target.name))?;
+
+    let pod_port = target_port(service_port, &pod)
+        .ok_or_else(|| format!("could not resolve target port of service port {port}"))?;
+    Ok((pod.metadata.name.unwrap_or_default(), pod_port))
+}
+
+fn container_ports(pod: &Pod) -> impl Iterator<Item = (Option<&str>, u16)> {
+    pod.spec
+        .iter()
+        .flat_map(|s| &s.containers)
+        .flat_map(|c| c.ports.iter().flatten())
+        .filter_map(|p| Some((p.name.as_deref(), u16::try_from(p.container_port).ok()?)))
+}
+
+/// The port on the pod that the service port sends traffic to.
+fn target_port(service_port: &ServicePort, pod: &Pod) -> Option<u16> {
+    match &service_port.target_port {
+        Some(IntOrString::Int(p)) => u16::try_from(*p).ok(),
+        Some(IntOrString::String(name)) => container_ports(pod)
+            .find(|(n, _)| *n == Some(name.as_str()))
+            .map(|(_, p)| p),
+        None => u16::try_from(service_port.port).ok(),
+    }
+}
+
+pub(crate) fn label_selector(labels: &BTreeMap<String, String>) -> String {
+    labels
+        .iter()
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::api::core::v1::{Container, ContainerPort, PodSpec};
+    use rstest::rstest;
+
+    use super::*;
+
+    fn pod_with_ports(ports: &[(&str, i32)]) -> Pod {
+        Pod {
+            spec: Some(PodSpec {
+                containers: vec![Container {
+                    ports: Some(
+                        ports
+                            .iter()
+                            .map(|(name, port)| ContainerPort {
+                                name: Some(name.to_string()),
+                                container_port: *port,
+                                ..ContainerPort::default()
+                            })
+                            .collect(),
+                    ),
+                    ..Container::default()
+                }],
+                ..PodSpec::default()
+            }),
+            ..Pod::default()
+        }
+    }
+
+    #[rstest]
+    #[case(80, None, &[], Some(80))]
+    #[case(80, Some(IntOrString::Int(8080)), &[], Some(8080))]
+    #[case(80, Some(IntOrString::String("http".into())), &[("http", 8000)], Some(8000))]
+    #[case(80, Some(IntOrString::String("http".into())), &[("metrics", 9090)], None)]
+    fn test_target_port(
+        #[case] port: i32,
+        #[case] target: Option<IntOrString>,
+        #[case] container_ports: &[(&str, i32)],
+        #[case] expected: Option<u16>,
+    ) {
+        let service_port = ServicePort {
+            port,
+            target_port: target,
+            ..ServicePort::default()
+        };
+        assert_eq!(
+            target_port(&service_port, &pod_with_ports(container_ports)),
+            expected
+        );
+    }
+
+    #[rstest]
+    #[case(&[], "")]
+    #[case(&[("app", "foo")], "app=foo")]
+    #[case(&[("app", "foo"), ("tier", "web")], "app=foo,tier=web")]
+    fn test_label_selector(#[case] labels: &[(&str, &str)], #[case] expected: &str) {
+        let labels = labels
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        assert_eq!(label_selector(&labels), expected);
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::BTreeMap;
+
+use k8s_openapi::api::core::v1::{Pod, PodSpec, Service};
+use kube::{api::ListParams, Api, Client};
+use serde_json::Value;
+
+use crate::{
+    discovery::Discovery,
+    portforward::label_selector,
+    state::{ObjectRef, Tab},
+    DynResult,
+};
+
+/// A place that can be jumped to from an object: a resource, filtered down to the related objects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Jump {
+    /// A description of the relation, like `Node node-1`.
+    pub(crate) label: String,
+    pub(crate) resource: String,
+    pub(crate) namespace: Option<String>,
+    pub(crate) label_selector: Option<String>,
+    pub(crate) field_selector: Option<String>,
+}
+
+impl Jump {
+    fn named(kind: &str, resource: &str, namespace: Option<&str>, name: &str) -> Self {
+        Self {
+            label: format!("{kind} {name}"),
+            resource: resource.into(),
+            namespace: namespace.map(String::from),
+            label_selector: None,
+            field_selector: Some(format!("metadata.name={name}")),
+        }
+    }
+
+    fn selected(kind: &str, resource: &str, namespace: Option<&str>, selector: String) -> Self {
+        Self {
+            label: format!("{kind} with {selector}"),
+            resource: resource.into(),
+            namespace: namespace.map(String::from),
+            label_selector: Some(selector),
+            field_selector: None,
+        }
+    }
+
+    /// A new tab showing the jump's destination, which remembers the tab it was opened from.
+    pub(crate) fn tab(&self, from: &Tab) -> Tab {
+        Tab {
+            namespace: self.namespace.clone(),
+            resource: self.resource.clone(),
+            label_selector: self.label_selector.clone(),
+            field_selector: self.field_selector.clone(),
+            context: from.context.clone(),
+            opened_from: Some(from.id),
+            ..Tab::default()
+        }
+    }
+}
+
+/// The objects related to the target that can be jumped to.
+pub(crate) async fn related(
+    client: Client,
+    discovery: &Discovery,
+    target: &ObjectRef,
+) -> DynResult<Vec<Jump>> {
+    let object = target.api(client.clone()).get(&target.name).await?;
+    let namespace = object.metadata.namespace.as_deref();
+    let mut jumps = vec![];
+
+    for owner in object.metadata.owner_references.iter().flatten() {
+        if let Some(r) = discovery.resource_for(&owner.api_version, &owner.kind) {
+            let resource = r.qualified_name();
+            jumps.push(Jump::named(&owner.kind, &resource, namespace, &owner.name));
+        }
+    }
+
+    let is_core =
+        |plural: &str| target.resource.group.is_empty() && target.resource.plural == plural;
+    if is_core("pods") {
+        let pod: Pod = object.clone().try_parse()?;
+        jumps.extend(pod_references(&pod));
+
+        let labels = pod.metadata.labels.unwrap_or_default();
+        let services = Api::<Service>::namespaced(client, namespace.unwrap_or_default())
+            .list(&ListParams::default())
+            .await?;
+        for service in services {
+            let selector = service.spec.and_then(|s| s.selector).unwrap_or_default();
+            if selects(&selector, &labels) {
+                let name = service.metadata.name.unwrap_or_default();
+                jumps.push(Jump::named("Service", "services", namespace, &name));
+            }
+        }
+    } else if is_core("services") {
+        let service: Service = object.clone().try_parse()?;
+        jumps.push(Jump::selected(
+            "EndpointSlices",
+            "endpointslices",
+            namespace,
+            format!("kubernetes.io/service-name={}", target.name),
+        ));
+        if let Some(selector) = service.spec.and_then(|s| s.selector) {
+            if !selector.is_empty() {
+                jumps.push(Jump::selected(
+                    "Pods",
+                    "pods",
+                    namespace,
+                    label_selector(&selector),
+                ));
+            }
+        }
+    } else if let Some(labels) = object.data.pointer("/spec/selector/matchLabels") {
+        // Workloads select the pods they manage.
+        let labels: BTreeMap<String, String> = serde_json::from_value(labels.clone())?;
+        if !labels.is_empty() {
+            jumps.push(Jump::selected(
+                "Pods",
+                "pods",
+                namespace,
+                label_selector(&labels),
+            ));
+        }
+    }
+
+    Ok(jumps)
+}
+
+/// Whether a (non-empty) service selector selects an object with the given labels.
+fn selects(selector: &BTreeMap<String, String>, labels: &BTreeMap<String, String>) -> bool {
+    !selector.is_empty() && selector.iter().all(|(k, v)| labels.get(k) == Some(v))
+}
+
+/// The node, service account, and mounted or referenced config of the pod.
+fn pod_references(pod: &Pod) -> Vec<Jump> {
+    let namespace = pod.metadata.namespace.as_deref();
+    let Some(spec) = &pod.spec else {
+        return vec![];
+    };
+
+    let mut jumps = vec![];
+    if let Some(node) = &spec.node_name {
+        jumps.push(Jump::named("Node", "nodes", None, node));
+    }
+    if let Some(sa) = &spec.service_account_name {
+        jumps.push(Jump::named(
+            "ServiceAccount",
+            "serviceaccounts",
+            namespace,
+            sa,
+        ));
+    }
+
+    let (config_maps, secrets, claims) = referenced_config(spec);
+    for name in config_maps {
+        jumps.push(Jump::named("ConfigMap", "configmaps", namespace, &name));
+    }
+    for name in secrets {
+        jumps.push(Jump::named("Secret", "secrets", namespace, &name));
+    }
+    for name in claims {
+        jumps.push(Jump::named(
+            "PersistentVolumeClaim",
+            "persistentvolumeclaims",
+            namespace,
+            &name,
+        ));
+    }
+
+    jumps
+}
+
+/// The names of the ConfigMaps, Secrets, and PersistentVolumeClaims that the pod
+/// mounts or reads environment variables from, each sorted and deduplicated.
+fn referenced_config(spec: &PodSpec) -> (Vec<String>, Vec<String>, Vec<String>) {
+    // Walking the spec as JSON finds references in volumes, projected volumes,
+    // env, envFrom, and image pull secrets alike, in every kind of container.
+    let spec = serde_json::to_value(spec).unwrap_or_default();
+    let mut config_maps = vec![];
+    let mut secrets = vec![];
+    let mut claims = vec![];
+    walk(&spec, &mut |key, value| {
+        let name = |field| value.get(field).and_then(Value::as_str).map(String::from);
+        match key {
+            "configMap" | "configMapRef" | "configMapKeyRef" => config_maps.extend(name("name")),
+            "secret" => secrets.extend(name("secretName").or_else(|| name("name"))),
+            "secretRef" | "secretKeyRef" => secrets.extend(name("name")),
+            "imagePullSecrets" => secrets.extend(
+                value
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|s| s.get("name").and_then(Value::as_str).map(String::from)),
+            ),
+            "persistentVolumeClaim" => claims.extend(name("claimName")),
+            _ => {}
+        }
+    });
+
+    for names in [&mut config_maps, &mut secrets, &mut claims] {
+        names.sort();
+        names.dedup();
+    }
+    (config_maps, secrets, claims)
+}
+
+fn walk(value: &Value, f: &mut impl FnMut(&str, &Value)) {
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                f(k, v);
+                walk(v, f);
+            }
+        }
+        Value::Array(items) => {
+            for v in items {
+                walk(v, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+    use serde_json::json;
+
+    use super::*;
+
+    #[rstest]
+    #[case(json!({"containers": []}), vec![], vec![], vec![])]
+    #[case(
+        json!({
+            "containers": [{
+                "name": "app",
+                "envFrom": [{"configMapRef": {"name": "env"}}, {"secretRef": {"name": "creds"}}],
+                "env": [{"name": "X", "valueFrom": {"secretKeyRef": {"name": "creds", "key": "x"}}}],
+            }],
+            "initContainers": [{
+                "name": "init",
+                "env": [{"name": "Y", "valueFrom": {"configMapKeyRef": {"name": "init", "key": "y"}}}],
+            }],
+            "imagePullSecrets": [{"name": "registry"}],
+            "volumes": [
+                {"name": "a", "configMap": {"name": "env"}},
+                {"name": "b", "secret": {"secretName": "tls"}},
+                {"name": "c", "persistentVolumeClaim": {"claimName": "data"}},
+                {"name": "d", "projected": {"sources": [{"configMap": {"name": "projected"}}]}},
+            ],
+        }),
+        vec!["env", "init", "projected"],
+        vec!["creds", "registry", "tls"],
+        vec!["data"],
+    )]
+    fn test_referenced_config(
+        #[case] spec: Value,
+        #[case] config_maps: Vec<&str>,
+        #[case] secrets: Vec<&str>,
+        #[case] claims: Vec<&str>,
+    ) {
+        let spec: PodSpec = serde_json::from_value(spec).unwrap();
+        assert_eq!(
+            referenced_config(&spec),
+            (
+                config_maps.into_iter().map(String::from).collect(),
+                secrets.into_iter().map(String::from).collect(),
+                claims.into_iter().map(String::from).collect(),
+            )
+        );
+    }
+
+    #[rstest]
+    #[case(&[], &[("app", "foo")], false)]
+    #[case(&[("app", "foo")], &[("app", "foo"), ("tier", "web")], true)]
+    #[case(&[("app", "foo"), ("tier", "db")], &[("app", "foo"), ("tier", "web")], false)]
+    #[case(&[("app", "foo")], &[], false)]
+    fn test_selects(
+        #[case] selector: &[(&str, &str)],
+        #[case] labels: &[(&str, &str)],
+        #[case] expected: bool,
+    ) {
+        let to_map = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        assert_eq!(selects(&to_map(selector), &to_map(labels)), expected);
+    }
+}
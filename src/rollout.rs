@@ -0,0 +1,322 @@
+use k8s_openapi::{
+    api::{
+        apps::v1::{Deployment, ReplicaSet},
+        core::v1::PodTemplateSpec,
+    },
+    apimachinery::pkg::apis::meta::v1::Time,
+};
+use kube::{
+    api::{ListParams, Patch},
+    Api, Client,
+};
+use serde_json::{json, Value};
+
+use crate::{diff::DiffLine, state::ObjectRef, workloads::patch_params, DynResult};
+
+pub const REVISION_ANNOTATION: &str = "deployment.kubernetes.io/revision";
+pub const CHANGE_CAUSE_ANNOTATION: &str = "kubernetes.io/change-cause";
+/// The label the Deployment controller adds to its ReplicaSets' pod templates, which differs
+/// between revisions but isn't part of what changed.
+const POD_TEMPLATE_HASH_LABEL: &str = "pod-template-hash";
+
+pub(crate) fn is_deployment(target: &ObjectRef) -> bool {
+    target.resource.group == "apps" && target.resource.plural == "deployments"
+}
+
+/// The revisions of a Deployment and the progress of its current rollout.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Rollout {
+    /// Newest first.
+    pub(crate) revisions: Vec<Revision>,
+    pub(crate) progress: Progress,
+}
+
+/// A revision of a Deployment, which is one of its ReplicaSets.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Revision {
+    pub(crate) number: u64,
+    pub(crate) replica_set: String,
+    pub(crate) created: Option<Time>,
+    pub(crate) replicas: i32,
+    pub(crate) ready: i32,
+    pub(crate) change_cause: Option<String>,
+    /// The ReplicaSet's pod template, without its `pod-template-hash` label.
+    pub(crate) template: PodTemplateSpec,
+}
+
+/// How far along a Deployment's rollout is, like `kubectl rollout status`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Progress {
+    pub(crate) desired: i32,
+    pub(crate) updated: i32,
+    pub(crate) ready: i32,
+    pub(crate) available: i32,
+    pub(crate) message: String,
+    pub(crate) state: RolloutState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RolloutState {
+    Progressing,
+    Complete,
+    /// The rollout exceeded its progress deadline.
+    Failed,
+}
+
+impl Progress {
+    fn of(deployment: &Deployment) -> Self {
+        let spec = deployment.spec.clone().unwrap_or_default();
+        let status = deployment.status.clone().unwrap_or_default();
+        let desired = spec.replicas.unwrap_or(1);
+        let updated = status.updated_replicas.unwrap_or_default();
+        let replicas = status.replicas.unwrap_or_default();
+        let available = status.available_replicas.unwrap_or_default();
+        let deadline_exceeded = status.conditions.iter().flatten().any(|c| {
+            c.type_ == "Progressing" && c.reason.as_deref() == Some("ProgressDeadlineExceeded")
+        });
+
+        let (state, message) = if deadline_exceeded {
+            (
+                RolloutState::Failed,
+                "progress deadline exceeded".to_string(),
+            )
+        } else if deployment.metadata.generation > status.observed_generation {
+            (
+                RolloutState::Progressing,
+                "waiting for the rollout to be observed".to_string(),
+            )
+        } else if updated < desired {
+            (
+                RolloutState::Progressing,
+                format!("{updated} of {desired} new replicas updated"),
+            )
+        } else if replicas > updated {
+            (
+                RolloutState::Progressing,
+                format!("{} old replicas pending termination", replicas - updated),
+            )
+        } else if available < updated {
+            (
+                RolloutState::Progressing,
+                format!("{available} of {updated} updated replicas available"),
+            )
+        } else {
+            (RolloutState::Complete, "complete".to_string())
+        };
+
+        Self {
+            desired,
+            updated,
+            ready: status.ready_replicas.unwrap_or_default(),
+            available,
+            message,
+            state,
+        }
+    }
+}
+
+impl Rollout {
+    /// The rollout of the Deployment, whose revisions are the ReplicaSets that it controls.
+    fn build(deployment: &Deployment, replica_sets: Vec<ReplicaSet>) -> Self {
+        let uid = deployment.metadata.uid.as_deref();
+        let mut revisions: Vec<Revision> = replica_sets
+            .into_iter()
+            .filter(|rs| {
+                rs.metadata
+                    .owner_references
+                    .iter()
+                    .flatten()
+                    .any(|o| o.controller == Some(true) && Some(o.uid.as_str()) == uid)
+            })
+            .filter_map(|rs| {
+                let annotations = rs.metadata.annotations.clone().unwrap_or_default();
+                let spec = rs.spec?;
+                let mut template = spec.template.unwrap_or_default();
+                if let Some(labels) = template.metadata.as_mut().and_then(|m| m.labels.as_mut()) {
+                    labels.remove(POD_TEMPLATE_HASH_LABEL);
+                }
+                Some(Revision {
+                    number: annotations.get(REVISION_ANNOTATION)?.parse().ok()?,
+                    replica_set: rs.metadata.name?,
+                    created: rs.metadata.creation_timestamp,
+                    replicas: spec.replicas.unwrap_or_default(),
+                    ready: rs.status.and_then(|s| s.ready_replicas).unwrap_or_default(),
+                    change_cause: annotations.get(CHANGE_CAUSE_ANNOTATION).cloned(),
+                    template,
+                })
+            })
+            .collect();
+        revisions.sort_by_key(|r| std::cmp::Reverse(r.number));
+        Self {
+            revisions,
+            progress: Progress::of(deployment),
+        }
+    }
+
+    /// The diff of the pod template from the revision to the current one.
+    pub(crate) fn diff(&self, idx: usize) -> Vec<DiffLine> {
+        let yaml = |r: Option<&Revision>| {
+            r.and_then(|r| serde_yaml::to_string(&r.template).ok())
+                .unwrap_or_default()
+        };
+        crate::diff::diff_lines(
+            &yaml(self.revisions.get(idx)),
+            &yaml(self.revisions.first()),
+        )
+    }
+}
+
+pub(crate) async fn fetch(client: Client, target: &ObjectRef) -> DynResult<Rollout> {
+    let namespace = target.namespace.as_deref().unwrap_or_default();
+    let deployment = Api::<Deployment>::namespaced(client.clone(), namespace)
+        .get(&target.name)
+        .await?;
+    let selector = deployment
+        .spec
+        .as_ref()
+        .and_then(|s| s.selector.match_labels.as_ref())
+        .map(|labels| {
+            labels
+                .iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .unwrap_or_default();
+    let replica_sets = Api::<ReplicaSet>::namespaced(client, namespace)
+        .list(&ListParams::default().labels(&selector))
+        .await?;
+    Ok(Rollout::build(&deployment, replica_sets.items))
+}
+
+/// Roll the Deployment back to the revision, by replacing its pod template with the revision's,
+/// like `kubectl rollout undo`.
+pub(crate) async fn rollback(
+    client: Client,
+    target: &ObjectRef,
+    revision: &Revision,
+) -> DynResult<()> {
+    Api::<Deployment>::namespaced(client, target.namespace.as_deref().unwrap_or_default())
+        .patch(
+            &target.name,
+            &patch_params(),
+            &Patch::Strategic(rollback_patch(&revision.template)?),
+        )
+        .await?;
+    Ok(())
+}
+
+/// A strategic merge patch that replaces the whole template, rather than merging into it,
+/// so that anything added since the revision is removed.
+fn rollback_patch(template: &PodTemplateSpec) -> DynResult<Value> {
+    let mut template = serde_json::to_value(template)?;
+    template["$patch"] = "replace".into();
+    Ok(json!({"spec": {"template": template}}))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    fn deployment(status: Value) -> Deployment {
+        serde_json::from_value(json!({
+            "metadata": {"name": "web", "uid": "d", "generation": 2},
+            "spec": {
+                "replicas": 3,
+                "selector": {"matchLabels": {"app": "web"}},
+                "template": {},
+            },
+            "status": status,
+        }))
+        .unwrap()
+    }
+
+    fn replica_set(name: &str, revision: &str, owner: &str, image: &str) -> ReplicaSet {
+        serde_json::from_value(json!({
+            "metadata": {
+                "name": name,
+                "annotations": {REVISION_ANNOTATION: revision},
+                "ownerReferences": [
+                    {"apiVersion": "apps/v1", "kind": "Deployment", "name": "web", "uid": owner, "controller": true},
+                ],
+            },
+            "spec": {
+                "replicas": 1,
+                "selector": {"matchLabels": {"app": "web"}},
+                "template": {
+                    "metadata": {"labels": {"app": "web", POD_TEMPLATE_HASH_LABEL: name}},
+                    "spec": {"containers": [{"name": "web", "image": image}]},
+                },
+            },
+        }))
+        .unwrap()
+    }
+
+    #[rstest]
+    fn test_build() {
+        let rollout = Rollout::build(
+            &deployment(json!({})),
+            vec![
+                replica_set("web-1", "1", "d", "web:1"),
+                replica_set("web-10", "10", "d", "web:2"),
+                replica_set("other", "2", "x", "web:1"),
+            ],
+        );
+        let numbers: Vec<(u64, &str)> = rollout
+            .revisions
+            .iter()
+            .map(|r| (r.number, r.replica_set.as_str()))
+            .collect();
+        assert_eq!(numbers, vec![(10, "web-10"), (1, "web-1")]);
+        let diff = rollout.diff(1);
+        let changes: Vec<&DiffLine> = diff
+            .iter()
+            .filter(|l| matches!(l, DiffLine::Added(_) | DiffLine::Removed(_)))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                &DiffLine::Removed("  - image: web:1".into()),
+                &DiffLine::Added("  - image: web:2".into()),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case(json!({"observedGeneration": 1}), RolloutState::Progressing, "waiting for the rollout to be observed")]
+    #[case(json!({"observedGeneration": 2, "replicas": 3, "updatedReplicas": 1}), RolloutState::Progressing, "1 of 3 new replicas updated")]
+    #[case(json!({"observedGeneration": 2, "replicas": 4, "updatedReplicas": 3}), RolloutState::Progressing, "1 old replicas pending termination")]
+    #[case(json!({"observedGeneration": 2, "replicas": 3, "updatedReplicas": 3, "availableReplicas": 2}), RolloutState::Progressing, "2 of 3 updated replicas available")]
+    #[case(json!({"observedGeneration": 2, "replicas": 3, "updatedReplicas": 3, "availableReplicas": 3}), RolloutState::Complete, "complete")]
+    #[case(
+        json!({"observedGeneration": 2, "conditions": [
+            {"type": "Progressing", "status": "False", "reason": "ProgressDeadlineExceeded"},
+        ]}),
+        RolloutState::Failed,
+        "progress deadline exceeded"
+    )]
+    fn test_progress(#[case] status: Value, #[case] state: RolloutState, #[case] message: &str) {
+        let progress = Progress::of(&deployment(status));
+        assert_eq!(
+            (progress.state, progress.message.as_str()),
+            (state, message)
+        );
+    }
+
+    #[rstest]
+    fn test_rollback_patch() {
+        let template = replica_set("web-1", "1", "d", "web:1")
+            .spec
+            .unwrap()
+            .template
+            .unwrap();
+        let patch = rollback_patch(&template).unwrap();
+        assert_eq!(patch["spec"]["template"]["$patch"], "replace");
+        assert_eq!(
+            patch["spec"]["template"]["spec"]["containers"][0]["image"],
+            "web:1"
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{validate_tabs, TabConfig},
+    state::UIState,
+    DynResult,
+};
+
+/// The name of the session that is used if none is given.
+pub const DEFAULT_SESSION: &str = "default";
+
+/// The open tabs, as saved on quit and restored on launch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub(crate) struct Session {
+    #[serde(default)]
+    pub(crate) active_tab: usize,
+    pub(crate) tabs: Vec<TabConfig>,
+}
+
+impl Session {
+    pub(crate) fn of(ui: &UIState) -> Self {
+        Self {
+            active_tab: ui.active_tab_idx,
+            tabs: ui.tabs.iter().map(TabConfig::from).collect(),
+        }
+    }
+
+    /// Replace the tabs of the UI with the session's tabs.
+    pub(crate) fn restore(&self, ui: &mut UIState) {
+        if self.tabs.is_empty() {
+            return;
+        }
+        ui.tabs = self.tabs.iter().map(TabConfig::tab).collect();
+        ui.active_tab_idx = self.active_tab.min(ui.tabs.len() - 1);
+    }
+
+    /// Load the session from `path`, if it has been saved.
+    pub(crate) fn load(path: &Path) -> DynResult<Option<Self>> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let session: Self = toml::from_str(&contents)
+            .map_err(|e| format!("invalid session file {}:\n  {e}", path.display()))?;
+        let errors = validate_tabs(&session.tabs);
+        if !errors.is_empty() {
+            return Err(format!(
+                "invalid session file {}:\n  {}",
+                path.display(),
+                errors.join("\n  ")
+            )
+            .into());
+        }
+        Ok(Some(session))
+    }
+
+    pub(crate) fn save(&self, path: &Path) -> DynResult<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// The path of the session file for a session name, which may also be the path to a session file
+/// (like one checked into a repository).
+pub(crate) fn session_path(name: &str) -> Option<PathBuf> {
+    if name.contains(std::path::MAIN_SEPARATOR) || name.ends_with(".toml") {
+        return Some(PathBuf::from(name));
+    }
+    let state_home = env::var_os("XDG_STATE_HOME")
+        .filter(|p| !p.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|h| h.join(".local").join("state")))?;
+    Some(
+        state_home
+            .join("flotilla")
+            .join("sessions")
+            .join(format!("{name}.toml")),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+    use crate::{
+        config::Config,
+        state::{Tab, TabView},
+        table::SortBy,
+    };
+
+    #[rstest]
+    fn test_session_round_trips_through_toml() {
+        let mut ui = UIState::new(Config::default());
+        ui.tabs = vec![
+            Tab::default(),
+            Tab {
+                title: Some("frontend".into()),
+                context: Some("prod".into()),
+                namespace: None,
+                resource: "deployments".into(),
+                filter: "web".into(),
+                label_selector: Some("tier=frontend".into()),
+                view: TabView::Tree,
+                wide: true,
+                sort: Some(SortBy {
+                    column: "Age".into(),
+                    descending: true,
+                }),
+                ..Tab::default()
+            },
+        ];
+        ui.active_tab_idx = 1;
+
+        let session = Session::of(&ui);
+        let toml = toml::to_string(&session).unwrap();
+        assert_eq!(toml::from_str::<Session>(&toml).unwrap(), session);
+
+        let mut restored = UIState::new(Config::default());
+        session.restore(&mut restored);
+        assert_eq!(restored.active_tab_idx, 1);
+        assert_eq!(Session::of(&restored), session);
+    }
+
+    #[rstest]
+    fn test_session_file_is_plain() {
+        let session = Session {
+            active_tab: 0,
+            tabs: vec![TabConfig::from(&Tab::default())],
+        };
+        assert_eq!(
+            toml::to_string(&session).unwrap(),
+            "active-tab = 0\n\n[[tabs]]\nresource = \"pods\"\nnamespace = \"default\"\n"
+        );
+    }
+
+    #[rstest]
+    #[case("oncall", false)]
+    #[case("sessions/oncall.toml", true)]
+    #[case("oncall.toml", true)]
+    fn test_session_path(#[case] name: &str, #[case] is_path: bool) {
+        assert_eq!(session_path(name) == Some(PathBuf::from(name)), is_path);
+    }
+}
@@ -1,40 +1,229 @@
-use std::{collections::HashMap, io};
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    fmt::{Debug, Formatter},
+    io,
+    path::PathBuf,
+    rc::Rc,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Instant,
+};
 
 use crossterm::{
     event,
-    event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    event::{Event, KeyCode, KeyEvent, KeyEventKind},
 };
-use kube::Client;
+use kube::{api::DynamicObject, config::KubeConfigOptions, Api, Client};
+use ratatui::{layout::Direction, widgets::TableState};
 
 use crate::{
+    config::{ColumnPreference, Config, TabConfig},
+    data::{is_key_char, DataEntry, DataKind},
     discovery::{DiscoveredAPIResource, Discovery},
-    table::ResourceTable,
+    input::LineEditor,
+    keys::{Command, KeyChord, Mode},
+    manifest::{ObjectApply, ObjectDiff},
+    metadata::{parse_entry, MetadataEntry, Section},
+    nodes::Draining,
+    overview::{Overview, OVERVIEW},
+    palette::{complete, Completions},
+    portforward::PortForwards,
+    relations::Jump,
+    rollout::{Revision, Rollout},
+    table::{ResourceRow, ResourceTable, SortBy},
+    tree::{OwnerNode, OwnerTree, TreeQuery},
     DynResult,
 };
 
 #[derive(Debug)]
 pub(crate) struct App {
-    pub(crate) kube: KubeState,
+    /// The clusters that tabs are connected to, by kubeconfig context (`None` for the default).
+    pub(crate) clusters: HashMap<Option<String>, KubeState>,
     pub(crate) ui: UIState,
+    pub(crate) port_forwards: PortForwards,
 }
 
 impl App {
-    pub(crate) fn new(kube: KubeState, ui: UIState) -> Self {
-        Self { kube, ui }
+    pub(crate) fn new(ui: UIState) -> Self {
+        Self {
+            clusters: HashMap::new(),
+            ui,
+            port_forwards: PortForwards::default(),
+        }
+    }
+
+    /// Connect to the cluster of the context, unless already connected.
+    pub(crate) async fn connect(&mut self, context: Option<&str>) -> DynResult<()> {
+        if let Entry::Vacant(entry) = self.clusters.entry(context.map(String::from)) {
+            entry.insert(KubeState::connect(context).await?);
+        }
+        Ok(())
+    }
+
+    /// The cluster of the active tab, which is always connected.
+    pub(crate) fn kube(&self) -> &KubeState {
+        &self.clusters[&self.ui.active_tab().context]
+    }
+
+    pub(crate) fn kube_mut(&mut self) -> &mut KubeState {
+        let context = &self.ui.active_tab().context;
+        self.clusters
+            .get_mut(context)
+            .expect("active tab is connected")
+    }
+
+    /// The resource of the active tab and the row selected in its most recently fetched table.
+    pub(crate) fn selected_row(&self) -> Option<(&Rc<DiscoveredAPIResource>, &ResourceRow)> {
+        let tab = self.ui.active_tab();
+        let resource = self.kube().discovery.get(&tab.resource)?;
+        let row = self
+            .kube()
+            .resources
+            .get(&tab.id)?
+            .rows
+            .get(tab.table_state.selected()?)?;
+        Some((resource, row))
+    }
+
+    /// The owner tree displayed by the active tab, if it is in tree view.
+    pub(crate) fn active_owner_tree(&self) -> Option<&OwnerTree> {
+        let tab = self.ui.active_tab();
+        if tab.view != TabView::Tree {
+            return None;
+        }
+        let resource = self.kube().discovery.get(&tab.resource)?;
+        self.kube()
+            .owner_trees
+            .get(&TreeQuery::of(tab, resource))
+            .map(|(_, tree)| tree)
+    }
+
+    /// The node selected in the active tab, if it is in tree view.
+    pub(crate) fn selected_owner_node(&self) -> Option<&OwnerNode> {
+        let tab = self.ui.active_tab();
+        let tree = self.active_owner_tree()?;
+        let row = tree
+            .visible(&tab.expanded, &tab.filter)
+            .into_iter()
+            .nth(tab.table_state.selected()?)?;
+        Some(&tree.nodes[row.node])
+    }
+
+    /// A reference to the object in the row selected in the active tab.
+    pub(crate) fn selected_object(&self) -> Option<ObjectRef> {
+        if self.ui.active_tab().view == TabView::Tree {
+            return self.selected_owner_node().map(OwnerNode::object_ref);
+        }
+
+        let (resource, row) = self.selected_row()?;
+        ObjectRef::of_row(resource, row)
+    }
+
+    /// References to the objects in the rows marked in the active tab,
+    /// or to the selected object if none are marked.
+    pub(crate) fn selected_objects(&self) -> Vec<ObjectRef> {
+        let tab = self.ui.active_tab();
+        let marked = match (
+            self.kube().discovery.get(&tab.resource),
+            self.kube().resources.get(&tab.id),
+        ) {
+            (Some(resource), Some(table)) if tab.view == TabView::Table => table
+                .rows
+                .iter()
+                .filter(|row| row.uid().is_some_and(|uid| tab.marked.contains(uid)))
+                .filter_map(|row| ObjectRef::of_row(resource, row))
+                .collect(),
+            _ => vec![],
+        };
+        if marked.is_empty() {
+            self.selected_object().into_iter().collect()
+        } else {
+            marked
+        }
+    }
+}
+
+/// The scroll position after scrolling up or down a line with the key,
+/// which stays on the `lines` being scrolled through.
+fn scrolled(scroll: usize, code: KeyCode, lines: usize) -> usize {
+    if matches!(code, KeyCode::Up | KeyCode::Char('k')) {
+        scroll.saturating_sub(1)
+    } else {
+        scroll.saturating_add(1).min(lines.saturating_sub(1))
+    }
+}
+
+/// The name of the only target, or how many there are.
+pub(crate) fn describe_targets(targets: &[ObjectRef]) -> String {
+    match targets {
+        [target] => target.name.clone(),
+        targets => format!("{} objects", targets.len()),
+    }
+}
+
+/// A reference to a single object of a discovered resource.
+#[derive(Debug, Clone)]
+pub(crate) struct ObjectRef {
+    pub(crate) resource: Rc<DiscoveredAPIResource>,
+    pub(crate) namespace: Option<String>,
+    pub(crate) name: String,
+}
+
+impl ObjectRef {
+    fn of_row(resource: &Rc<DiscoveredAPIResource>, row: &ResourceRow) -> Option<Self> {
+        Some(Self {
+            resource: resource.clone(),
+            namespace: row.namespace().map(String::from),
+            name: row.name()?.to_string(),
+        })
+    }
+
+    pub(crate) fn api(&self, client: Client) -> Api<DynamicObject> {
+        self.resource.api(client, self.namespace.as_deref())
     }
 }
 
-#[derive(Debug)]
 pub(crate) struct KubeState {
+    pub(crate) client: Client,
     pub(crate) discovery: Discovery,
-    pub(crate) resources: HashMap<DiscoveredAPIResource, ResourceTable>,
+    /// The most recently fetched table of each tab showing the cluster, by tab id.
+    pub(crate) resources: HashMap<u64, ResourceTable>,
+    /// Owner trees rooted at the objects that their query matches, and when they were built.
+    pub(crate) owner_trees: HashMap<TreeQuery, (Instant, OwnerTree)>,
+    /// The overview of the cluster and when it was fetched.
+    pub(crate) overview: Option<(Instant, Overview)>,
+}
+
+impl Debug for KubeState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // Client isn't Debug.
+        f.debug_struct("KubeState")
+            .field("discovery", &self.discovery)
+            .field("resources", &self.resources)
+            .field("owner_trees", &self.owner_trees)
+            .field("overview", &self.overview)
+            .finish_non_exhaustive()
+    }
 }
 
 impl KubeState {
-    pub(crate) async fn new(client: &Client) -> DynResult<Self> {
+    /// Connect to the cluster of the kubeconfig context, or of the default context if `None`.
+    pub(crate) async fn connect(context: Option<&str>) -> DynResult<Self> {
+        let client = match context {
+            None => Client::try_default().await?,
+            Some(context) => Client::try_from(
+                kube::Config::from_kubeconfig(&KubeConfigOptions {
+                    context: Some(context.to_string()),
+                    ..KubeConfigOptions::default()
+                })
+                .await?,
+            )?,
+        };
         Ok(Self {
-            discovery: Discovery::discover(client).await?,
+            discovery: Discovery::discover(&client).await?,
+            client,
             resources: HashMap::new(),
+            owner_trees: HashMap::new(),
+            overview: None,
         })
     }
 }
@@ -43,30 +232,276 @@ impl KubeState {
 pub(crate) struct UIState {
     pub(crate) tabs: Vec<Tab>,
     pub(crate) active_tab_idx: usize,
-    pub(crate) editing: Option<Editing>,
+    /// The field being edited, if any, and its editor.
+    pub(crate) editing: Option<(Editing, LineEditor)>,
+    /// The previous values of each field, which the editor can recall.
+    pub(crate) history: HashMap<Editing, Vec<String>>,
+    pub(crate) focus: Focus,
+    pub(crate) show_events: bool,
+    pub(crate) port_forwards_state: TableState,
+    pub(crate) dialog: Option<Dialog>,
+    /// An action to perform next, before reading any more input.
+    pub(crate) pending: Option<Action>,
+    pub(crate) status: Option<String>,
+    pub(crate) config: Config,
+    /// The ids of the tabs shown side by side in the resource area, one per pane.
+    pub(crate) panes: Vec<u64>,
+    /// The index of the pane showing the active tab.
+    pub(crate) focused_pane: usize,
+    /// How the resource area is divided between the panes.
+    pub(crate) split: Direction,
 }
 
-impl Default for UIState {
-    fn default() -> Self {
+impl UIState {
+    pub(crate) fn new(config: Config) -> Self {
+        let mut tabs: Vec<Tab> = config.tabs.iter().map(TabConfig::tab).collect();
+        if tabs.is_empty() {
+            // Land on the overview of the cluster, with pods a tab away.
+            tabs.extend([Tab::overview(None), Tab::default()]);
+        }
         Self {
-            tabs: vec![Tab::default()],
+            tabs,
             active_tab_idx: 0,
             editing: None,
+            history: HashMap::new(),
+            focus: Focus::Table,
+            show_events: false,
+            port_forwards_state: TableState::default().with_selected(0),
+            dialog: None,
+            pending: None,
+            status: None,
+            config,
+            panes: vec![],
+            focused_pane: 0,
+            split: Direction::Horizontal,
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub(crate) enum Editing {
     Namespace,
     Resource,
     Filter,
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Focus {
+    Table,
+    PortForwards,
+}
+
+#[derive(Debug)]
+pub(crate) enum Dialog {
+    Scale {
+        target: ObjectRef,
+        replicas: LineEditor,
+    },
+    Confirm {
+        prompt: String,
+        action: Action,
+    },
+    SelectContainer {
+        target: ObjectRef,
+        containers: Vec<String>,
+        selected: usize,
+    },
+    Jump {
+        jumps: Vec<Jump>,
+        selected: usize,
+    },
+    PortForward {
+        target: ObjectRef,
+        suggestions: Vec<u16>,
+        local: LineEditor,
+        remote: LineEditor,
+        editing_local: bool,
+    },
+    Palette {
+        input: LineEditor,
+        completions: Completions,
+        /// The index of the highlighted candidate for completing the input.
+        selected: usize,
+    },
+    RenameTab {
+        title: LineEditor,
+    },
+    /// The keys available in a mode.
+    Help {
+        mode: Mode,
+        bindings: Vec<(Command, Vec<KeyChord>)>,
+        scroll: usize,
+    },
+    /// The keys of a config map or secret, with their (decoded) values.
+    Data {
+        target: ObjectRef,
+        kind: DataKind,
+        entries: Vec<DataEntry>,
+        selected: usize,
+        /// The name of a key being added, as it is typed.
+        new_key: Option<LineEditor>,
+    },
+    /// The revisions of a deployment and the progress of its rollout, which is kept up to date.
+    Rollout {
+        target: ObjectRef,
+        rollout: Rollout,
+        fetched: Instant,
+        selected: usize,
+        /// How far the diff of the selected revision to the current one is scrolled,
+        /// if it is shown.
+        diff: Option<usize>,
+    },
+    /// The progress of draining nodes, which is cancelled when the dialog is closed.
+    Drain {
+        drain: Draining,
+        scroll: usize,
+    },
+    /// What applying a manifest would change, object by object.
+    Diff {
+        path: PathBuf,
+        diffs: Vec<ObjectDiff>,
+        scroll: usize,
+    },
+    /// What applying a manifest did, object by object.
+    Apply {
+        path: PathBuf,
+        applies: Vec<ObjectApply>,
+        scroll: usize,
+    },
+    /// The labels and annotations of one or more objects.
+    Metadata {
+        targets: Vec<ObjectRef>,
+        entries: Vec<MetadataEntry>,
+        selected: usize,
+        input: Option<MetadataInput>,
+    },
+}
+
+impl Dialog {
+    /// How many lines the content of a scrolling dialog has, as it is rendered.
+    fn scroll_lines(&self) -> usize {
+        match self {
+            Dialog::Drain { drain, .. } => {
+                let drain = drain.progress.lock().unwrap();
+                drain.evictions.len() + drain.skipped.len()
+            }
+            Dialog::Diff { diffs, .. } => diffs
+                .iter()
+                .map(|d| match &d.diff {
+                    Ok(diff) if !diff.is_empty() => diff.len() + 1,
+                    _ => 1,
+                })
+                .sum(),
+            Dialog::Apply { applies, .. } => applies.len(),
+            _ => 0,
+        }
+    }
+}
+
+/// The value of a label or annotation being typed, or a new `key=value` entry if `key` is `None`.
+#[derive(Debug)]
+pub(crate) struct MetadataInput {
+    pub(crate) section: Section,
+    pub(crate) key: Option<String>,
+    pub(crate) editor: LineEditor,
+}
+
 #[derive(Debug)]
 pub(crate) enum Action {
     Continue,
     Quit,
+    Edit,
+    PromptScale,
+    Scale {
+        target: ObjectRef,
+        replicas: i32,
+    },
+    PromptRolloutRestart,
+    RolloutRestart(ObjectRef),
+    PromptExec,
+    Exec {
+        target: ObjectRef,
+        container: String,
+    },
+    PromptPortForward,
+    PortForward {
+        target: ObjectRef,
+        local: u16,
+        remote: u16,
+    },
+    StopPortForward(usize),
+    /// Expand (or collapse) the selected node of a tree view.
+    SetExpanded(bool),
+    PromptJump,
+    PromptPalette,
+    RunPalette(String),
+    /// Forget cached data about the cluster of the active tab, so that it is fetched again.
+    Refresh,
+    /// Sort the active tab by the next (or previous) column of its table.
+    CycleSort {
+        forward: bool,
+    },
+    /// Open a tab showing the selected item of the overview.
+    OpenOverviewItem,
+    PromptData,
+    ShowData {
+        target: ObjectRef,
+        kind: DataKind,
+        /// The key to select, like one that was just edited.
+        select: Option<String>,
+    },
+    /// Edit the value of a key in `$EDITOR`, starting from `value`, or from nothing for a new key.
+    EditData {
+        target: ObjectRef,
+        kind: DataKind,
+        key: String,
+        value: Option<String>,
+    },
+    /// Set (or, given `None`, delete) the value of a key.
+    SetData {
+        target: ObjectRef,
+        kind: DataKind,
+        key: String,
+        value: Option<String>,
+    },
+    PromptRollout,
+    ShowRollout(ObjectRef),
+    /// Roll back the deployment to the revision.
+    Rollback {
+        target: ObjectRef,
+        revision: Box<Revision>,
+    },
+    ToggleSuspend,
+    PromptTrigger,
+    /// Create a job from the cron job.
+    Trigger(ObjectRef),
+    Logs,
+    /// Apply the manifest, or the manifests in the directory.
+    Apply(PathBuf),
+    /// Cordon (or uncordon) the selected nodes.
+    Cordon(bool),
+    PromptDrain,
+    Drain(Vec<String>),
+    /// Mark (or unmark) the selected row of the active tab.
+    ToggleMark,
+    PromptMetadata,
+    ShowMetadata {
+        targets: Vec<ObjectRef>,
+        /// The entry to select, like one that was just edited.
+        select: Option<(Section, String)>,
+    },
+    /// Set (or, given `None`, remove) a label or annotation on every target.
+    SetMetadata {
+        targets: Vec<ObjectRef>,
+        section: Section,
+        key: String,
+        value: Option<String>,
+    },
+    /// Put a value on the clipboard.
+    Copy {
+        label: String,
+        value: Vec<u8>,
+    },
 }
 
 impl UIState {
@@ -79,109 +514,1338 @@ impl UIState {
     }
 
     pub fn handle_events(&mut self) -> io::Result<Action> {
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Press {
-                match key {
-                    KeyEvent {
-                        modifiers: KeyModifiers::CONTROL,
-                        code: KeyCode::Char('t'),
-                        ..
-                    } => self.new_tab(),
-                    KeyEvent {
-                        code: KeyCode::Tab, ..
-                    } => {
-                        self.active_tab_idx = self
-                            .active_tab_idx
+        if let Some(action) = self.pending.take() {
+            return Ok(action);
+        }
+
+        if !event::poll(self.config.refresh_interval)? {
+            return Ok(Action::Continue);
+        }
+
+        match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => {
+                if let Some(dialog) = self.dialog.take() {
+                    return Ok(self.handle_dialog_key(dialog, key));
+                }
+
+                let mode = self.mode();
+                match (mode, self.config.keymap.get(mode, &KeyChord::from(key))) {
+                    (_, Some(command)) => return Ok(self.run_command(command)),
+                    (Mode::Editing, None) => self.edit_text(|editor| editor.handle_key(key)),
+                    _ => {}
+                }
+            }
+            Event::Paste(text) if self.dialog.is_some() => self.paste_into_dialog(&text),
+            Event::Paste(text) => self.edit_text(|editor| {
+                editor.paste(&text);
+                true
+            }),
+            _ => {}
+        }
+
+        Ok(Action::Continue)
+    }
+
+    /// The mode that determines which keys are available.
+    pub(crate) fn mode(&self) -> Mode {
+        if self.editing.is_some() {
+            Mode::Editing
+        } else if self.focus == Focus::PortForwards {
+            Mode::PortForwards
+        } else if self.active_tab().is_overview() {
+            Mode::Overview
+        } else if self.active_tab().view == TabView::Tree {
+            Mode::Tree
+        } else {
+            Mode::Table
+        }
+    }
+
+    /// Insert pasted text into the text input of the open dialog, if it has one,
+    /// keeping only the characters that could be typed into it.
+    fn paste_into_dialog(&mut self, text: &str) {
+        let digits: String = text.chars().filter(char::is_ascii_digit).collect();
+        match &mut self.dialog {
+            Some(Dialog::Metadata {
+                input: Some(input), ..
+            }) => input.editor.paste(text),
+            Some(Dialog::Data {
+                new_key: Some(key), ..
+            }) => key.paste(&text.chars().filter(|&c| is_key_char(c)).collect::<String>()),
+            Some(Dialog::Palette {
+                input, selected, ..
+            }) => {
+                input.paste(text);
+                *selected = 0;
+            }
+            Some(Dialog::RenameTab { title }) => title.paste(text),
+            Some(Dialog::Scale { replicas, .. }) => replicas.paste(&digits),
+            Some(Dialog::PortForward {
+                local,
+                remote,
+                editing_local,
+                ..
+            }) => if *editing_local { local } else { remote }.paste(&digits),
+            _ => {}
+        }
+    }
+
+    /// Change the text of the field being edited, keeping the tab up to date with it.
+    fn edit_text(&mut self, f: impl FnOnce(&mut LineEditor) -> bool) {
+        let Some((field, editor)) = &mut self.editing else {
+            return;
+        };
+        if f(editor) {
+            let (field, text) = (*field, editor.text().to_string());
+            self.set_field(field, text);
+        }
+    }
+
+    fn field(&self, field: Editing) -> String {
+        let tab = self.active_tab();
+        match field {
+            Editing::Namespace => tab.namespace.clone().unwrap_or_default(),
+            Editing::Resource => tab.resource.clone(),
+            Editing::Filter => tab.filter.clone(),
+        }
+    }
+
+    /// Set a field of the active tab; an empty namespace means all namespaces.
+    fn set_field(&mut self, field: Editing, text: String) {
+        let tab = self.active_tab_mut();
+        match field {
+            Editing::Namespace => tab.namespace = Some(text).filter(|t| !t.is_empty()),
+            Editing::Resource => tab.resource = text,
+            Editing::Filter => tab.filter = text,
+        }
+    }
+
+    fn start_editing(&mut self, field: Editing) {
+        let history = self.history.get(&field).map_or(&[][..], Vec::as_slice);
+        let editor = LineEditor::new(&self.field(field), history);
+        self.editing = Some((field, editor));
+    }
+
+    /// Stop editing, keeping the edited text (and remembering it), or reverting to the original.
+    fn finish_editing(&mut self, keep: bool) {
+        let Some((field, editor)) = self.editing.take() else {
+            return;
+        };
+        if keep {
+            editor.remember(self.history.entry(field).or_default());
+        } else {
+            self.set_field(field, editor.original().to_string());
+        }
+    }
+
+    pub(crate) fn run_command(&mut self, command: Command) -> Action {
+        let port_forwards_focused = self.focus == Focus::PortForwards;
+        let tree_view = self.active_tab().view == TabView::Tree;
+        match command {
+            Command::Quit => return Action::Quit,
+            Command::Palette => return Action::PromptPalette,
+            Command::Refresh => return Action::Refresh,
+            Command::Help => {
+                let mode = self.mode();
+                self.dialog = Some(Dialog::Help {
+                    mode,
+                    bindings: self.config.keymap.available(mode),
+                    scroll: 0,
+                })
+            }
+            Command::NewTab => self.new_tab(),
+            Command::CloseTab => self.close_tab(),
+            Command::DuplicateTab => self.duplicate_tab(),
+            Command::RenameTab => {
+                self.dialog = Some(Dialog::RenameTab {
+                    title: LineEditor::new(
+                        self.active_tab().title.as_deref().unwrap_or_default(),
+                        &[],
+                    ),
+                })
+            }
+            Command::SplitRight => self.split_pane(Direction::Horizontal),
+            Command::SplitDown => self.split_pane(Direction::Vertical),
+            Command::NextPane => self.focus_pane(true),
+            Command::PreviousPane => self.focus_pane(false),
+            Command::ClosePane => self.close_pane(),
+            Command::MoveTabLeft => self.move_tab(false),
+            Command::MoveTabRight => self.move_tab(true),
+            Command::NextTab => self.active_tab_idx = (self.active_tab_idx + 1) % self.tabs.len(),
+            Command::PreviousTab => {
+                self.active_tab_idx = (self.active_tab_idx + self.tabs.len() - 1) % self.tabs.len()
+            }
+            Command::GoToTab1
+            | Command::GoToTab2
+            | Command::GoToTab3
+            | Command::GoToTab4
+            | Command::GoToTab5
+            | Command::GoToTab6
+            | Command::GoToTab7
+            | Command::GoToTab8
+            | Command::GoToTab9 => {
+                if let Some(idx) = command.tab_index().filter(|&idx| idx < self.tabs.len()) {
+                    self.active_tab_idx = idx;
+                }
+            }
+            Command::EditFilter => self.start_editing(Editing::Filter),
+            Command::EditResource => self.start_editing(Editing::Resource),
+            Command::EditNamespace => self.start_editing(Editing::Namespace),
+            Command::FinishEditing => self.finish_editing(true),
+            Command::CancelEditing => self.finish_editing(false),
+            Command::SelectNext if port_forwards_focused => self.port_forwards_state.select_next(),
+            Command::SelectNext => self.active_tab_mut().table_state.select_next(),
+            Command::SelectPrevious if port_forwards_focused => {
+                self.port_forwards_state.select_previous()
+            }
+            Command::SelectPrevious => self.active_tab_mut().table_state.select_previous(),
+            Command::Back if port_forwards_focused => self.focus = Focus::Table,
+            Command::Back => self.go_back(),
+            Command::Edit => return Action::Edit,
+            Command::ToggleEvents => self.show_events = !self.show_events,
+            Command::Scale => return Action::PromptScale,
+            Command::RolloutRestart => return Action::PromptRolloutRestart,
+            Command::Exec => return Action::PromptExec,
+            Command::PortForward => return Action::PromptPortForward,
+            Command::FocusPortForwards => {
+                self.focus = match self.focus {
+                    Focus::Table => Focus::PortForwards,
+                    Focus::PortForwards => Focus::Table,
+                }
+            }
+            Command::StopPortForward if port_forwards_focused => {
+                if let Some(idx) = self.port_forwards_state.selected() {
+                    return Action::StopPortForward(idx);
+                }
+            }
+            Command::StopPortForward => {}
+            Command::ToggleTree => {
+                let tab = self.active_tab_mut();
+                tab.view = match tab.view {
+                    TabView::Table => TabView::Tree,
+                    TabView::Tree => TabView::Table,
+                };
+                tab.table_state.select_first();
+            }
+            Command::ToggleWide => {
+                let tab = self.active_tab_mut();
+                tab.wide = !tab.wide;
+            }
+            Command::SortNextColumn => return Action::CycleSort { forward: true },
+            Command::SortPreviousColumn => return Action::CycleSort { forward: false },
+            Command::ReverseSort => {
+                if let Some(sort) = &mut self.active_tab_mut().sort {
+                    sort.descending = !sort.descending;
+                }
+            }
+            Command::Expand if tree_view => return Action::SetExpanded(true),
+            Command::Collapse if tree_view => return Action::SetExpanded(false),
+            Command::Expand | Command::Collapse => {}
+            Command::GoTo => return Action::PromptJump,
+            Command::ViewData => return Action::PromptData,
+            Command::ToggleMark if !tree_view => return Action::ToggleMark,
+            Command::ToggleMark => {}
+            Command::ClearMarks => self.active_tab_mut().marked.clear(),
+            Command::EditMetadata => return Action::PromptMetadata,
+            Command::Cordon => return Action::Cordon(true),
+            Command::Uncordon => return Action::Cordon(false),
+            Command::Drain => return Action::PromptDrain,
+            Command::RolloutHistory => return Action::PromptRollout,
+            Command::ToggleSuspend => return Action::ToggleSuspend,
+            Command::Trigger => return Action::PromptTrigger,
+            Command::Logs => return Action::Logs,
+            Command::ShowOverview => self.show_overview(),
+            Command::Open if self.active_tab().is_overview() => return Action::OpenOverviewItem,
+            Command::Open => {}
+        }
+
+        Action::Continue
+    }
+
+    fn handle_dialog_key(&mut self, dialog: Dialog, key: KeyEvent) -> Action {
+        match (dialog, key.code) {
+            (
+                Dialog::Data {
+                    target,
+                    kind,
+                    entries,
+                    selected,
+                    new_key: Some(_),
+                },
+                KeyCode::Esc,
+            ) => {
+                self.dialog = Some(Dialog::Data {
+                    target,
+                    kind,
+                    entries,
+                    selected,
+                    new_key: None,
+                })
+            }
+            (
+                Dialog::Metadata {
+                    targets,
+                    entries,
+                    selected,
+                    input: Some(_),
+                },
+                KeyCode::Esc,
+            ) => {
+                self.dialog = Some(Dialog::Metadata {
+                    targets,
+                    entries,
+                    selected,
+                    input: None,
+                })
+            }
+            (
+                Dialog::Rollout {
+                    target,
+                    rollout,
+                    fetched,
+                    selected,
+                    diff: Some(_),
+                },
+                KeyCode::Esc,
+            ) => {
+                self.dialog = Some(Dialog::Rollout {
+                    target,
+                    rollout,
+                    fetched,
+                    selected,
+                    diff: None,
+                })
+            }
+            (_, KeyCode::Esc) => {}
+            (Dialog::Scale { target, replicas }, KeyCode::Enter) => match replicas.text().parse() {
+                Ok(replicas) => return Action::Scale { target, replicas },
+                Err(_) => self.dialog = Some(Dialog::Scale { target, replicas }),
+            },
+            (
+                Dialog::Scale {
+                    target,
+                    mut replicas,
+                },
+                _,
+            ) => {
+                replicas.handle_key_accepting(key, |c| c.is_ascii_digit());
+                self.dialog = Some(Dialog::Scale { target, replicas });
+            }
+            (Dialog::Confirm { action, .. }, KeyCode::Enter | KeyCode::Char('y')) => {
+                return action;
+            }
+            (Dialog::Confirm { .. }, KeyCode::Char('n')) => {}
+            (Dialog::Help { .. }, KeyCode::Char('?' | 'q')) => {}
+            (Dialog::RenameTab { title }, KeyCode::Enter) => {
+                let title = title.text().trim();
+                self.active_tab_mut().title = (!title.is_empty()).then(|| title.to_string());
+            }
+            (Dialog::RenameTab { mut title }, _) => {
+                title.handle_key(key);
+                self.dialog = Some(Dialog::RenameTab { title });
+            }
+            (Dialog::Palette { input, .. }, KeyCode::Enter) => {
+                return Action::RunPalette(input.text().to_string())
+            }
+            (
+                Dialog::Palette {
+                    input,
+                    completions,
+                    selected,
+                },
+                code,
+            ) => {
+                let (mut input, mut selected) = (input, selected);
+                let candidates = completions.candidates(input.text());
+                match code {
+                    KeyCode::Tab => {
+                        if let Some(candidate) = candidates.get(selected) {
+                            input = LineEditor::new(&complete(input.text(), candidate), &[]);
+                            selected = 0;
+                        }
+                    }
+                    // Up and down choose between completions rather than browsing history.
+                    KeyCode::Up => selected = selected.saturating_sub(1),
+                    KeyCode::Down => {
+                        selected = selected
                             .saturating_add(1)
-                            .min(self.tabs.len() - 1)
-                    }
-                    KeyEvent {
-                        code: KeyCode::BackTab,
-                        ..
-                    } => self.active_tab_idx = self.active_tab_idx.saturating_sub(1),
-                    KeyEvent {
-                        code: KeyCode::Char('f'),
-                        ..
-                    } if self.editing.is_none() => self.editing = Some(Editing::Filter),
-                    KeyEvent {
-                        code: KeyCode::Char('r'),
-                        ..
-                    } if self.editing.is_none() => self.editing = Some(Editing::Resource),
-                    KeyEvent {
-                        code: KeyCode::Char('n'),
-                        ..
-                    } if self.editing.is_none() => self.editing = Some(Editing::Namespace),
-                    KeyEvent {
-                        code: KeyCode::Char(c),
-                        ..
-                    } if self.editing.is_some() => match self.editing.as_ref().unwrap() {
-                        Editing::Filter => {
-                            self.active_tab_mut().filter.push(c);
+                            .min(candidates.len().saturating_sub(1))
+                    }
+                    _ => {
+                        if input.handle_key(key) {
+                            selected = 0;
                         }
-                        Editing::Namespace => {
-                            if let Some(ref mut n) = self.active_tab_mut().namespace {
-                                n.push(c);
+                    }
+                }
+                self.dialog = Some(Dialog::Palette {
+                    input,
+                    completions,
+                    selected,
+                });
+            }
+            (
+                Dialog::Help {
+                    mode,
+                    bindings,
+                    scroll,
+                },
+                code @ (KeyCode::Up | KeyCode::Down | KeyCode::Char('k' | 'j')),
+            ) => {
+                let scroll = scrolled(scroll, code, bindings.len());
+                self.dialog = Some(Dialog::Help {
+                    mode,
+                    bindings,
+                    scroll,
+                });
+            }
+            (Dialog::Jump { jumps, selected }, KeyCode::Enter) => {
+                if let Some(jump) = jumps.get(selected) {
+                    self.jump(jump);
+                }
+            }
+            (Dialog::Jump { jumps, selected }, code @ (KeyCode::Up | KeyCode::Down)) => {
+                let selected = if code == KeyCode::Up {
+                    selected.saturating_sub(1)
+                } else {
+                    selected
+                        .saturating_add(1)
+                        .min(jumps.len().saturating_sub(1))
+                };
+                self.dialog = Some(Dialog::Jump { jumps, selected });
+            }
+            (
+                Dialog::PortForward {
+                    target,
+                    suggestions,
+                    local,
+                    remote,
+                    editing_local,
+                },
+                code,
+            ) => {
+                let (mut local, mut remote, mut editing_local) = (local, remote, editing_local);
+                match code {
+                    KeyCode::Enter => {
+                        if let (Ok(local), Ok(remote)) =
+                            (local.text().parse(), remote.text().parse())
+                        {
+                            return Action::PortForward {
+                                target,
+                                local,
+                                remote,
+                            };
+                        }
+                    }
+                    KeyCode::Tab | KeyCode::BackTab => editing_local = !editing_local,
+                    KeyCode::Up | KeyCode::Down if !suggestions.is_empty() => {
+                        let current = suggestions
+                            .iter()
+                            .position(|p| p.to_string() == remote.text());
+                        let next = match (code, current) {
+                            (KeyCode::Up, Some(idx)) => {
+                                idx.checked_sub(1).unwrap_or(suggestions.len() - 1)
                             }
+                            (_, Some(idx)) => (idx + 1) % suggestions.len(),
+                            (_, None) => 0,
+                        };
+                        remote = LineEditor::new(&suggestions[next].to_string(), &[]);
+                        local = remote.clone();
+                    }
+                    _ => {
+                        if editing_local {
+                            &mut local
+                        } else {
+                            &mut remote
                         }
-                        Editing::Resource => {
-                            self.active_tab_mut().resource.push(c);
+                        .handle_key_accepting(key, |c| c.is_ascii_digit());
+                    }
+                }
+                self.dialog = Some(Dialog::PortForward {
+                    target,
+                    suggestions,
+                    local,
+                    remote,
+                    editing_local,
+                });
+            }
+            (
+                Dialog::SelectContainer {
+                    target,
+                    mut containers,
+                    selected,
+                },
+                KeyCode::Enter,
+            ) => {
+                if selected < containers.len() {
+                    return Action::Exec {
+                        target,
+                        container: containers.swap_remove(selected),
+                    };
+                }
+            }
+            (
+                Dialog::SelectContainer {
+                    target,
+                    containers,
+                    selected,
+                },
+                code @ (KeyCode::Up | KeyCode::Down),
+            ) => {
+                let selected = if code == KeyCode::Up {
+                    selected.saturating_sub(1)
+                } else {
+                    selected
+                        .saturating_add(1)
+                        .min(containers.len().saturating_sub(1))
+                };
+                self.dialog = Some(Dialog::SelectContainer {
+                    target,
+                    containers,
+                    selected,
+                });
+            }
+            (
+                Dialog::Data {
+                    target,
+                    kind,
+                    entries,
+                    selected,
+                    new_key: Some(new_key),
+                },
+                code,
+            ) => {
+                let mut new_key = Some(new_key);
+                match (code, &mut new_key) {
+                    (KeyCode::Enter, Some(editor)) if !editor.text().is_empty() => {
+                        return Action::EditData {
+                            target,
+                            kind,
+                            key: editor.text().to_string(),
+                            value: None,
                         }
-                    },
-                    KeyEvent {
-                        code: KeyCode::Backspace,
-                        ..
-                    } if self.editing.is_some() => match self.editing.as_ref().unwrap() {
-                        Editing::Filter => {
-                            self.active_tab_mut().filter.pop();
+                    }
+                    (_, Some(editor)) => {
+                        editor.handle_key_accepting(key, is_key_char);
+                    }
+                    _ => {}
+                }
+                self.dialog = Some(Dialog::Data {
+                    target,
+                    kind,
+                    entries,
+                    selected,
+                    new_key,
+                });
+            }
+            (
+                Dialog::Data {
+                    target,
+                    kind,
+                    mut entries,
+                    selected,
+                    new_key: None,
+                },
+                code,
+            ) => {
+                let mut selected = selected;
+                let mut new_key = None;
+                match (code, entries.get_mut(selected)) {
+                    (KeyCode::Char('q'), _) => return Action::Continue,
+                    (KeyCode::Up | KeyCode::Char('k'), _) => selected = selected.saturating_sub(1),
+                    (KeyCode::Down | KeyCode::Char('j'), _) => {
+                        selected = selected
+                            .saturating_add(1)
+                            .min(entries.len().saturating_sub(1))
+                    }
+                    (KeyCode::Char(' '), Some(entry)) => entry.revealed = !entry.revealed,
+                    (KeyCode::Char('a'), _) => {
+                        let reveal = entries.iter().any(|e| !e.revealed);
+                        entries.iter_mut().for_each(|e| e.revealed = reveal);
+                    }
+                    (KeyCode::Char('c' | 'y'), Some(entry)) => {
+                        self.pending = Some(Action::Copy {
+                            label: entry.key.clone(),
+                            value: entry.value.clone(),
+                        });
+                    }
+                    (KeyCode::Enter | KeyCode::Char('e'), Some(entry)) => match entry.text() {
+                        Some(text) => {
+                            return Action::EditData {
+                                target,
+                                kind,
+                                key: entry.key.clone(),
+                                value: Some(text.to_string()),
+                            }
                         }
-                        Editing::Namespace => {
-                            if let Some(ref mut n) = self.active_tab_mut().namespace {
-                                n.pop();
+                        None => self.status = Some(format!("{} is binary", entry.key)),
+                    },
+                    (KeyCode::Char('n'), _) => new_key = Some(LineEditor::default()),
+                    (KeyCode::Char('d') | KeyCode::Delete, Some(entry)) => {
+                        self.dialog = Some(Dialog::Confirm {
+                            prompt: format!("Delete {} from {}?", entry.key, target.name),
+                            action: Action::SetData {
+                                key: entry.key.clone(),
+                                target,
+                                kind,
+                                value: None,
+                            },
+                        });
+                        return Action::Continue;
+                    }
+                    _ => {}
+                }
+                self.dialog = Some(Dialog::Data {
+                    target,
+                    kind,
+                    entries,
+                    selected,
+                    new_key,
+                });
+            }
+            (
+                Dialog::Metadata {
+                    targets,
+                    entries,
+                    selected,
+                    input: Some(mut input),
+                },
+                code,
+            ) => {
+                if code == KeyCode::Enter {
+                    let text = input.editor.text();
+                    let entry = match &input.key {
+                        Some(key) => Some((key.as_str(), text)),
+                        None => parse_entry(text),
+                    };
+                    match entry {
+                        Some((key, value)) => {
+                            return Action::SetMetadata {
+                                section: input.section,
+                                key: key.to_string(),
+                                value: Some(value.to_string()),
+                                targets,
                             }
                         }
-                        Editing::Resource => {
-                            self.active_tab_mut().resource.pop();
+                        None => self.status = Some("Expected key=value".into()),
+                    }
+                } else {
+                    input.editor.handle_key(key);
+                }
+                self.dialog = Some(Dialog::Metadata {
+                    targets,
+                    entries,
+                    selected,
+                    input: Some(input),
+                });
+            }
+            (
+                Dialog::Metadata {
+                    targets,
+                    entries,
+                    selected,
+                    input: None,
+                },
+                code,
+            ) => {
+                let mut selected = selected;
+                let mut input = None;
+                match (code, entries.get(selected)) {
+                    (KeyCode::Char('q'), _) => return Action::Continue,
+                    (KeyCode::Up | KeyCode::Char('k'), _) => selected = selected.saturating_sub(1),
+                    (KeyCode::Down | KeyCode::Char('j'), _) => {
+                        selected = selected
+                            .saturating_add(1)
+                            .min(entries.len().saturating_sub(1))
+                    }
+                    (KeyCode::Enter | KeyCode::Char('e'), Some(entry)) => {
+                        input = Some(MetadataInput {
+                            section: entry.section,
+                            key: Some(entry.key.clone()),
+                            editor: LineEditor::new(entry.common().unwrap_or_default(), &[]),
+                        })
+                    }
+                    (KeyCode::Char(c @ ('l' | 'a')), _) => {
+                        input = Some(MetadataInput {
+                            section: if c == 'l' {
+                                Section::Labels
+                            } else {
+                                Section::Annotations
+                            },
+                            key: None,
+                            editor: LineEditor::new("", &[]),
+                        })
+                    }
+                    (KeyCode::Char('d') | KeyCode::Delete, Some(entry)) => {
+                        self.dialog = Some(Dialog::Confirm {
+                            prompt: format!(
+                                "Remove {} from {}?",
+                                entry.key,
+                                describe_targets(&targets)
+                            ),
+                            action: Action::SetMetadata {
+                                section: entry.section,
+                                key: entry.key.clone(),
+                                value: None,
+                                targets,
+                            },
+                        });
+                        return Action::Continue;
+                    }
+                    _ => {}
+                }
+                self.dialog = Some(Dialog::Metadata {
+                    targets,
+                    entries,
+                    selected,
+                    input,
+                });
+            }
+            (
+                Dialog::Rollout {
+                    target,
+                    rollout,
+                    fetched,
+                    selected,
+                    diff,
+                },
+                code,
+            ) => {
+                let (mut selected, mut diff) = (selected, diff);
+                let last = rollout.revisions.len().saturating_sub(1);
+                match (code, &mut diff) {
+                    (KeyCode::Char('q'), _) => return Action::Continue,
+                    (KeyCode::Up | KeyCode::Char('k'), None) => {
+                        selected = selected.saturating_sub(1)
+                    }
+                    (KeyCode::Down | KeyCode::Char('j'), None) => {
+                        selected = selected.saturating_add(1).min(last)
+                    }
+                    (
+                        code @ (KeyCode::Up | KeyCode::Down | KeyCode::Char('k' | 'j')),
+                        Some(scroll),
+                    ) => *scroll = scrolled(*scroll, code, rollout.diff(selected).len()),
+                    (KeyCode::Enter | KeyCode::Char('d'), _) => {
+                        diff = match diff {
+                            Some(_) => None,
+                            None => Some(0),
+                        }
+                    }
+                    (KeyCode::Char('r'), _) => match rollout.revisions.get(selected) {
+                        Some(_) if selected == 0 => {
+                            self.status = Some("That is the current revision".into())
+                        }
+                        Some(revision) => {
+                            self.dialog = Some(Dialog::Confirm {
+                                prompt: format!(
+                                    "Roll {} back to revision {}?",
+                                    target.name, revision.number
+                                ),
+                                action: Action::Rollback {
+                                    revision: Box::new(revision.clone()),
+                                    target,
+                                },
+                            });
+                            return Action::Continue;
                         }
+                        None => {}
                     },
-                    KeyEvent {
-                        code: KeyCode::Enter | KeyCode::Esc,
-                        ..
-                    } if self.editing.is_some() => {
-                        self.editing = None;
-                    }
-                    KeyEvent {
-                        modifiers: KeyModifiers::CONTROL,
-                        code: KeyCode::Char('c'),
-                        ..
-                    } => return Ok(Action::Quit),
                     _ => {}
                 }
+                self.dialog = Some(Dialog::Rollout {
+                    target,
+                    rollout,
+                    fetched,
+                    selected,
+                    diff,
+                });
             }
+            (
+                Dialog::Drain { .. } | Dialog::Diff { .. } | Dialog::Apply { .. },
+                KeyCode::Char('q'),
+            ) => {}
+            (
+                mut dialog @ (Dialog::Drain { .. } | Dialog::Diff { .. } | Dialog::Apply { .. }),
+                code @ (KeyCode::Up | KeyCode::Down | KeyCode::Char('k' | 'j')),
+            ) => {
+                let lines = dialog.scroll_lines();
+                if let Dialog::Drain { scroll, .. }
+                | Dialog::Diff { scroll, .. }
+                | Dialog::Apply { scroll, .. } = &mut dialog
+                {
+                    *scroll = scrolled(*scroll, code, lines);
+                }
+                self.dialog = Some(dialog);
+            }
+            (dialog, _) => self.dialog = Some(dialog),
         }
 
-        Ok(Action::Continue)
+        Action::Continue
+    }
+
+    /// Open a new tab at the jump's destination.
+    pub(crate) fn jump(&mut self, jump: &Jump) {
+        let tab = jump.tab(self.active_tab());
+        self.open_tab(tab);
+    }
+
+    /// Open a tab from the active tab, which Esc returns to.
+    pub(crate) fn open_tab(&mut self, tab: Tab) {
+        let tab = Tab {
+            opened_from: Some(self.active_tab().id),
+            ..tab
+        };
+        self.tabs.push(tab);
+        self.active_tab_idx = self.tabs.len() - 1;
     }
 
+    /// Switch to the overview of the active tab's cluster, opening it if it isn't open.
+    fn show_overview(&mut self) {
+        let context = self.active_tab().context.clone();
+        match self
+            .tabs
+            .iter()
+            .position(|t| t.is_overview() && t.context == context)
+        {
+            Some(idx) => self.active_tab_idx = idx,
+            None => {
+                self.tabs.push(Tab::overview(context));
+                self.active_tab_idx = self.tabs.len() - 1;
+            }
+        }
+    }
+
+    /// Close the active tab and return to the tab it was opened from, if it is still open.
+    fn go_back(&mut self) {
+        let Some(from) = self.active_tab().opened_from else {
+            return;
+        };
+        if let Some(idx) = self.tabs.iter().position(|t| t.id == from) {
+            self.tabs.remove(self.active_tab_idx);
+            self.active_tab_idx = if idx > self.active_tab_idx {
+                idx - 1
+            } else {
+                idx
+            };
+        }
+    }
+
+    /// Open a new tab on the cluster of the active tab.
     fn new_tab(&mut self) {
-        self.tabs.push(Tab::default());
+        let tab = Tab {
+            context: self.active_tab().context.clone(),
+            ..Tab::default()
+        };
+        self.tabs.push(tab);
+    }
+
+    /// Open a copy of the active tab next to it.
+    fn duplicate_tab(&mut self) {
+        let tab = Tab {
+            id: NEXT_TAB_ID.fetch_add(1, Ordering::Relaxed),
+            opened_from: None,
+            ..self.active_tab().clone()
+        };
+        self.tabs.insert(self.active_tab_idx + 1, tab);
+        self.active_tab_idx += 1;
+    }
+
+    /// Swap the active tab with its neighbor to the right (or left), keeping it active.
+    fn move_tab(&mut self, right: bool) {
+        let idx = self.active_tab_idx;
+        let other = if right {
+            Some(idx + 1).filter(|&i| i < self.tabs.len())
+        } else {
+            idx.checked_sub(1)
+        };
+        if let Some(other) = other {
+            self.tabs.swap(idx, other);
+            self.active_tab_idx = other;
+        }
+    }
+
+    /// Close the active tab, unless it is the only one.
+    fn close_tab(&mut self) {
+        if self.tabs.len() > 1 {
+            self.tabs.remove(self.active_tab_idx);
+            self.active_tab_idx = self.active_tab_idx.min(self.tabs.len() - 1);
+        }
+    }
+
+    /// Bring the panes up to date with the tabs: closed tabs leave their panes, and the active tab
+    /// is shown in the focused pane, unless another pane already shows it.
+    pub(crate) fn sync_panes(&mut self) {
+        self.panes
+            .retain(|id| self.tabs.iter().any(|t| t.id == *id));
+        self.focused_pane = self.focused_pane.min(self.panes.len().saturating_sub(1));
+        let active = self.active_tab().id;
+        match self.panes.iter().position(|&id| id == active) {
+            Some(idx) => self.focused_pane = idx,
+            None if self.panes.is_empty() => self.panes.push(active),
+            None => self.panes[self.focused_pane] = active,
+        }
+    }
+
+    /// The indices of the tabs shown in the panes, in order.
+    pub(crate) fn pane_tabs(&self) -> Vec<usize> {
+        self.panes
+            .iter()
+            .filter_map(|id| self.tabs.iter().position(|t| t.id == *id))
+            .collect()
+    }
+
+    /// Open a copy of the active tab in a new pane after the focused one, and focus it.
+    /// All the panes are arranged in the direction of the latest split.
+    fn split_pane(&mut self, direction: Direction) {
+        self.sync_panes();
+        self.duplicate_tab();
+        self.split = direction;
+        self.focused_pane += 1;
+        self.panes.insert(self.focused_pane, self.active_tab().id);
+    }
+
+    /// Focus the next (or previous) pane, making its tab the active tab.
+    fn focus_pane(&mut self, next: bool) {
+        self.sync_panes();
+        let n = self.panes.len();
+        self.focused_pane = if next {
+            (self.focused_pane + 1) % n
+        } else {
+            (self.focused_pane + n - 1) % n
+        };
+        self.activate_focused_pane();
+    }
+
+    fn activate_focused_pane(&mut self) {
+        let id = self.panes[self.focused_pane];
+        if let Some(idx) = self.tabs.iter().position(|t| t.id == id) {
+            self.active_tab_idx = idx;
+        }
+    }
+
+    /// Stop showing the focused pane, unless it is the only one. Its tab stays open.
+    fn close_pane(&mut self) {
+        self.sync_panes();
+        if self.panes.len() > 1 {
+            self.panes.remove(self.focused_pane);
+            self.focused_pane = self.focused_pane.min(self.panes.len() - 1);
+            self.activate_focused_pane();
+        }
     }
 }
 
-#[allow(dead_code)]
-#[derive(Debug)]
+static NEXT_TAB_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone)]
 pub(crate) struct Tab {
-    // TODO: context as well?
+    /// A unique identifier for the tab, which stays the same as tabs are opened and closed.
+    pub(crate) id: u64,
+    /// A title set by the user, shown in the tab bar instead of the tab's resource.
+    pub(crate) title: Option<String>,
+    /// The kubeconfig context of the cluster the tab shows, or `None` for the default context.
+    pub(crate) context: Option<String>,
     pub(crate) namespace: Option<String>,
     pub(crate) resource: String,
     pub(crate) filter: String,
+    pub(crate) label_selector: Option<String>,
+    pub(crate) field_selector: Option<String>,
+    /// The id of the tab that this tab was jumped to from, which Esc returns to.
+    pub(crate) opened_from: Option<u64>,
+    pub(crate) view: TabView,
+    /// Show the table's low-priority columns, like `kubectl get -o wide`.
+    pub(crate) wide: bool,
+    pub(crate) sort: Option<SortBy>,
+    /// Overrides the configured column preference for the resource.
+    pub(crate) columns: Option<ColumnPreference>,
+    /// The uids of the expanded nodes of the tree view.
+    pub(crate) expanded: HashSet<String>,
+    /// The uids of the objects in the rows marked in the table view.
+    pub(crate) marked: HashSet<String>,
+    pub(crate) table_state: TableState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TabView {
+    Table,
+    /// Objects arranged by their owner references.
+    Tree,
+}
+
+impl Tab {
+    /// A tab showing the overview of the cluster of the context.
+    pub(crate) fn overview(context: Option<String>) -> Self {
+        Self {
+            context,
+            namespace: None,
+            resource: OVERVIEW.into(),
+            ..Self::default()
+        }
+    }
+
+    pub(crate) fn is_overview(&self) -> bool {
+        self.resource == OVERVIEW
+    }
+
+    /// The label of the tab in the tab bar.
+    pub(crate) fn label(&self) -> String {
+        match (&self.title, &self.context) {
+            (Some(title), _) => title.clone(),
+            (None, Some(context)) => format!("{context}:{}", self.resource),
+            (None, None) => self.resource.clone(),
+        }
+    }
 }
 
 impl Default for Tab {
     fn default() -> Self {
         Self {
+            id: NEXT_TAB_ID.fetch_add(1, Ordering::Relaxed),
+            title: None,
+            context: None,
             namespace: Some("default".to_string()),
             resource: "pods".to_string(),
             filter: String::default(),
+            label_selector: None,
+            field_selector: None,
+            opened_from: None,
+            view: TabView::Table,
+            wide: false,
+            sort: None,
+            columns: None,
+            expanded: HashSet::new(),
+            marked: HashSet::new(),
+            table_state: TableState::default().with_selected(0),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyModifiers;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn ui(resources: &[&str], active: usize) -> UIState {
+        let mut ui = UIState::new(Config::default());
+        ui.tabs = resources
+            .iter()
+            .map(|r| Tab {
+                resource: r.to_string(),
+                ..Tab::default()
+            })
+            .collect();
+        ui.active_tab_idx = active;
+        ui
+    }
+
+    fn resources(ui: &UIState) -> Vec<&str> {
+        ui.tabs.iter().map(|t| t.resource.as_str()).collect()
+    }
+
+    #[rstest]
+    #[case(Command::NextTab, 2, vec!["a", "b", "c"], 0)]
+    #[case(Command::PreviousTab, 0, vec!["a", "b", "c"], 2)]
+    #[case(Command::GoToTab2, 0, vec!["a", "b", "c"], 1)]
+    #[case(Command::GoToTab9, 0, vec!["a", "b", "c"], 0)]
+    #[case(Command::MoveTabRight, 0, vec!["b", "a", "c"], 1)]
+    #[case(Command::MoveTabRight, 2, vec!["a", "b", "c"], 2)]
+    #[case(Command::MoveTabLeft, 1, vec!["b", "a", "c"], 0)]
+    #[case(Command::CloseTab, 2, vec!["a", "b"], 1)]
+    #[case(Command::DuplicateTab, 1, vec!["a", "b", "b", "c"], 2)]
+    fn test_tab_commands(
+        #[case] command: Command,
+        #[case] active: usize,
+        #[case] expected: Vec<&str>,
+        #[case] expected_active: usize,
+    ) {
+        let mut ui = ui(&["a", "b", "c"], active);
+        ui.run_command(command);
+        assert_eq!(resources(&ui), expected);
+        assert_eq!(ui.active_tab_idx, expected_active);
+    }
+
+    #[rstest]
+    fn test_close_last_tab() {
+        let mut ui = ui(&["a"], 0);
+        ui.run_command(Command::CloseTab);
+        assert_eq!(resources(&ui), vec!["a"]);
+    }
+
+    #[rstest]
+    fn test_duplicate_tab_gets_new_id() {
+        let mut ui = ui(&["a"], 0);
+        ui.run_command(Command::DuplicateTab);
+        assert_ne!(ui.tabs[0].id, ui.tabs[1].id);
+    }
+
+    fn pane_resources(ui: &UIState) -> Vec<&str> {
+        ui.pane_tabs()
+            .into_iter()
+            .map(|idx| ui.tabs[idx].resource.as_str())
+            .collect()
+    }
+
+    #[rstest]
+    fn test_split_pane_shows_copy_of_tab() {
+        let mut ui = ui(&["a", "b"], 0);
+        ui.run_command(Command::SplitDown);
+        assert_eq!(resources(&ui), vec!["a", "a", "b"]);
+        assert_eq!(pane_resources(&ui), vec!["a", "a"]);
+        assert_eq!(ui.split, Direction::Vertical);
+        assert_eq!((ui.focused_pane, ui.active_tab_idx), (1, 1));
+    }
+
+    #[rstest]
+    #[case(Command::NextPane, 0)]
+    #[case(Command::PreviousPane, 0)]
+    #[case(Command::ClosePane, 0)]
+    fn test_focus_pane(#[case] command: Command, #[case] expected_pane: usize) {
+        let mut ui = ui(&["a"], 0);
+        ui.run_command(Command::SplitRight);
+        ui.run_command(command);
+        assert_eq!(ui.focused_pane, expected_pane);
+        assert_eq!(ui.active_tab().id, ui.panes[expected_pane]);
+    }
+
+    #[rstest]
+    fn test_switching_tab_replaces_focused_pane() {
+        let mut ui = ui(&["a", "b", "c"], 0);
+        ui.run_command(Command::SplitRight);
+        ui.run_command(Command::GoToTab4);
+        ui.sync_panes();
+        assert_eq!(pane_resources(&ui), vec!["a", "c"]);
+
+        // A tab already shown in a pane is focused there instead.
+        ui.run_command(Command::GoToTab1);
+        ui.sync_panes();
+        assert_eq!(pane_resources(&ui), vec!["a", "c"]);
+        assert_eq!(ui.focused_pane, 0);
+    }
+
+    #[rstest]
+    fn test_closing_tab_closes_its_pane() {
+        let mut ui = ui(&["a", "b"], 1);
+        ui.run_command(Command::SplitRight);
+        ui.run_command(Command::CloseTab);
+        ui.sync_panes();
+        assert_eq!(pane_resources(&ui), vec!["b"]);
+    }
+
+    #[rstest]
+    #[case(Command::FinishEditing, "web", vec!["web"])]
+    #[case(Command::CancelEditing, "", vec![])]
+    fn test_finish_editing(
+        #[case] command: Command,
+        #[case] expected_filter: &str,
+        #[case] expected_history: Vec<&str>,
+    ) {
+        let mut ui = ui(&["a"], 0);
+        ui.run_command(Command::EditFilter);
+        ui.edit_text(|editor| {
+            editor.paste("web");
+            true
+        });
+        assert_eq!(ui.active_tab().filter, "web");
+        ui.run_command(command);
+        assert_eq!(ui.active_tab().filter, expected_filter);
+        assert_eq!(
+            ui.history
+                .get(&Editing::Filter)
+                .cloned()
+                .unwrap_or_default(),
+            expected_history
+        );
+    }
+
+    #[rstest]
+    fn test_empty_namespace_is_all_namespaces() {
+        let mut ui = ui(&["a"], 0);
+        ui.run_command(Command::EditNamespace);
+        ui.edit_text(|editor| {
+            editor.handle_key(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL))
+        });
+        assert_eq!(ui.active_tab().namespace, None);
+        ui.run_command(Command::CancelEditing);
+        assert_eq!(ui.active_tab().namespace.as_deref(), Some("default"));
+    }
+
+    #[rstest]
+    fn test_lands_on_overview() {
+        let ui = UIState::new(Config::default());
+        assert_eq!(resources(&ui), vec![OVERVIEW, "pods"]);
+        assert_eq!(ui.mode(), Mode::Overview);
+    }
+
+    #[rstest]
+    fn test_show_overview_reuses_tab() {
+        let mut ui = ui(&["a", "b"], 1);
+        ui.run_command(Command::ShowOverview);
+        ui.run_command(Command::GoToTab1);
+        ui.run_command(Command::ShowOverview);
+        assert_eq!(resources(&ui), vec!["a", "b", OVERVIEW]);
+        assert_eq!(ui.active_tab_idx, 2);
+    }
+
+    fn target() -> ObjectRef {
+        ObjectRef {
+            resource: Rc::new(DiscoveredAPIResource {
+                group: String::new(),
+                version: "v1".into(),
+                api_version: "v1".into(),
+                kind: "ConfigMap".into(),
+                plural: "configmaps".into(),
+                singular: "configmap".into(),
+                short_names: None,
+                verbs: vec![],
+                namespaced: true,
+            }),
+            namespace: Some("default".into()),
+            name: "settings".into(),
+        }
+    }
+
+    fn data_dialog(entries: Vec<DataEntry>) -> Dialog {
+        Dialog::Data {
+            target: target(),
+            kind: DataKind::ConfigMap,
+            entries,
+            selected: 0,
+            new_key: None,
+        }
+    }
+
+    fn press(ui: &mut UIState, code: KeyCode) -> Action {
+        let dialog = ui.dialog.take().expect("a dialog is open");
+        ui.handle_dialog_key(dialog, KeyEvent::from(code))
+    }
+
+    #[rstest]
+    fn test_add_data_key() {
+        let mut ui = ui(&["configmaps"], 0);
+        ui.dialog = Some(data_dialog(vec![]));
+        for code in [KeyCode::Char('n'), KeyCode::Char('a'), KeyCode::Char('/')]
+            .into_iter()
+            .chain("pp.yaml".chars().map(KeyCode::Char))
+        {
+            press(&mut ui, code);
+        }
+        let Action::EditData { key, value, .. } = press(&mut ui, KeyCode::Enter) else {
+            panic!("not editing");
+        };
+        assert_eq!((key.as_str(), value), ("app.yaml", None));
+    }
+
+    #[rstest]
+    fn test_rename_tab_with_line_editing() {
+        let mut ui = ui(&["pods"], 0);
+        ui.run_command(Command::RenameTab);
+        for c in "web pods".chars() {
+            press(&mut ui, KeyCode::Char(c));
+        }
+        let dialog = ui.dialog.take().unwrap();
+        ui.handle_dialog_key(
+            dialog,
+            KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL),
+        );
+        press(&mut ui, KeyCode::Enter);
+        assert_eq!(ui.active_tab().title.as_deref(), Some("web"));
+    }
+
+    #[rstest]
+    fn test_scale_only_takes_digits() {
+        let mut ui = ui(&["deployments"], 0);
+        ui.dialog = Some(Dialog::Scale {
+            target: target(),
+            replicas: LineEditor::new("3", &[]),
+        });
+        for code in [KeyCode::Char('x'), KeyCode::Home, KeyCode::Char('1')] {
+            press(&mut ui, code);
+        }
+        let Action::Scale { replicas, .. } = press(&mut ui, KeyCode::Enter) else {
+            panic!("not scaling");
+        };
+        assert_eq!(replicas, 13);
+    }
+
+    #[rstest]
+    fn test_select_from_no_containers() {
+        let mut ui = ui(&["pods"], 0);
+        ui.dialog = Some(Dialog::SelectContainer {
+            target: target(),
+            containers: vec![],
+            selected: 0,
+        });
+        press(&mut ui, KeyCode::Down);
+        assert!(matches!(press(&mut ui, KeyCode::Enter), Action::Continue));
+    }
+
+    #[rstest]
+    fn test_paste_into_data_key() {
+        let mut ui = ui(&["configmaps"], 0);
+        ui.dialog = Some(data_dialog(vec![]));
+        press(&mut ui, KeyCode::Char('n'));
+        ui.paste_into_dialog("app.yaml\n");
+        let Action::EditData { key, .. } = press(&mut ui, KeyCode::Enter) else {
+            panic!("not editing");
+        };
+        assert_eq!(key, "app.yaml");
+    }
+
+    #[rstest]
+    fn test_edit_and_delete_data_key() {
+        let mut ui = ui(&["configmaps"], 0);
+        ui.dialog = Some(data_dialog(vec![DataEntry::new("k".into(), b"v".to_vec())]));
+        let Action::EditData { key, value, .. } = press(&mut ui, KeyCode::Char('e')) else {
+            panic!("not editing");
+        };
+        assert_eq!((key.as_str(), value.as_deref()), ("k", Some("v")));
+
+        ui.dialog = Some(data_dialog(vec![DataEntry::new("k".into(), b"v".to_vec())]));
+        press(&mut ui, KeyCode::Char('d'));
+        assert!(matches!(
+            ui.dialog,
+            Some(Dialog::Confirm {
+                action: Action::SetData { value: None, .. },
+                ..
+            })
+        ));
+    }
+
+    #[rstest]
+    fn test_scrolling_stops_at_the_last_line() {
+        let mut ui = ui(&["pods"], 0);
+        let apply = |object: &str| ObjectApply {
+            object: object.into(),
+            outcome: Err("nope".into()),
+        };
+        ui.dialog = Some(Dialog::Apply {
+            path: PathBuf::from("app.yaml"),
+            applies: vec![apply("a"), apply("b")],
+            scroll: 0,
+        });
+        for _ in 0..3 {
+            press(&mut ui, KeyCode::Char('j'));
+        }
+        assert!(matches!(ui.dialog, Some(Dialog::Apply { scroll: 1, .. })));
+        press(&mut ui, KeyCode::Char('k'));
+        assert!(matches!(ui.dialog, Some(Dialog::Apply { scroll: 0, .. })));
+    }
+
+    #[rstest]
+    fn test_marks() {
+        let mut ui = ui(&["pods"], 0);
+        assert!(matches!(
+            ui.run_command(Command::ToggleMark),
+            Action::ToggleMark
+        ));
+        ui.active_tab_mut().marked.insert("uid".into());
+        ui.run_command(Command::ClearMarks);
+        assert!(ui.active_tab().marked.is_empty());
+
+        ui.run_command(Command::ToggleTree);
+        assert!(matches!(
+            ui.run_command(Command::ToggleMark),
+            Action::Continue
+        ));
+    }
+}
@@ -1,5 +1,10 @@
-use std::fmt::{Display, Error, Formatter};
+use std::{
+    cmp::Ordering,
+    fmt::{Display, Error, Formatter},
+};
 
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+use kube::api::DynamicObject;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -9,6 +14,64 @@ pub struct ResourceTable {
     pub rows: Vec<ResourceRow>,
 }
 
+impl ResourceTable {
+    /// Sort the rows by a column, comparing dates by the objects' creation timestamps.
+    pub fn sort_by(&mut self, sort: &SortBy) {
+        let Some(idx) = self
+            .column_definitions
+            .iter()
+            .position(|c| c.name.eq_ignore_ascii_case(&sort.column))
+        else {
+            return;
+        };
+        let is_date = self.column_definitions[idx].format == "date";
+        self.rows.sort_by(|a, b| {
+            let ordering = if is_date {
+                // Ages ascend as creation timestamps descend.
+                b.created().cmp(&a.created())
+            } else {
+                match (a.cells.get(idx), b.cells.get(idx)) {
+                    (Some(a), Some(b)) => a.compare(b),
+                    (a, b) => a.is_some().cmp(&b.is_some()),
+                }
+            };
+            if sort.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+
+    /// Add a column computed from each row, before the first date column (usually Age)
+    /// so that it reads like the server's columns, or at the end if there isn't one.
+    pub fn insert_column(
+        &mut self,
+        column: ColumnDefinition,
+        mut cell: impl FnMut(&ResourceRow) -> ResourceRowCellValue,
+    ) {
+        let idx = self
+            .column_definitions
+            .iter()
+            .position(|c| c.format == "date")
+            .unwrap_or(self.column_definitions.len());
+        self.column_definitions.insert(idx, column);
+        for row in &mut self.rows {
+            let value = cell(row);
+            row.cells.insert(idx.min(row.cells.len()), value);
+        }
+    }
+}
+
+/// The column to sort a table by.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct SortBy {
+    pub column: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub descending: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ColumnDefinition {
@@ -16,7 +79,21 @@ pub struct ColumnDefinition {
     r#type: String,
     description: String,
     format: String,
-    priority: u8, // TODO: respect priority
+    /// Columns with a priority above 0 are only shown in wide mode, like `kubectl get -o wide`.
+    pub priority: u8,
+}
+
+impl ColumnDefinition {
+    /// A column of numbers computed by flotilla rather than the server.
+    pub fn computed(name: &str, description: &str, priority: u8) -> Self {
+        Self {
+            name: name.to_string(),
+            r#type: "number".to_string(),
+            description: description.to_string(),
+            format: String::new(),
+            priority,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -24,6 +101,31 @@ pub struct ColumnDefinition {
 pub enum ResourceRowCellValue {
     String(String),
     Number(f64),
+    /// A number computed by flotilla, like CPU usage, which sorts by its value but is displayed
+    /// with units.
+    #[serde(skip)]
+    Measured {
+        value: f64,
+        display: String,
+    },
+}
+
+impl ResourceRowCellValue {
+    fn compare(&self, other: &Self) -> Ordering {
+        match (self.value(), other.value(), self, other) {
+            (Some(a), Some(b), ..) => a.total_cmp(&b),
+            (.., Self::String(a), Self::String(b)) => a.cmp(b),
+            (Some(_), None, ..) => Ordering::Less,
+            (None, ..) => Ordering::Greater,
+        }
+    }
+
+    fn value(&self) -> Option<f64> {
+        match self {
+            Self::String(_) => None,
+            Self::Number(value) | Self::Measured { value, .. } => Some(*value),
+        }
+    }
 }
 
 impl Display for ResourceRowCellValue {
@@ -34,6 +136,7 @@ impl Display for ResourceRowCellValue {
             match self {
                 ResourceRowCellValue::String(s) => s.clone(),
                 ResourceRowCellValue::Number(n) => n.to_string(),
+                ResourceRowCellValue::Measured { display, .. } => display.clone(),
             }
         )
     }
@@ -43,6 +146,28 @@ impl Display for ResourceRowCellValue {
 #[serde(rename_all = "camelCase")]
 pub struct ResourceRow {
     pub cells: Vec<ResourceRowCellValue>,
+    /// The object the row describes, as requested by `includeObject`
+    /// (by default, only its metadata).
+    #[serde(default)]
+    pub object: Option<DynamicObject>,
+}
+
+impl ResourceRow {
+    pub fn name(&self) -> Option<&str> {
+        self.object.as_ref()?.metadata.name.as_deref()
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        self.object.as_ref()?.metadata.namespace.as_deref()
+    }
+
+    pub fn uid(&self) -> Option<&str> {
+        self.object.as_ref()?.metadata.uid.as_deref()
+    }
+
+    fn created(&self) -> Option<&Time> {
+        self.object.as_ref()?.metadata.creation_timestamp.as_ref()
+    }
 }
 
 #[cfg(test)]
@@ -62,4 +187,59 @@ mod tests {
     ) {
         assert_eq!(value.to_string(), expected);
     }
+
+    #[rstest]
+    #[case(r#"{"cells": ["foo"]}"#, None, None)]
+    #[case(
+        r#"{"cells": ["foo"], "object": {"apiVersion": "meta.k8s.io/v1", "kind": "PartialObjectMetadata", "metadata": {"name": "foo", "namespace": "bar"}}}"#,
+        Some("foo"),
+        Some("bar"),
+    )]
+    #[case(
+        r#"{"cells": ["foo"], "object": {"apiVersion": "meta.k8s.io/v1", "kind": "PartialObjectMetadata", "metadata": {"name": "foo"}}}"#,
+        Some("foo"),
+        None
+    )]
+    fn test_resource_row_object_metadata(
+        #[case] json: &str,
+        #[case] name: Option<&str>,
+        #[case] namespace: Option<&str>,
+    ) {
+        let row: ResourceRow = serde_json::from_str(json).unwrap();
+        assert_eq!(row.name(), name);
+        assert_eq!(row.namespace(), namespace);
+    }
+
+    fn table() -> ResourceTable {
+        serde_json::from_value(serde_json::json!({
+            "columnDefinitions": [
+                {"name": "Name", "type": "string", "description": "", "format": "name", "priority": 0},
+                {"name": "Restarts", "type": "integer", "description": "", "format": "", "priority": 0},
+                {"name": "Age", "type": "string", "description": "", "format": "date", "priority": 0},
+            ],
+            "rows": [
+                {"cells": ["b", 3, "5m"], "object": {"metadata": {"name": "b", "creationTimestamp": "2025-01-01T00:05:00Z"}}},
+                {"cells": ["a", 10, "10m"], "object": {"metadata": {"name": "a", "creationTimestamp": "2025-01-01T00:00:00Z"}}},
+                {"cells": ["c", 0, "1m"], "object": {"metadata": {"name": "c", "creationTimestamp": "2025-01-01T00:09:00Z"}}},
+            ],
+        }))
+        .unwrap()
+    }
+
+    #[rstest]
+    #[case("Name", false, vec!["a", "b", "c"])]
+    #[case("name", true, vec!["c", "b", "a"])]
+    #[case("Restarts", false, vec!["c", "b", "a"])]
+    #[case("Age", false, vec!["c", "b", "a"])]
+    #[case("Age", true, vec!["a", "b", "c"])]
+    #[case("Nope", false, vec!["b", "a", "c"])]
+    fn test_sort_by(#[case] column: &str, #[case] descending: bool, #[case] expected: Vec<&str>) {
+        let mut table = table();
+        table.sort_by(&SortBy {
+            column: column.into(),
+            descending,
+        });
+        let names: Vec<&str> = table.rows.iter().filter_map(ResourceRow::name).collect();
+        assert_eq!(names, expected);
+    }
 }
@@ -0,0 +1,366 @@
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use futures::future::join_all;
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kube::{api::ListParams, Client};
+
+use crate::{
+    discovery::{DiscoveredAPIResource, Discovery},
+    state::{ObjectRef, Tab},
+    DynResult,
+};
+
+/// Resources that are commonly owned by other objects,
+/// which are listed to find the descendants of the objects in a tree.
+///
+/// Objects can only be found as descendants if their resource is listed, so besides these,
+/// only the other resources of the root's own group are listed, which covers custom resources
+/// owned by those of the same operator. Objects of other resources never appear in a tree.
+pub const OWNED_RESOURCES: &[&str] = &[
+    "deployments",
+    "replicasets",
+    "statefulsets",
+    "daemonsets",
+    "controllerrevisions",
+    "cronjobs",
+    "jobs",
+    "pods",
+    "services",
+    "endpointslices",
+    "configmaps",
+    "secrets",
+    "persistentvolumeclaims",
+];
+
+#[derive(Debug)]
+pub(crate) struct OwnerNode {
+    pub(crate) resource: Rc<DiscoveredAPIResource>,
+    pub(crate) metadata: ObjectMeta,
+    children: Vec<usize>,
+}
+
+impl OwnerNode {
+    pub(crate) fn uid(&self) -> &str {
+        self.metadata.uid.as_deref().unwrap_or_default()
+    }
+
+    pub(crate) fn object_ref(&self) -> ObjectRef {
+        ObjectRef {
+            resource: self.resource.clone(),
+            namespace: self.metadata.namespace.clone(),
+            name: self.metadata.name.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// A forest of objects, linked from owners to the objects they own through `metadata.ownerReferences`.
+#[derive(Debug, Default)]
+pub(crate) struct OwnerTree {
+    pub(crate) nodes: Vec<OwnerNode>,
+    roots: Vec<usize>,
+}
+
+/// A node of an [`OwnerTree`] as it appears when the tree is flattened for display.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct TreeRow {
+    pub(crate) node: usize,
+    /// The guide lines that connect the node to its ancestors.
+    pub(crate) prefix: String,
+    pub(crate) has_children: bool,
+    pub(crate) expanded: bool,
+}
+
+impl OwnerTree {
+    /// Build the tree of the objects in `lists`, rooted at the objects of `root`.
+    pub(crate) fn build(
+        lists: Vec<(Rc<DiscoveredAPIResource>, Vec<ObjectMeta>)>,
+        root: &DiscoveredAPIResource,
+    ) -> Self {
+        let mut nodes: Vec<OwnerNode> = lists
+            .into_iter()
+            .flat_map(|(resource, metas)| {
+                metas.into_iter().map(move |metadata| OwnerNode {
+                    resource: resource.clone(),
+                    metadata,
+                    children: vec![],
+                })
+            })
+            .collect();
+        nodes.sort_by(|a, b| {
+            (&a.resource.kind, &a.metadata.namespace, &a.metadata.name).cmp(&(
+                &b.resource.kind,
+                &b.metadata.namespace,
+                &b.metadata.name,
+            ))
+        });
+
+        let uid_to_idx: HashMap<String, usize> = nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| !n.uid().is_empty())
+            .map(|(idx, n)| (n.uid().to_string(), idx))
+            .collect();
+
+        for idx in 0..nodes.len() {
+            let owners: Vec<usize> = nodes[idx]
+                .metadata
+                .owner_references
+                .iter()
+                .flatten()
+                .filter_map(|o| uid_to_idx.get(&o.uid).copied())
+                .collect();
+            for owner in owners {
+                if !nodes[owner].children.contains(&idx) {
+                    nodes[owner].children.push(idx);
+                }
+            }
+        }
+
+        let roots = nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| *n.resource == *root)
+            .map(|(idx, _)| idx)
+            .collect();
+
+        Self { nodes, roots }
+    }
+
+    /// The rows of the tree that are visible when only the nodes with the given uids are expanded,
+    /// under the roots whose names contain `filter`.
+    pub(crate) fn visible(&self, expanded: &HashSet<String>, filter: &str) -> Vec<TreeRow> {
+        let mut rows = vec![];
+        let mut ancestors = vec![];
+        for &root in &self.roots {
+            let name = self.nodes[root]
+                .metadata
+                .name
+                .as_deref()
+                .unwrap_or_default();
+            if !name.contains(filter) {
+                continue;
+            }
+            self.push_visible(root, "", "", expanded, &mut ancestors, &mut rows);
+        }
+        rows
+    }
+
+    fn push_visible(
+        &self,
+        idx: usize,
+        prefix: &str,
+        child_prefix: &str,
+        expanded: &HashSet<String>,
+        ancestors: &mut Vec<usize>,
+        rows: &mut Vec<TreeRow>,
+    ) {
+        let node = &self.nodes[idx];
+        let is_expanded = expanded.contains(node.uid());
+        rows.push(TreeRow {
+            node: idx,
+            prefix: prefix.to_string(),
+            has_children: !node.children.is_empty(),
+            expanded: is_expanded,
+        });
+
+        // Guard against ownership cycles, which the API server does not prevent.
+        if !is_expanded || ancestors.contains(&idx) {
+            return;
+        }
+
+        ancestors.push(idx);
+        for (i, &child) in node.children.iter().enumerate() {
+            let last = i == node.children.len() - 1;
+            self.push_visible(
+                child,
+                &format!("{child_prefix}{}", if last { "└─ " } else { "├─ " }),
+                &format!("{child_prefix}{}", if last { "   " } else { "│  " }),
+                expanded,
+                ancestors,
+                rows,
+            );
+        }
+        ancestors.pop();
+    }
+}
+
+/// What an owner tree shows: the objects of a resource in a namespace (or all namespaces)
+/// that match the selectors, and their descendants.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct TreeQuery {
+    pub(crate) root: DiscoveredAPIResource,
+    pub(crate) namespace: Option<String>,
+    pub(crate) label_selector: Option<String>,
+    pub(crate) field_selector: Option<String>,
+}
+
+impl TreeQuery {
+    pub(crate) fn of(tab: &Tab, root: &DiscoveredAPIResource) -> Self {
+        Self {
+            root: root.clone(),
+            namespace: tab.namespace.clone(),
+            label_selector: tab.label_selector.clone(),
+            field_selector: tab.field_selector.clone(),
+        }
+    }
+}
+
+/// List the metadata of the objects of `root` that match the query, and of the
+/// [`OWNED_RESOURCES`] and the other resources of the root's group, concurrently.
+pub(crate) async fn list_tree_resources(
+    client: Client,
+    discovery: &Discovery,
+    root: &Rc<DiscoveredAPIResource>,
+    query: &TreeQuery,
+) -> DynResult<Vec<(Rc<DiscoveredAPIResource>, Vec<ObjectMeta>)>> {
+    let mut resources = vec![root.clone()];
+    let same_group = discovery
+        .name_to_resource
+        .values()
+        .filter(|r| !root.group.is_empty() && r.group == root.group);
+    for r in OWNED_RESOURCES
+        .iter()
+        .filter_map(|name| discovery.get(name))
+        .chain(same_group)
+    {
+        if !resources.contains(r) {
+            resources.push(r.clone());
+        }
+    }
+
+    let mut root_params = ListParams::default();
+    if let Some(selector) = query.label_selector.as_deref().filter(|s| !s.is_empty()) {
+        root_params = root_params.labels(selector);
+    }
+    if let Some(selector) = query.field_selector.as_deref().filter(|s| !s.is_empty()) {
+        root_params = root_params.fields(selector);
+    }
+    let lists = join_all(resources.iter().map(|r| {
+        let api = r.api(client.clone(), query.namespace.as_deref());
+        let params = if r == root {
+            root_params.clone()
+        } else {
+            ListParams::default()
+        };
+        async move { api.list_metadata(&params).await }
+    }))
+    .await;
+
+    let mut metas = vec![];
+    for (resource, list) in resources.into_iter().zip(lists) {
+        let items = match list {
+            Ok(list) => list.items.into_iter().map(|i| i.metadata).collect(),
+            Err(e) if resource == *root => return Err(e.into()),
+            // We may not be allowed to list some of the owned resources; skip them.
+            Err(_) => vec![],
+        };
+        metas.push((resource, items));
+    }
+    Ok(metas)
+}
+
+#[cfg(test)]
+mod tests {
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference;
+    use rstest::rstest;
+
+    use super::*;
+
+    fn resource(kind: &str) -> Rc<DiscoveredAPIResource> {
+        Rc::new(DiscoveredAPIResource {
+            group: "apps".into(),
+            version: "v1".into(),
+            api_version: "apps/v1".into(),
+            kind: kind.into(),
+            plural: format!("{}s", kind.to_lowercase()),
+            singular: kind.to_lowercase(),
+            short_names: None,
+            verbs: vec!["list".into()],
+            namespaced: true,
+        })
+    }
+
+    fn meta(name: &str, owners: &[&str]) -> ObjectMeta {
+        ObjectMeta {
+            name: Some(name.into()),
+            uid: Some(name.into()),
+            owner_references: Some(
+                owners
+                    .iter()
+                    .map(|o| OwnerReference {
+                        uid: o.to_string(),
+                        ..OwnerReference::default()
+                    })
+                    .collect(),
+            ),
+            ..ObjectMeta::default()
+        }
+    }
+
+    fn tree() -> OwnerTree {
+        let deployment = resource("Deployment");
+        OwnerTree::build(
+            vec![
+                (deployment.clone(), vec![meta("d", &[])]),
+                (
+                    resource("ReplicaSet"),
+                    vec![meta("rs1", &["d"]), meta("rs2", &["d"])],
+                ),
+                (
+                    resource("Pod"),
+                    vec![meta("p1", &["rs1"]), meta("p2", &["rs2"]), meta("p3", &[])],
+                ),
+            ],
+            &deployment,
+        )
+    }
+
+    #[rstest]
+    #[case(&[], vec![("", "d", false)])]
+    #[case(&["d"], vec![("", "d", true), ("├─ ", "rs1", false), ("└─ ", "rs2", false)])]
+    #[case(
+        &["d", "rs1", "rs2"],
+        vec![
+            ("", "d", true),
+            ("├─ ", "rs1", true),
+            ("│  └─ ", "p1", false),
+            ("└─ ", "rs2", true),
+            ("   └─ ", "p2", false),
+        ]
+    )]
+    #[case(&["rs1"], vec![("", "d", false)])]
+    fn test_visible(#[case] expanded: &[&str], #[case] expected: Vec<(&str, &str, bool)>) {
+        let tree = tree();
+        let expanded = expanded.iter().map(|s| s.to_string()).collect();
+        let rows = tree.visible(&expanded, "");
+        let visible: Vec<(&str, &str, bool)> = rows
+            .iter()
+            .map(|r| {
+                (
+                    r.prefix.as_str(),
+                    tree.nodes[r.node].metadata.name.as_deref().unwrap(),
+                    r.expanded,
+                )
+            })
+            .collect();
+        assert_eq!(visible, expected);
+    }
+
+    #[rstest]
+    fn test_visible_filters_roots() {
+        let deployment = resource("Deployment");
+        let tree = OwnerTree::build(
+            vec![(deployment.clone(), vec![meta("web", &[]), meta("db", &[])])],
+            &deployment,
+        );
+        let rows = tree.visible(&HashSet::new(), "we");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(
+            tree.nodes[rows[0].node].metadata.name.as_deref(),
+            Some("web")
+        );
+    }
+}
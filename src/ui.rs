@@ -1,6 +1,36 @@
-use ratatui::layout::{Constraint, Constraint::Length};
+use std::{collections::HashSet, ops::Range};
+
+use itertools::Itertools;
+use k8s_openapi::chrono::{TimeDelta, Utc};
+use ratatui::{
+    layout::{
+        Constraint,
+        Constraint::{Length, Min},
+        Flex, Layout, Rect,
+    },
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph, Row, Table, TableState},
+    Frame,
+};
 use unicode_width::UnicodeWidthStr;
 
+use crate::{
+    config::Theme,
+    diff::DiffLine,
+    events::EventSummary,
+    health::Health,
+    input::LineEditor,
+    manifest::{ApplyOutcome, ObjectApply, ObjectDiff},
+    metadata::Section,
+    nodes::EvictionStatus,
+    overview::OverviewItem,
+    portforward::PortForwards,
+    rollout::RolloutState,
+    state::{self, Dialog, MetadataInput},
+    tree::OwnerTree,
+};
+
 pub fn table_column_constraints<S: AsRef<str>>(
     header_strings: &[S],
     row_strings: &[Vec<S>],
@@ -25,6 +55,671 @@ pub fn table_column_constraints<S: AsRef<str>>(
         .collect()
 }
 
+/// A `width` by `height` area centered in `area`, shrunk to fit if necessary.
+pub fn popup_area(area: Rect, width: u16, height: u16) -> Rect {
+    let [area] = Layout::vertical([Length(height)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::horizontal([Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
+
+/// How many days before a certificate expires to start warning about it.
+const SOON: i64 = 30;
+
+/// How many characters of a label's or annotation's value to show.
+const MAX_VALUE_WIDTH: usize = 60;
+
+/// How many completions to show below the command palette's input.
+const PALETTE_CANDIDATES: usize = 10;
+
+const REPLICAS_PROMPT: &str = "Replicas: ";
+const LOCAL_PORT_PROMPT: &str = "Local port: ";
+const REMOTE_PORT_PROMPT: &str = "Remote port: ";
+const TITLE_PROMPT: &str = "Title: ";
+const PALETTE_PROMPT: &str = ":";
+const NEW_KEY_PROMPT: &str = "New key: ";
+
+pub(crate) fn render_dialog(frame: &mut Frame, dialog: &Dialog, theme: &Theme) {
+    let (title, lines): (String, Vec<Line>) = match dialog {
+        Dialog::Scale { target, replicas } => (
+            format!("Scale {} {}", target.resource.kind, target.name),
+            vec![format!("{REPLICAS_PROMPT}{}", replicas.text()).into()],
+        ),
+        Dialog::Confirm { prompt, .. } => (
+            "Confirm".to_string(),
+            vec![format!("{prompt} [y/n]").into()],
+        ),
+        Dialog::SelectContainer {
+            target,
+            containers,
+            selected,
+        } => (
+            format!("Exec into {}", target.name),
+            containers
+                .iter()
+                .enumerate()
+                .map(|(idx, c)| {
+                    if idx == *selected {
+                        Line::from(c.as_str()).reversed()
+                    } else {
+                        Line::from(c.as_str())
+                    }
+                })
+                .collect(),
+        ),
+        Dialog::Jump { jumps, selected } => (
+            "Go to".to_string(),
+            if jumps.is_empty() {
+                vec![Line::from("No related objects").dim()]
+            } else {
+                jumps
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, j)| {
+                        if idx == *selected {
+                            Line::from(j.label.as_str()).reversed()
+                        } else {
+                            Line::from(j.label.as_str())
+                        }
+                    })
+                    .collect()
+            },
+        ),
+        Dialog::PortForward {
+            target,
+            suggestions,
+            local,
+            remote,
+            editing_local,
+        } => {
+            let field = |prompt: &str, value: &LineEditor, editing: bool| {
+                let line = Line::from(format!("{prompt}{}", value.text()));
+                if editing {
+                    line.reversed()
+                } else {
+                    line
+                }
+            };
+            (
+                format!("Port-forward {}", target.name),
+                vec![
+                    field(LOCAL_PORT_PROMPT, local, *editing_local),
+                    field(REMOTE_PORT_PROMPT, remote, !*editing_local),
+                    Line::from(format!("Ports: {}", suggestions.iter().join(", "))).dim(),
+                ],
+            )
+        }
+        Dialog::RenameTab { title } => (
+            "Rename tab".to_string(),
+            vec![format!("{TITLE_PROMPT}{}", title.text()).into()],
+        ),
+        Dialog::Palette {
+            input,
+            completions,
+            selected,
+        } => (
+            "Command".to_string(),
+            [Line::from(format!("{PALETTE_PROMPT}{}", input.text()))]
+                .into_iter()
+                .chain(
+                    completions
+                        .candidates(input.text())
+                        .into_iter()
+                        .enumerate()
+                        .take(PALETTE_CANDIDATES)
+                        .map(|(idx, c)| {
+                            if idx == *selected {
+                                Line::from(c).reversed()
+                            } else {
+                                Line::from(c).dim()
+                            }
+                        }),
+                )
+                .collect(),
+        ),
+        Dialog::Help {
+            mode,
+            bindings,
+            scroll,
+        } => {
+            let chords: Vec<String> = bindings
+                .iter()
+                .map(|(_, chords)| chords.iter().join(", "))
+                .collect();
+            let chords_width = chords.iter().map(|c| c.width()).max().unwrap_or_default();
+            (
+                format!("Keys: {}", mode.name()),
+                bindings
+                    .iter()
+                    .zip(chords)
+                    .skip(*scroll)
+                    .map(|((command, _), chords)| {
+                        Line::from(vec![
+                            Span::from(format!("{chords:chords_width$}  ")).bold(),
+                            Span::from(command.description()),
+                        ])
+                    })
+                    .collect(),
+            )
+        }
+        Dialog::Data {
+            target,
+            entries,
+            selected,
+            new_key,
+            ..
+        } => {
+            let now = Utc::now();
+            let mut lines: Vec<Line> = vec![];
+            let mut selected_lines = 0..0;
+            for (idx, entry) in entries.iter().enumerate() {
+                let start = lines.len();
+                let expiry = match entry.expires() {
+                    Some(t) if t < now => Style::new().fg(theme.error),
+                    Some(t) if t - now < TimeDelta::days(SOON) => Style::new().fg(theme.warning),
+                    _ => Style::new().dim(),
+                };
+                let header = Line::from(vec![
+                    Span::from(entry.key.as_str()).bold(),
+                    Span::from(format!("  {}", entry.summary(now))).style(expiry),
+                ]);
+                lines.push(
+                    if idx == *selected {
+                        header.reversed()
+                    } else {
+                        header
+                    },
+                );
+                lines.extend(
+                    entry
+                        .lines()
+                        .into_iter()
+                        .map(|l| Line::from(format!("  {l}"))),
+                );
+                if idx == *selected {
+                    selected_lines = start..lines.len();
+                }
+            }
+            if entries.is_empty() {
+                lines.push(Line::from("No data").dim());
+            }
+            if let Some(key) = new_key {
+                selected_lines = lines.len()..lines.len() + 1;
+                lines.push(Line::from(format!("{NEW_KEY_PROMPT}{}", key.text())).reversed());
+            }
+            let mut lines = scrolled(lines, selected_lines, frame.area().height);
+            lines.push(
+                Line::from(
+                    "space: reveal  a: reveal all  c: copy  e: edit  n: new  d: delete  esc: close",
+                )
+                .dim(),
+            );
+            (format!("{} {}", target.resource.kind, target.name), lines)
+        }
+        Dialog::Rollout {
+            target,
+            rollout,
+            selected,
+            diff,
+            ..
+        } => {
+            let progress = &rollout.progress;
+            let style = match progress.state {
+                RolloutState::Progressing => Style::new().fg(theme.warning),
+                RolloutState::Complete => Style::new(),
+                RolloutState::Failed => Style::new().fg(theme.error),
+            };
+            let mut lines = vec![Line::from(format!(
+                "{}/{} updated, {} ready, {} available: {}",
+                progress.updated,
+                progress.desired,
+                progress.ready,
+                progress.available,
+                progress.message
+            ))
+            .style(style)];
+            let now = Utc::now();
+            for (idx, revision) in rollout.revisions.iter().enumerate() {
+                let age = revision
+                    .created
+                    .as_ref()
+                    .map(|t| format_age(now - t.0))
+                    .unwrap_or_default();
+                let line = Line::from(format!(
+                    "{:>4}  {}  {}/{}  {age}{}  {}",
+                    revision.number,
+                    revision.replica_set,
+                    revision.ready,
+                    revision.replicas,
+                    if idx == 0 { "  current" } else { "" },
+                    revision.change_cause.as_deref().unwrap_or_default(),
+                ));
+                lines.push(
+                    if idx == *selected {
+                        line.reversed()
+                    } else {
+                        line
+                    },
+                );
+            }
+            if rollout.revisions.is_empty() {
+                lines.push(Line::from("No revisions").dim());
+            }
+            if let Some(scroll) = diff {
+                let diff = rollout.diff(*selected);
+                lines.push(Line::default());
+                if diff.is_empty() {
+                    lines.push(Line::from("No changes to the pod template").dim());
+                }
+                let height = (frame.area().height as usize)
+                    .saturating_sub(lines.len() + 3)
+                    .max(1);
+                let skip = (*scroll).min(diff.len().saturating_sub(height));
+                lines.extend(
+                    diff.iter()
+                        .skip(skip)
+                        .take(height)
+                        .map(|line| diff_line(line, theme)),
+                );
+            }
+            lines.push(
+                Line::from(
+                    if diff.is_some() {
+                        "j/k: scroll  d: hide diff  r: roll back  esc: close diff"
+                    } else {
+                        "d: diff to current  r: roll back  esc: close"
+                    },
+                )
+                .dim(),
+            );
+            (format!("Rollout of {}", target.name), lines)
+        }
+        Dialog::Drain { drain, scroll } => {
+            let drain = drain.progress.lock().unwrap();
+            let mut lines: Vec<Line> =
+                drain
+                    .evictions
+                    .iter()
+                    .map(|e| {
+                        let style = match &e.status {
+                            EvictionStatus::Blocked { .. } => Style::new().fg(theme.warning),
+                            EvictionStatus::Failed(_) => Style::new().fg(theme.error),
+                            EvictionStatus::Deleted => Style::new().dim(),
+                            _ => Style::new(),
+                        };
+                        Line::from(format!("{}  {}", e.pod, e.status)).style(style)
+                    })
+                    .chain(drain.skipped.iter().map(|(pod, reason)| {
+                        Line::from(format!("{pod}  skipped ({reason})")).dim()
+                    }))
+                    .collect();
+            if lines.is_empty() {
+                lines.push(Line::from("No pods to evict").dim());
+            }
+            let height = frame.area().height.saturating_sub(4) as usize;
+            let skip = (*scroll).min(lines.len().saturating_sub(height));
+            let mut lines: Vec<Line> = lines.into_iter().skip(skip).take(height).collect();
+            lines.push(Line::from(drain.summary()).bold());
+            lines.push(
+                Line::from(
+                    if drain.finished {
+                        "j/k: scroll  esc: close"
+                    } else {
+                        "j/k: scroll  esc: cancel the drain"
+                    },
+                )
+                .dim(),
+            );
+            (format!("Drain {}", drain.nodes.join(", ")), lines)
+        }
+        Dialog::Diff {
+            path,
+            diffs,
+            scroll,
+        } => {
+            let mut lines = vec![];
+            for ObjectDiff { object, diff } in diffs {
+                match diff {
+                    Ok(diff) if diff.is_empty() => {
+                        lines.push(Line::from(format!("{object}: no changes")).dim())
+                    }
+                    Ok(diff) => {
+                        lines.push(Line::from(object.as_str()).bold());
+                        lines.extend(diff.iter().map(|line| diff_line(line, theme)));
+                    }
+                    Err(e) => lines.push(
+                        Line::from(format!("{object}: {e}")).style(Style::new().fg(theme.error)),
+                    ),
+                }
+            }
+            if lines.is_empty() {
+                lines.push(Line::from("No objects").dim());
+            }
+            let height = frame.area().height.saturating_sub(3) as usize;
+            let skip = (*scroll).min(lines.len().saturating_sub(height));
+            let mut lines: Vec<Line> = lines.into_iter().skip(skip).take(height).collect();
+            lines.push(Line::from("j/k: scroll  esc: close").dim());
+            (format!("Diff of {}", path.display()), lines)
+        }
+        Dialog::Apply {
+            path,
+            applies,
+            scroll,
+        } => {
+            let mut lines: Vec<Line> = applies
+                .iter()
+                .map(|ObjectApply { object, outcome }| match outcome {
+                    Ok(ApplyOutcome::Unchanged) => Line::from(format!("{object} unchanged")).dim(),
+                    Ok(outcome) => Line::from(format!("{object} {outcome}")),
+                    Err(e) => {
+                        Line::from(format!("{object}: {e}")).style(Style::new().fg(theme.error))
+                    }
+                })
+                .collect();
+            if lines.is_empty() {
+                lines.push(Line::from("No objects").dim());
+            }
+            let height = frame.area().height.saturating_sub(3) as usize;
+            let skip = (*scroll).min(lines.len().saturating_sub(height));
+            let mut lines: Vec<Line> = lines.into_iter().skip(skip).take(height).collect();
+            lines.push(Line::from("j/k: scroll  esc: close").dim());
+            (format!("Apply of {}", path.display()), lines)
+        }
+        Dialog::Metadata {
+            targets,
+            entries,
+            selected,
+            input,
+        } => {
+            let mut lines: Vec<Line> = vec![];
+            let mut selected_lines = 0..0;
+            for (idx, entry) in entries.iter().enumerate() {
+                if idx == 0 || entries[idx - 1].section != entry.section {
+                    lines.push(Line::from(entry.section.title()).bold());
+                }
+                let value = match entry.common() {
+                    Some(value) => Span::from(format!(" = {}", truncate(value))),
+                    None => Span::from(format!("  ({})", entry.difference())).dim(),
+                };
+                let line = Line::from(vec![Span::from(format!("  {}", entry.key)), value]);
+                if idx == *selected && input.is_none() {
+                    selected_lines = lines.len()..lines.len() + 1;
+                    lines.push(line.reversed());
+                } else {
+                    lines.push(line);
+                }
+            }
+            if entries.is_empty() {
+                lines.push(Line::from("No labels or annotations").dim());
+            }
+            if let Some(input) = input {
+                selected_lines = lines.len()..lines.len() + 1;
+                lines.push(Line::from(format!(
+                    "{}{}",
+                    metadata_input_prefix(input),
+                    input.editor.text()
+                )));
+            }
+            let mut lines = scrolled(lines, selected_lines, frame.area().height);
+            lines.push(
+                Line::from("e: edit  l: new label  a: new annotation  d: remove  esc: close").dim(),
+            );
+            (
+                format!(
+                    "Labels and annotations of {}",
+                    state::describe_targets(targets)
+                ),
+                lines,
+            )
+        }
+    };
+
+    let width = lines
+        .iter()
+        .map(Line::width)
+        .chain([title.width()])
+        .max()
+        .unwrap_or_default()
+        + 4;
+    let area = popup_area(frame.area(), width as u16, lines.len() as u16 + 2);
+    frame.render_widget(Clear, area);
+    let input_line = lines.len().saturating_sub(2) as u16;
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(title).fg(theme.focused)),
+        area,
+    );
+
+    // The line being typed on, the width of its prompt, and its editor.
+    let input = match dialog {
+        Dialog::Scale { replicas, .. } => Some((0, REPLICAS_PROMPT.width(), replicas)),
+        Dialog::RenameTab { title } => Some((0, TITLE_PROMPT.width(), title)),
+        Dialog::Palette { input, .. } => Some((0, PALETTE_PROMPT.width(), input)),
+        Dialog::PortForward {
+            local,
+            editing_local: true,
+            ..
+        } => Some((0, LOCAL_PORT_PROMPT.width(), local)),
+        Dialog::PortForward { remote, .. } => Some((1, REMOTE_PORT_PROMPT.width(), remote)),
+        // New keys and values are typed on the line above the help.
+        Dialog::Data {
+            new_key: Some(key), ..
+        } => Some((input_line, NEW_KEY_PROMPT.width(), key)),
+        Dialog::Metadata {
+            input: Some(input), ..
+        } => Some((
+            input_line,
+            metadata_input_prefix(input).width(),
+            &input.editor,
+        )),
+        _ => None,
+    };
+    if let Some((line, prompt_width, editor)) = input {
+        let typed: String = editor.text().chars().take(editor.cursor()).collect();
+        let x = area.x + 1 + (prompt_width + typed.width()) as u16;
+        frame.set_cursor_position((x.min(area.right().saturating_sub(2)), area.y + 1 + line));
+    }
+}
+
+/// A line of a diff, colored like `git diff`.
+pub(crate) fn diff_line(line: &DiffLine, theme: &Theme) -> Line<'static> {
+    let style = match line {
+        DiffLine::Hunk(_) => Style::new().fg(theme.tab_highlight),
+        DiffLine::Context(_) => Style::new(),
+        DiffLine::Added(_) => Style::new().fg(Color::Green),
+        DiffLine::Removed(_) => Style::new().fg(theme.error),
+    };
+    Line::from(line.text()).style(style)
+}
+
+/// The lines of a dialog that fit in a frame of the height, scrolled so that as much of the
+/// selected lines as fits is visible.
+fn scrolled(lines: Vec<Line>, selected: Range<usize>, height: u16) -> Vec<Line> {
+    // Leave room for the borders and a line of help.
+    let height = height.saturating_sub(3) as usize;
+    let skip = selected.end.saturating_sub(height).min(selected.start);
+    lines.into_iter().skip(skip).take(height).collect()
+}
+
+/// What precedes the text being typed for a label or annotation.
+fn metadata_input_prefix(input: &MetadataInput) -> String {
+    match &input.key {
+        Some(key) => format!("  {key} = "),
+        None => format!(
+            "New {} (key=value): ",
+            match input.section {
+                Section::Labels => "label",
+                Section::Annotations => "annotation",
+            }
+        ),
+    }
+}
+
+/// A value cut short to fit in a dialog, like a long annotation.
+fn truncate(value: &str) -> String {
+    let mut chars = value.chars();
+    let truncated: String = chars.by_ref().take(MAX_VALUE_WIDTH).collect();
+    if chars.next().is_some() {
+        format!("{truncated}…")
+    } else {
+        truncated
+    }
+}
+
+pub(crate) fn render_port_forwards(
+    frame: &mut Frame,
+    area: Rect,
+    port_forwards: &PortForwards,
+    state: &mut TableState,
+    focused: bool,
+    theme: &Theme,
+) {
+    let rows = port_forwards.forwards.iter().map(|pf| {
+        Row::new([
+            format!("localhost:{}", pf.local_port),
+            format!("{}/{}", pf.namespace, pf.source),
+            format!("{}:{}", pf.pod, pf.remote_port),
+            pf.error.lock().unwrap().clone().unwrap_or_default(),
+        ])
+    });
+    let table = Table::new(rows, [Length(16), Min(0), Min(0), Min(0)])
+        .header(Row::new(["LOCAL", "SOURCE", "POD", "ERROR"]).bold())
+        .row_highlight_style(
+            if focused {
+                Modifier::REVERSED
+            } else {
+                Modifier::empty()
+            },
+        )
+        .column_spacing(2)
+        .block(Block::bordered().title("Port-forwards").border_style(
+            if focused {
+                theme.focused
+            } else {
+                theme.unfocused
+            },
+        ));
+    frame.render_stateful_widget(table, area, state);
+}
+
+/// A short human-readable age, in the style of `kubectl get`.
+pub fn format_age(age: TimeDelta) -> String {
+    let s = age.num_seconds().max(0);
+    match s {
+        0..120 => format!("{s}s"),
+        120..600 => format!("{}m{}s", s / 60, s % 60),
+        600..10_800 => format!("{}m", s / 60),
+        10_800..172_800 => format!("{}h", s / 3600),
+        _ => format!("{}d", s / 86_400),
+    }
+}
+
+pub(crate) fn render_events(
+    frame: &mut Frame,
+    area: Rect,
+    events: &Result<Vec<EventSummary>, String>,
+    theme: &Theme,
+) {
+    let block = Block::bordered().title("Events");
+    let events = match events {
+        Ok(events) => events,
+        Err(e) => {
+            frame.render_widget(
+                Paragraph::new(e.as_str()).fg(theme.error).block(block),
+                area,
+            );
+            return;
+        }
+    };
+
+    let now = Utc::now();
+    let rows = events.iter().map(|e| {
+        Row::new([
+            e.last_seen
+                .map(|t| format_age(now - t))
+                .unwrap_or_else(|| "<unknown>".into()),
+            e.type_.clone(),
+            e.reason.clone(),
+            e.count.to_string(),
+            e.message.clone(),
+        ])
+        .fg(if e.is_warning() {
+            theme.warning
+        } else {
+            Color::default()
+        })
+    });
+    let table = Table::new(rows, [Length(9), Length(7), Length(20), Length(5), Min(0)])
+        .header(Row::new(["LAST SEEN", "TYPE", "REASON", "COUNT", "MESSAGE"]).bold())
+        .column_spacing(2)
+        .block(block);
+    frame.render_widget(table, area);
+}
+
+/// The style of a table row whose object is in the given health.
+pub(crate) fn health_style(health: Option<Health>, theme: &Theme) -> Style {
+    match health {
+        None | Some(Health::Healthy) => Style::default(),
+        Some(Health::Warning) => Style::default().fg(theme.warning),
+        Some(Health::Error) => Style::default().fg(theme.error),
+        Some(Health::Done) => Style::default().add_modifier(Modifier::DIM),
+    }
+}
+
+pub(crate) fn overview_table(items: &[OverviewItem], theme: &Theme) -> Table<'static> {
+    let header = ["Item", "Summary"];
+    let strings: Vec<Vec<String>> = items
+        .iter()
+        .map(|i| vec![i.label.clone(), i.detail.clone()])
+        .collect();
+    let constraints = table_column_constraints(&header.map(String::from), &strings);
+    let rows = strings
+        .into_iter()
+        .zip(items)
+        .map(|(cells, item)| Row::new(cells).style(health_style(item.health, theme)));
+    Table::new(rows, constraints)
+        .header(Row::new(header).bold().bg(theme.header_background))
+        .row_highlight_style(Modifier::REVERSED)
+        .column_spacing(2)
+}
+
+/// A table of the visible rows of the tree, with the guide lines drawn in the name column.
+pub(crate) fn owner_tree_table(
+    tree: &OwnerTree,
+    expanded: &HashSet<String>,
+    filter: &str,
+    theme: &Theme,
+) -> Table<'static> {
+    let rows = tree.visible(expanded, filter).into_iter().map(|row| {
+        let node = &tree.nodes[row.node];
+        let marker = match (row.has_children, row.expanded) {
+            (false, _) => "  ",
+            (true, false) => "▸ ",
+            (true, true) => "▾ ",
+        };
+        Row::new([
+            Line::from(vec![
+                Span::from(row.prefix).dim(),
+                Span::from(marker),
+                Span::from(format!("{}/", node.resource.kind)).dim(),
+                Span::from(node.metadata.name.clone().unwrap_or_default()),
+            ]),
+            Line::from(node.metadata.namespace.clone().unwrap_or_default()),
+        ])
+    });
+
+    Table::new(rows, [Min(0), Length(20)])
+        .header(
+            Row::new(["NAME", "NAMESPACE"])
+                .bold()
+                .bg(theme.header_background),
+        )
+        .row_highlight_style(Modifier::REVERSED)
+        .column_spacing(2)
+}
+
 #[cfg(test)]
 mod tests {
     use rstest::*;
@@ -62,4 +757,36 @@ mod tests {
             expected
         );
     }
+
+    #[rstest]
+    #[case(Rect::new(0, 0, 100, 50), 20, 4, Rect::new(40, 23, 20, 4))]
+    #[case(Rect::new(10, 10, 10, 10), 4, 2, Rect::new(13, 14, 4, 2))]
+    #[case(Rect::new(0, 0, 10, 10), 20, 20, Rect::new(0, 0, 10, 10))]
+    fn test_popup_area(
+        #[case] area: Rect,
+        #[case] width: u16,
+        #[case] height: u16,
+        #[case] expected: Rect,
+    ) {
+        assert_eq!(popup_area(area, width, height), expected);
+    }
+
+    #[rstest]
+    #[case(TimeDelta::seconds(-5), "0s")]
+    #[case(TimeDelta::seconds(42), "42s")]
+    #[case(TimeDelta::seconds(150), "2m30s")]
+    #[case(TimeDelta::minutes(45), "45m")]
+    #[case(TimeDelta::hours(5), "5h")]
+    #[case(TimeDelta::days(3), "3d")]
+    fn test_format_age(#[case] age: TimeDelta, #[case] expected: &str) {
+        assert_eq!(format_age(age), expected);
+    }
+
+    #[rstest]
+    #[case("short", "short")]
+    #[case(&"x".repeat(MAX_VALUE_WIDTH), &"x".repeat(MAX_VALUE_WIDTH))]
+    #[case(&"x".repeat(MAX_VALUE_WIDTH + 1), &format!("{}…", "x".repeat(MAX_VALUE_WIDTH)))]
+    fn test_truncate(#[case] value: &str, #[case] expected: &str) {
+        assert_eq!(truncate(value), expected);
+    }
 }
@@ -0,0 +1,81 @@
+use k8s_openapi::chrono::{SecondsFormat, Utc};
+use kube::{
+    api::{Patch, PatchParams},
+    Client,
+};
+use serde_json::{json, Value};
+
+use crate::{edit::FIELD_MANAGER, state::ObjectRef, DynResult};
+
+pub const RESTARTED_AT_ANNOTATION: &str = "kubectl.kubernetes.io/restartedAt";
+
+pub(crate) fn patch_params() -> PatchParams {
+    PatchParams {
+        field_manager: Some(FIELD_MANAGER.into()),
+        ..PatchParams::default()
+    }
+}
+
+pub(crate) async fn current_replicas(client: Client, target: &ObjectRef) -> DynResult<i32> {
+    let scale = target.api(client).get_scale(&target.name).await?;
+    Ok(scale.spec.and_then(|s| s.replicas).unwrap_or_default())
+}
+
+pub(crate) async fn scale(client: Client, target: &ObjectRef, replicas: i32) -> DynResult<()> {
+    target
+        .api(client)
+        .patch_scale(
+            &target.name,
+            &patch_params(),
+            &Patch::Merge(json!({"spec": {"replicas": replicas}})),
+        )
+        .await?;
+    Ok(())
+}
+
+/// Restart the pods of a workload the same way `kubectl rollout restart` does,
+/// by setting an annotation on its pod template.
+pub(crate) async fn rollout_restart(client: Client, target: &ObjectRef) -> DynResult<()> {
+    let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+    target
+        .api(client)
+        .patch(
+            &target.name,
+            &patch_params(),
+            &Patch::Merge(restart_patch(&now)),
+        )
+        .await?;
+    Ok(())
+}
+
+fn restart_patch(restarted_at: &str) -> Value {
+    json!({
+        "spec": {
+            "template": {
+                "metadata": {
+                    "annotations": {
+                        RESTARTED_AT_ANNOTATION: restarted_at,
+                    }
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::rstest;
+
+    use super::*;
+
+    #[rstest]
+    #[case("2025-01-01T00:00:00Z")]
+    #[case("2025-06-15T12:34:56Z")]
+    fn test_restart_patch(#[case] restarted_at: &str) {
+        assert_eq!(
+            restart_patch(restarted_at)["spec"]["template"]["metadata"]["annotations"]
+                [RESTARTED_AT_ANNOTATION],
+            restarted_at
+        );
+    }
+}